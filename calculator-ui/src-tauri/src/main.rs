@@ -2,10 +2,22 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use matheval::{
+    analysis::{self, AnalysisOptions, KeyPoint},
+    ast::Expr,
+    compile::CompiledExpr,
     evaluator::Evaluator,
+    implicit::{self, ImplicitOptions},
+    ieee754::{FloatLayout, Precision},
+    integer::{IntegerView, WordSize},
     lexer::Lexer,
+    ode::{self, OdeOptions, Trajectory},
+    optimize::{self, Method, OptimizeOptions, Optimum},
     parser::Parser,
-    context::AngleMode,
+    plot::{self, Curve, SampleOptions},
+    symbolic,
+    table::{self, Table},
+    context::{AngleMode, Context},
+    value::Value,
 };
 use std::sync::Mutex;
 use tauri::State;
@@ -14,57 +26,490 @@ struct AppState {
     evaluator: Mutex<Evaluator>,
 }
 
+/// `result` is set for plain numbers; `display` always carries the formatted
+/// value, using ISO 8601 for dates, timestamps and durations.
 #[derive(serde::Serialize)]
 struct EvalResult {
     success: bool,
     result: Option<f64>,
+    display: Option<String>,
     error: Option<String>,
 }
 
+impl EvalResult {
+    fn error(message: String) -> Self {
+        EvalResult {
+            success: false,
+            result: None,
+            display: None,
+            error: Some(message),
+        }
+    }
+}
+
+/// Result for the programming tab: the integer in every base at once.
+#[derive(serde::Serialize)]
+struct ProgrammerResult {
+    success: bool,
+    view: Option<IntegerView>,
+    error: Option<String>,
+}
+
+impl ProgrammerResult {
+    fn error(message: String) -> Self {
+        ProgrammerResult {
+            success: false,
+            view: None,
+            error: Some(message),
+        }
+    }
+}
+
+/// How a number is stored in single and double precision, for the programming tab.
+#[derive(serde::Serialize)]
+struct InspectResult {
+    success: bool,
+    single: Option<FloatLayout>,
+    double: Option<FloatLayout>,
+    error: Option<String>,
+}
+
+/// A sampled curve for the graphs tab.
+#[derive(serde::Serialize)]
+struct PlotResult {
+    success: bool,
+    curve: Option<Curve>,
+    error: Option<String>,
+}
+
+impl From<Result<Curve, String>> for PlotResult {
+    fn from(curve: Result<Curve, String>) -> Self {
+        match curve {
+            Ok(curve) => PlotResult {
+                success: true,
+                curve: Some(curve),
+                error: None,
+            },
+            Err(e) => PlotResult {
+                success: false,
+                curve: None,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+/// A table of values for the graphs tab's table view.
+#[derive(serde::Serialize)]
+struct TableResult {
+    success: bool,
+    table: Option<Table>,
+    error: Option<String>,
+}
+
+/// Labelled points for the graphs tab to mark on a plot.
+#[derive(serde::Serialize)]
+struct AnalysisResult {
+    success: bool,
+    points: Option<Vec<KeyPoint>>,
+    error: Option<String>,
+}
+
+impl From<Result<Vec<KeyPoint>, String>> for AnalysisResult {
+    fn from(points: Result<Vec<KeyPoint>, String>) -> Self {
+        match points {
+            Ok(points) => AnalysisResult {
+                success: true,
+                points: Some(points),
+                error: None,
+            },
+            Err(e) => AnalysisResult {
+                success: false,
+                points: None,
+                error: Some(e),
+            },
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct OptimizeResult {
+    success: bool,
+    optimum: Option<Optimum>,
+    error: Option<String>,
+}
+
+#[derive(serde::Serialize)]
+struct OdeResult {
+    success: bool,
+    trajectory: Option<Trajectory>,
+    error: Option<String>,
+}
+
+/// `polynomial` is the Taylor polynomial as it would be typed in.
+#[derive(serde::Serialize)]
+struct ExpansionResult {
+    success: bool,
+    polynomial: Option<String>,
+    error: Option<String>,
+}
+
+fn display_value(context: &Context, value: &Value) -> String {
+    value.to_iso8601().unwrap_or_else(|| context.format_value(value))
+}
+
+fn parse_expression(expression: &str) -> Result<Expr, String> {
+    let mut lexer = Lexer::new(expression);
+    let tokens = lexer.tokenize().map_err(|e| format!("Lexing error: {}", e))?;
+
+    let mut parser = Parser::new(tokens);
+    parser.parse().map_err(|e| format!("Parsing error: {}", e))
+}
+
+fn parse_equation(equation: &str) -> Result<Expr, String> {
+    let mut lexer = Lexer::new(equation);
+    let tokens = lexer.tokenize().map_err(|e| format!("Lexing error: {}", e))?;
+
+    let mut parser = Parser::new(tokens);
+    parser.parse_equation().map_err(|e| format!("Parsing error: {}", e))
+}
+
+/// Parses `expression` as a function of `variable` for sampling.
+fn compile_expression(expression: &str, variable: &str, evaluator: &mut Evaluator) -> Result<CompiledExpr, String> {
+    let ast = parse_expression(expression)?;
+    CompiledExpr::new(&ast, &[variable], evaluator).map_err(|e| format!("Evaluation error: {}", e))
+}
+
 #[tauri::command]
 fn evaluate(expression: String, state: State<AppState>) -> EvalResult {
     let mut evaluator = state.evaluator.lock().unwrap();
-    
-    let mut lexer = Lexer::new(&expression);
-    let tokens = match lexer.tokenize() {
-        Ok(t) => t,
-        Err(e) => return EvalResult {
-            success: false,
-            result: None,
-            error: Some(format!("Lexing error: {}", e)),
-        },
+
+    let ast = match parse_expression(&expression) {
+        Ok(a) => a,
+        Err(e) => return EvalResult::error(e),
     };
     
-    let mut parser = Parser::new(tokens);
-    let ast = match parser.parse() {
+    match evaluator.evaluate(&ast) {
+        Ok(value) => EvalResult {
+            success: true,
+            result: value.as_number().ok(),
+            display: Some(display_value(evaluator.get_context(), &value)),
+            error: None,
+        },
+        Err(e) => EvalResult::error(format!("Evaluation error: {}", e)),
+    }
+}
+
+/// Evaluates an expression as a fixed-width integer and returns it in
+/// decimal, hexadecimal, binary and octal.
+#[tauri::command]
+fn evaluate_programmer(expression: String, state: State<AppState>) -> ProgrammerResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+
+    let ast = match parse_expression(&expression) {
         Ok(a) => a,
-        Err(e) => return EvalResult {
+        Err(e) => return ProgrammerResult::error(e),
+    };
+
+    let word = evaluator.get_context().get_word_size();
+    let integer = match evaluator.evaluate(&ast).map(Value::into_plain) {
+        Ok(Value::Integer(i)) => Ok(i),
+        Ok(value) => value.as_number()
+            .and_then(|n| word.from_f64(n))
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match integer {
+        Ok(i) => ProgrammerResult {
+            success: true,
+            view: Some(IntegerView::new(i, word)),
+            error: None,
+        },
+        Err(e) => ProgrammerResult::error(format!("Evaluation error: {}", e)),
+    }
+}
+
+#[tauri::command]
+fn inspect_float(expression: String, state: State<AppState>) -> InspectResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+
+    let number = parse_expression(&expression).and_then(|ast| {
+        evaluator.evaluate(&ast)
+            .and_then(|value| value.as_number())
+            .map_err(|e| format!("Evaluation error: {}", e))
+    });
+
+    match number {
+        Ok(x) => InspectResult {
+            success: true,
+            single: Some(FloatLayout::new(x, Precision::Single)),
+            double: Some(FloatLayout::new(x, Precision::Double)),
+            error: None,
+        },
+        Err(e) => InspectResult {
             success: false,
-            result: None,
-            error: Some(format!("Parsing error: {}", e)),
+            single: None,
+            double: None,
+            error: Some(e),
         },
-    };
-    
-    match evaluator.evaluate(&ast) {
-        Ok(result) => EvalResult {
+    }
+}
+
+/// Samples `expression`, a function of `variable`, over `[from, to]` for
+/// the graphs tab, using the current angle mode.
+#[tauri::command]
+fn sample_function(expression: String, variable: String, from: f64, to: f64, state: State<AppState>) -> PlotResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    PlotResult::from(compile_expression(&expression, &variable, evaluator).and_then(|f| {
+        plot::sample_function(&f, evaluator, from, to, &SampleOptions::default())
+            .map_err(|e| format!("Evaluation error: {}", e))
+    }))
+}
+
+/// Samples the curve `(x(variable), y(variable))` over `[from, to]`.
+#[tauri::command]
+fn sample_parametric(x: String, y: String, variable: String, from: f64, to: f64, state: State<AppState>) -> PlotResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    let curve = compile_expression(&x, &variable, evaluator).and_then(|x| {
+        let y = compile_expression(&y, &variable, evaluator)?;
+        plot::sample_parametric(&x, &y, evaluator, from, to, &SampleOptions::default())
+            .map_err(|e| format!("Evaluation error: {}", e))
+    });
+    PlotResult::from(curve)
+}
+
+/// Samples the polar curve `r(variable)` over `[from, to]`, with angles in
+/// the current angle mode.
+#[tauri::command]
+fn sample_polar(r: String, variable: String, from: f64, to: f64, state: State<AppState>) -> PlotResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    PlotResult::from(compile_expression(&r, &variable, evaluator).and_then(|r| {
+        plot::sample_polar(&r, evaluator, from, to, &SampleOptions::default())
+            .map_err(|e| format!("Evaluation error: {}", e))
+    }))
+}
+
+/// Traces the curve where `equation`, in `x` and `y`, holds over the view
+/// `[x_from, x_to]` by `[y_from, y_to]`.
+#[tauri::command]
+fn trace_implicit(equation: String, x_from: f64, x_to: f64, y_from: f64, y_to: f64, state: State<AppState>) -> PlotResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    let curve = parse_equation(&equation).and_then(|ast| {
+        CompiledExpr::new(&implicit::residual(&ast), &["x", "y"], evaluator)
+            .and_then(|f| implicit::trace_implicit(&f, evaluator, (x_from, x_to), (y_from, y_to), &ImplicitOptions::default()))
+            .map_err(|e| format!("Evaluation error: {}", e))
+    });
+    PlotResult::from(curve)
+}
+
+/// Tabulates `expression`, a function of `variable`, from `from` to `to` in
+/// steps of `step`. Undefined values come back as `null`.
+#[tauri::command]
+fn tabulate(expression: String, variable: String, from: f64, to: f64, step: f64, state: State<AppState>) -> TableResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    let result = compile_expression(&expression, &variable, evaluator).and_then(|f| {
+        table::tabulate(&f, evaluator, from, to, step).map_err(|e| format!("Evaluation error: {}", e))
+    });
+    match result {
+        Ok(table) => TableResult {
             success: true,
-            result: Some(result),
+            table: Some(table),
             error: None,
         },
-        Err(e) => EvalResult {
+        Err(e) => TableResult {
             success: false,
-            result: None,
-            error: Some(format!("Evaluation error: {}", e)),
+            table: None,
+            error: Some(e),
+        },
+    }
+}
+
+/// Finds the roots, extrema, inflection points and y-intercept of the graph
+/// of `expression`, a function of `variable`, over `[from, to]`.
+#[tauri::command]
+fn analyze_function(expression: String, variable: String, from: f64, to: f64, state: State<AppState>) -> AnalysisResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    AnalysisResult::from(compile_expression(&expression, &variable, evaluator).and_then(|f| {
+        analysis::analyze(&f, evaluator, from, to, &AnalysisOptions::default())
+            .map_err(|e| format!("Evaluation error: {}", e))
+    }))
+}
+
+/// Finds where the graphs of `f` and `g` meet over `[from, to]`.
+#[tauri::command]
+fn intersect_functions(f: String, g: String, variable: String, from: f64, to: f64, state: State<AppState>) -> AnalysisResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    let points = compile_expression(&f, &variable, evaluator).and_then(|f| {
+        let g = compile_expression(&g, &variable, evaluator)?;
+        analysis::intersect(&f, &g, evaluator, from, to, &AnalysisOptions::default())
+            .map_err(|e| format!("Evaluation error: {}", e))
+    });
+    AnalysisResult::from(points)
+}
+
+/// Finds a local minimum, or with `maximum` a maximum, of `expression` in
+/// `variables` near `start`, within `[lower, upper]` if both are given.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn optimize(
+    expression: String,
+    variables: Vec<String>,
+    start: Vec<f64>,
+    lower: Option<Vec<f64>>,
+    upper: Option<Vec<f64>>,
+    maximum: bool,
+    method: Option<Method>,
+    state: State<AppState>,
+) -> OptimizeResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    let bounds: Option<Vec<(f64, f64)>> = lower.zip(upper).map(|(lower, upper)| lower.into_iter().zip(upper).collect());
+    let options = OptimizeOptions { method: method.unwrap_or(Method::Auto), ..OptimizeOptions::default() };
+    let variables: Vec<&str> = variables.iter().map(String::as_str).collect();
+    let result = parse_expression(&expression).and_then(|ast| {
+        let f = CompiledExpr::new(&ast, &variables, evaluator).map_err(|e| format!("Evaluation error: {}", e))?;
+        let optimum = if maximum {
+            optimize::maximize(&f, evaluator, &start, bounds.as_deref(), &options)
+        } else {
+            optimize::minimize(&f, evaluator, &start, bounds.as_deref(), &options)
+        };
+        optimum.map_err(|e| format!("Evaluation error: {}", e))
+    });
+    match result {
+        Ok(optimum) => OptimizeResult {
+            success: true,
+            optimum: Some(optimum),
+            error: None,
+        },
+        Err(e) => OptimizeResult {
+            success: false,
+            optimum: None,
+            error: Some(e),
         },
     }
 }
 
+/// Solves `equations`, such as `y' = -y`, for `unknowns` as functions of
+/// `variable`, starting from `initial` at `from` and sampled every `step`
+/// up to `to`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn solve_ode(
+    equations: Vec<String>,
+    unknowns: Vec<String>,
+    variable: String,
+    from: f64,
+    to: f64,
+    initial: Vec<f64>,
+    step: f64,
+    method: Option<ode::Method>,
+    state: State<AppState>,
+) -> OdeResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    let options = OdeOptions { method: method.unwrap_or(ode::Method::Auto), ..OdeOptions::default() };
+    let unknowns: Vec<&str> = unknowns.iter().map(String::as_str).collect();
+    let mut variables = vec![variable.as_str()];
+    variables.extend(&unknowns);
+    let result = equations.iter().map(|equation| parse_equation(equation)).collect::<Result<Vec<_>, _>>().and_then(|equations| {
+        let rhs = ode::right_hand_sides(&equations, &unknowns)
+            .and_then(|sides| sides.iter().map(|side| CompiledExpr::new(side, &variables, evaluator)).collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("Evaluation error: {}", e))?;
+        ode::solve(&rhs, evaluator, &initial, from, to, step, &options).map_err(|e| format!("Evaluation error: {}", e))
+    });
+    match result {
+        Ok(trajectory) => OdeResult {
+            success: true,
+            trajectory: Some(trajectory),
+            error: None,
+        },
+        Err(e) => OdeResult {
+            success: false,
+            trajectory: None,
+            error: Some(e),
+        },
+    }
+}
+
+/// Expands `expression` in `variable` about `center` to a Taylor polynomial
+/// of the given order.
+#[tauri::command]
+fn taylor_expand(expression: String, variable: String, center: f64, order: usize, state: State<AppState>) -> ExpansionResult {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let evaluator = &mut *evaluator;
+
+    let result = compile_expression(&expression, &variable, evaluator).and_then(|f| {
+        symbolic::taylor(f.expr(), &variable, center, order, evaluator).map_err(|e| format!("Evaluation error: {}", e))
+    });
+    match result {
+        Ok(polynomial) => ExpansionResult {
+            success: true,
+            polynomial: Some(polynomial.to_string()),
+            error: None,
+        },
+        Err(e) => ExpansionResult {
+            success: false,
+            polynomial: None,
+            error: Some(e),
+        },
+    }
+}
+
+#[tauri::command]
+fn set_word_size(bits: u32, signed: bool, state: State<AppState>) -> Result<(), String> {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    let word = WordSize::new(bits, signed).map_err(|e| e.to_string())?;
+    evaluator.get_context_mut().set_word_size(word);
+    Ok(())
+}
+
+#[tauri::command]
+fn seed_random(seed: u64, state: State<AppState>) {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    evaluator.get_context_mut().seed(seed);
+}
+
+#[tauri::command]
+fn get_word_size(state: State<AppState>) -> WordSize {
+    let evaluator = state.evaluator.lock().unwrap();
+    evaluator.get_context().get_word_size()
+}
+
+#[tauri::command]
+fn set_output_base(base: u32, state: State<AppState>) -> Result<(), String> {
+    let mut evaluator = state.evaluator.lock().unwrap();
+    evaluator.get_context_mut().set_output_base(base).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_output_base(state: State<AppState>) -> u32 {
+    let evaluator = state.evaluator.lock().unwrap();
+    evaluator.get_context().get_output_base()
+}
+
 #[tauri::command]
-fn get_variables(state: State<AppState>) -> Vec<(String, f64)> {
+fn get_variables(state: State<AppState>) -> Vec<(String, String)> {
     let evaluator = state.evaluator.lock().unwrap();
-    let vars = evaluator.get_context().get_variables();
-    vars.iter()
-        .map(|(k, v)| (k.clone(), *v))
+    let context = evaluator.get_context();
+    context.get_variables().iter()
+        .map(|(k, v)| (k.clone(), display_value(context, v)))
         .collect()
 }
 
@@ -98,6 +543,23 @@ fn main() {
         })
         .invoke_handler(tauri::generate_handler![
             evaluate,
+            evaluate_programmer,
+            inspect_float,
+            sample_function,
+            sample_parametric,
+            sample_polar,
+            trace_implicit,
+            tabulate,
+            analyze_function,
+            intersect_functions,
+            optimize,
+            solve_ode,
+            taylor_expand,
+            set_word_size,
+            get_word_size,
+            seed_random,
+            set_output_base,
+            get_output_base,
             get_variables,
             set_angle_mode,
             get_angle_mode,
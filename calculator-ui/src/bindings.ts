@@ -3,15 +3,226 @@ import { invoke } from '@tauri-apps/api/core';
 export interface EvalResult {
     success: boolean;
     result?: number;
+    // Formatted value; dates, timestamps and durations use ISO 8601.
+    display?: string;
     error?: string;
 }
 
+export interface IntegerView {
+    dec: string;
+    hex: string;
+    bin: string;
+    oct: string;
+    bits: number;
+    signed: boolean;
+}
+
+export interface ProgrammerResult {
+    success: boolean;
+    view?: IntegerView;
+    error?: string;
+}
+
+export interface FloatLayout {
+    precision: 'single' | 'double';
+    input: number;
+    // Value actually held after rounding to the precision.
+    stored: number;
+    bits: string;
+    sign: 0 | 1;
+    exponent: string;
+    mantissa: string;
+    biased_exponent: number;
+    unbiased_exponent?: number;
+    class: 'zero' | 'subnormal' | 'normal' | 'infinite' | 'nan';
+}
+
+export interface InspectResult {
+    success: boolean;
+    single?: FloatLayout;
+    double?: FloatLayout;
+    error?: string;
+}
+
+export interface Point {
+    x: number;
+    y: number;
+}
+
+// Polylines to draw; the curve is undefined or jumps between segments.
+export interface Curve {
+    segments: Point[][];
+}
+
+export interface PlotResult {
+    success: boolean;
+    curve?: Curve;
+    error?: string;
+}
+
+// Rows of [x, y]; null where the function is undefined.
+export interface Table {
+    columns: string[];
+    rows: (number | null)[][];
+}
+
+export interface TableResult {
+    success: boolean;
+    table?: Table;
+    error?: string;
+}
+
+export interface KeyPoint {
+    kind: 'root' | 'minimum' | 'maximum' | 'inflection' | 'y_intercept' | 'intersection';
+    x: number;
+    y: number;
+}
+
+export interface AnalysisResult {
+    success: boolean;
+    points?: KeyPoint[];
+    error?: string;
+}
+
+export type OptimizeMethod = 'auto' | 'nelder_mead' | 'bfgs' | 'brent';
+
+export interface Optimum {
+    kind: 'minimum' | 'maximum';
+    point: number[];
+    value: number;
+    method: OptimizeMethod;
+    iterations: number;
+}
+
+export interface OptimizeResult {
+    success: boolean;
+    optimum?: Optimum;
+    error?: string;
+}
+
+export type OdeMethod = 'auto' | 'dormand_prince' | 'rosenbrock';
+
+// The solution at each of times: states[i] holds a value per unknown.
+export interface Trajectory {
+    variable: string;
+    unknowns: string[];
+    times: number[];
+    states: number[][];
+    method: OdeMethod;
+    steps: number;
+}
+
+export interface OdeResult {
+    success: boolean;
+    trajectory?: Trajectory;
+    error?: string;
+}
+
+// The polynomial is in the calculator's own syntax, e.g. 'x - x ^ 3 / 6'.
+export interface ExpansionResult {
+    success: boolean;
+    polynomial?: string;
+    error?: string;
+}
+
+export interface WordSize {
+    bits: 8 | 16 | 32 | 64;
+    signed: boolean;
+}
+
 export async function evaluate(expression: string): Promise<EvalResult> {
     console.log("Evaluating expression:", expression);
     return await invoke('evaluate', { expression });
 }
 
-export async function getVariables(): Promise<[string, number][]> {
+export async function evaluateProgrammer(expression: string): Promise<ProgrammerResult> {
+    return await invoke('evaluate_programmer', { expression });
+}
+
+export async function inspectFloat(expression: string): Promise<InspectResult> {
+    return await invoke('inspect_float', { expression });
+}
+
+export async function sampleFunction(expression: string, variable: string, from: number, to: number): Promise<PlotResult> {
+    return await invoke('sample_function', { expression, variable, from, to });
+}
+
+export async function sampleParametric(x: string, y: string, variable: string, from: number, to: number): Promise<PlotResult> {
+    return await invoke('sample_parametric', { x, y, variable, from, to });
+}
+
+// Angles are in the current angle mode.
+export async function samplePolar(r: string, variable: string, from: number, to: number): Promise<PlotResult> {
+    return await invoke('sample_polar', { r, variable, from, to });
+}
+
+// An equation in x and y such as 'x^2 + y^2 = 25', traced over the view.
+export async function traceImplicit(equation: string, xFrom: number, xTo: number, yFrom: number, yTo: number): Promise<PlotResult> {
+    return await invoke('trace_implicit', { equation, xFrom, xTo, yFrom, yTo });
+}
+
+export async function tabulate(expression: string, variable: string, from: number, to: number, step: number): Promise<TableResult> {
+    return await invoke('tabulate', { expression, variable, from, to, step });
+}
+
+export async function analyzeFunction(expression: string, variable: string, from: number, to: number): Promise<AnalysisResult> {
+    return await invoke('analyze_function', { expression, variable, from, to });
+}
+
+export async function intersectFunctions(f: string, g: string, variable: string, from: number, to: number): Promise<AnalysisResult> {
+    return await invoke('intersect_functions', { f, g, variable, from, to });
+}
+
+// Bounds apply when both lower and upper are given, one per variable.
+export async function optimize(
+    expression: string,
+    variables: string[],
+    start: number[],
+    options: { lower?: number[]; upper?: number[]; maximum?: boolean; method?: OptimizeMethod } = {},
+): Promise<OptimizeResult> {
+    const { lower, upper, maximum = false, method } = options;
+    return await invoke('optimize', { expression, variables, start, lower, upper, maximum, method });
+}
+
+export async function solveOde(
+    equations: string[],
+    unknowns: string[],
+    variable: string,
+    from: number,
+    to: number,
+    initial: number[],
+    step: number,
+    method?: OdeMethod,
+): Promise<OdeResult> {
+    return await invoke('solve_ode', { equations, unknowns, variable, from, to, initial, step, method });
+}
+
+export async function taylorExpand(expression: string, variable: string, center: number, order: number): Promise<ExpansionResult> {
+    return await invoke('taylor_expand', { expression, variable, center, order });
+}
+
+export async function setWordSize(bits: WordSize['bits'], signed: boolean): Promise<void> {
+    return await invoke('set_word_size', { bits, signed });
+}
+
+export async function getWordSize(): Promise<WordSize> {
+    return await invoke('get_word_size');
+}
+
+export async function seedRandom(seed: number): Promise<void> {
+    return await invoke('seed_random', { seed });
+}
+
+// Output base for `display`, 2 to 36.
+export async function setOutputBase(base: number): Promise<void> {
+    return await invoke('set_output_base', { base });
+}
+
+export async function getOutputBase(): Promise<number> {
+    return await invoke('get_output_base');
+}
+
+export async function getVariables(): Promise<[string, string][]> {
     return await invoke('get_variables');
 }
 
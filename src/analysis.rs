@@ -0,0 +1,485 @@
+//! Numerical analysis of the graph of a function: its roots, extrema and
+//! inflection points, where it crosses the y axis, and where two graphs
+//! meet.
+//!
+//! The function is first evaluated on an even grid. A sign change between
+//! neighbouring grid points brackets a root; a grid value above or below
+//! both its neighbours brackets an extremum, where the slope changes sign;
+//! and a change of sign in the grid's second differences brackets an
+//! inflection point. Each is then pinned down by bisection, on the function
+//! itself or on central-difference estimates of its derivatives. Near a
+//! pole or a jump all three kinds of bracket appear without there being
+//! anything to find, so candidates next to a discontinuity are dropped.
+//!
+//! Derivative estimates lose digits to rounding, so extrema and inflection
+//! points are rounded to the decimals they are good for, which turns
+//! `1.0e-8` into the `0` it stands for.
+
+use serde::Serialize;
+
+use crate::{compile::CompiledExpr, error::EvalError, evaluator::Evaluator, plot::{self, Jump}, table::Table};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PointKind {
+    Root,
+    Minimum,
+    Maximum,
+    Inflection,
+    YIntercept,
+    Intersection,
+}
+
+impl PointKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            PointKind::Root => "root",
+            PointKind::Minimum => "minimum",
+            PointKind::Maximum => "maximum",
+            PointKind::Inflection => "inflection",
+            PointKind::YIntercept => "y-intercept",
+            PointKind::Intersection => "intersection",
+        }
+    }
+}
+
+/// A point of interest on a graph, labelled with what it is.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct KeyPoint {
+    pub kind: PointKind,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnalysisOptions {
+    /// Evenly spaced intervals searched for sign changes and extrema. Two
+    /// features closer than one interval may be seen as one, or missed.
+    pub intervals: usize,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions { intervals: 1000 }
+    }
+}
+
+/// The roots, local minima and maxima, inflection points and y-intercept of
+/// the graph of `f` over `[from, to]`, in order along the x axis.
+pub fn analyze(
+    f: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    from: f64,
+    to: f64,
+    options: &AnalysisOptions,
+) -> Result<Vec<KeyPoint>, EvalError> {
+    check(&[f], from, to)?;
+    let mut eval = |x: f64| f.eval(evaluator, &[x]);
+    let grid = Grid::new(&mut eval, from, to, options.intervals);
+    if grid.is_zero() {
+        return Err(EvalError::MathError(format!("the function is zero everywhere on [{}, {}]", from, to)));
+    }
+
+    let mut points: Vec<KeyPoint> = grid.roots(&mut eval).into_iter()
+        .map(|x| KeyPoint { kind: PointKind::Root, x, y: 0.0 })
+        .collect();
+    let extrema = grid.extrema(&mut eval);
+    let inflections = grid.inflections(&mut eval);
+    let precision = grid.step() * 1e-5;
+    // Rounding leaves `sin(180)` in degrees at 1.2e-16 rather than 0.
+    let mut value_at = |x: f64| {
+        let y = eval(x);
+        if grid.is_rounding_error(&mut eval, x, y) { 0.0 } else { y }
+    };
+    for (x, is_max) in extrema {
+        let kind = if is_max { PointKind::Maximum } else { PointKind::Minimum };
+        let x = round_to(x, precision);
+        points.push(KeyPoint { kind, x, y: value_at(x) });
+    }
+    // Second differences place a change of curvature less precisely.
+    for x in inflections {
+        let x = round_to(x, precision * 10.0);
+        points.push(KeyPoint { kind: PointKind::Inflection, x, y: value_at(x) });
+    }
+
+    if from <= 0.0 && to >= 0.0 {
+        let y = value_at(0.0);
+        if y.is_finite() {
+            points.push(KeyPoint { kind: PointKind::YIntercept, x: 0.0, y });
+        }
+    }
+    Ok(arrange(points, grid.step()))
+}
+
+/// The points where the graphs of `f` and `g` meet over `[from, to]`.
+pub fn intersect(
+    f: &CompiledExpr,
+    g: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    from: f64,
+    to: f64,
+    options: &AnalysisOptions,
+) -> Result<Vec<KeyPoint>, EvalError> {
+    check(&[f, g], from, to)?;
+    let mut difference = |x: f64| f.eval(evaluator, &[x]) - g.eval(evaluator, &[x]);
+    let grid = Grid::new(&mut difference, from, to, options.intervals);
+    if grid.is_zero() {
+        return Err(EvalError::MathError(format!("the graphs coincide everywhere on [{}, {}]", from, to)));
+    }
+    let roots = grid.roots(&mut difference);
+
+    let points = roots.into_iter()
+        .map(|x| KeyPoint { kind: PointKind::Intersection, x, y: f.eval(evaluator, &[x]) })
+        .collect();
+    Ok(arrange(points, grid.step()))
+}
+
+/// Points as rows of a table, each labelled with its kind.
+pub fn to_table(points: &[KeyPoint]) -> Table {
+    Table {
+        columns: vec!["x".to_string(), "y".to_string()],
+        labels: points.iter().map(|p| p.kind.label().to_string()).collect(),
+        rows: points.iter().map(|p| vec![p.x, p.y]).collect(),
+    }
+}
+
+fn check(functions: &[&CompiledExpr], from: f64, to: f64) -> Result<(), EvalError> {
+    if functions.iter().any(|f| f.variables().len() != 1) {
+        return Err(EvalError::InvalidArguments("a function to analyze takes exactly one variable".to_string()));
+    }
+    if !from.is_finite() || !to.is_finite() || from >= to {
+        return Err(EvalError::InvalidArguments(format!(
+            "cannot analyze over [{}, {}]: expected a finite range with from < to", from, to
+        )));
+    }
+    Ok(())
+}
+
+/// Sorts points along the x axis, dropping any found twice, as a double
+/// root is when both its bracket and its extremum turn it up. Points are
+/// placed to within a few times `step * 1e-5`, so only points closer than
+/// that count as the same.
+fn arrange(mut points: Vec<KeyPoint>, step: f64) -> Vec<KeyPoint> {
+    points.sort_by(|a, b| a.kind.cmp(&b.kind).then(a.x.total_cmp(&b.x)));
+    points.dedup_by(|b, a| a.kind == b.kind && (b.x - a.x).abs() <= step * 1e-4);
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.kind.cmp(&b.kind)));
+    points
+}
+
+/// `x` with only the decimals that `precision` leaves meaningful.
+pub(crate) fn round_to(x: f64, precision: f64) -> f64 {
+    let decimals = (-precision.log10()).floor().clamp(0.0, 17.0) as usize;
+    // Adding zero turns a rounded -0 into 0.
+    format!("{:.*}", decimals, x).parse().map_or(x, |rounded: f64| rounded + 0.0)
+}
+
+/// A function's values on an even grid.
+struct Grid {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+}
+
+impl Grid {
+    fn new(eval: &mut dyn FnMut(f64) -> f64, from: f64, to: f64, intervals: usize) -> Self {
+        let intervals = intervals.max(2);
+        let xs: Vec<f64> = (0..=intervals)
+            .map(|i| from + (to - from) * i as f64 / intervals as f64)
+            .collect();
+        let ys = xs.iter().map(|&x| eval(x)).collect();
+        Grid { xs, ys }
+    }
+
+    fn step(&self) -> f64 {
+        self.xs[1] - self.xs[0]
+    }
+
+    fn is_zero(&self) -> bool {
+        self.ys.iter().all(|&y| y == 0.0)
+    }
+
+    /// Whether `y`, the value at `x`, is zero but for rounding error. That
+    /// error is estimated from the size and slope of the function within the
+    /// precision points are placed to, so a value is never lost against
+    /// larger values elsewhere on the grid.
+    fn is_rounding_error(&self, eval: &mut dyn FnMut(f64) -> f64, x: f64, y: f64) -> bool {
+        let h = self.step() * 1e-5;
+        let (left, right) = (eval(x - h), eval(x + h));
+        // Rounding `x` itself moves the value by about its slope times ε|x|.
+        let slope = (right - left).abs() / (2.0 * h);
+        y.abs() <= 64.0 * f64::EPSILON * (left.abs().max(right.abs()) + x.abs() * slope)
+    }
+
+    /// Sign changes refined by bisection, and extrema that touch zero. A run
+    /// of zeros is an interval where the function vanishes, given by its ends.
+    fn roots(&self, eval: &mut dyn FnMut(f64) -> f64) -> Vec<f64> {
+        let mut roots = Vec::new();
+        let mut i = 0;
+        while i < self.xs.len() {
+            let (a, ya) = (self.xs[i], self.ys[i]);
+            if ya == 0.0 {
+                let end = (i..self.xs.len()).take_while(|&j| self.ys[j] == 0.0).last().unwrap();
+                if end == i {
+                    roots.push(a);
+                } else {
+                    roots.push(if i == 0 { a } else { zero_edge(eval, a, self.xs[i - 1]) });
+                    let b = self.xs[end];
+                    roots.push(if end + 1 == self.xs.len() { b } else { zero_edge(eval, b, self.xs[end + 1]) });
+                }
+                i = end + 1;
+                continue;
+            }
+            let Some((&b, &yb)) = self.xs.get(i + 1).zip(self.ys.get(i + 1)) else { break };
+            if ya.is_finite() && yb.is_finite() && yb != 0.0 && (ya < 0.0) != (yb < 0.0) && !self.is_jump(eval, i) {
+                roots.push(bisect(eval, a, b, ya));
+            }
+            i += 1;
+        }
+        for (x, _) in self.extrema(eval) {
+            let y = eval(x);
+            if self.is_rounding_error(eval, x, y) {
+                roots.push(x);
+            }
+        }
+        roots
+    }
+
+    /// Local maxima and minima inside the grid, with `true` marking maxima.
+    fn extrema(&self, eval: &mut dyn FnMut(f64) -> f64) -> Vec<(f64, bool)> {
+        // Central differences move the turning point by about h², so h is
+        // kept well under the precision the points are rounded to.
+        let h = self.step() * 1e-4;
+        let mut found = Vec::new();
+        for i in 1..self.xs.len() - 1 {
+            let (a, b, c) = (self.ys[i - 1], self.ys[i], self.ys[i + 1]);
+            if !(a.is_finite() && b.is_finite() && c.is_finite()) {
+                continue;
+            }
+            // Differences this small are rounding error.
+            let noise = 1e-9 * a.abs().max(b.abs()).max(c.abs());
+            for sign in [1.0, -1.0] {
+                let (a, b, c) = (sign * a, sign * b, sign * c);
+                if b < a || b < c || b - a.min(c) <= noise || self.any_jump(eval, i - 1, i) {
+                    continue;
+                }
+                let mut slope = |x: f64| sign * (eval(x + h) - eval(x - h));
+                let (left, right) = (self.xs[i - 1], self.xs[i + 1]);
+                let rise = slope(left);
+                if !(rise > 0.0 && slope(right) < 0.0) {
+                    continue;
+                }
+                let x = bisect(&mut slope, left, right, rise);
+                if eval(x).is_finite() {
+                    found.push((x, sign > 0.0));
+                }
+            }
+        }
+        found
+    }
+
+    /// Where the curvature changes sign. Second differences of a straight
+    /// stretch are rounding error, so they only count when well clear of it.
+    fn inflections(&self, eval: &mut dyn FnMut(f64) -> f64) -> Vec<f64> {
+        let h = self.step() * 0.1;
+        let curved: Vec<(usize, f64)> = (1..self.xs.len() - 1)
+            .filter_map(|i| {
+                let (a, b, c) = (self.ys[i - 1], self.ys[i], self.ys[i + 1]);
+                let difference = a - 2.0 * b + c;
+                let noise = 1e-12 * (a.abs() + 2.0 * b.abs() + c.abs());
+                (difference.is_finite() && difference.abs() > noise).then_some((i, difference))
+            })
+            .collect();
+
+        let mut found = Vec::new();
+        for pair in curved.windows(2) {
+            let ((i, di), (j, dj)) = (pair[0], pair[1]);
+            if j - i > 2 || (di < 0.0) == (dj < 0.0) || self.any_jump(eval, i - 1, j) {
+                continue;
+            }
+            let mut curvature = |x: f64| eval(x - h) - 2.0 * eval(x) + eval(x + h);
+            let (left, right) = (self.xs[i], self.xs[j]);
+            let bend = curvature(left);
+            if !bend.is_finite() || bend == 0.0 {
+                continue;
+            }
+            let x = bisect(&mut curvature, left, right, bend);
+            if eval(x).is_finite() {
+                found.push(x);
+            }
+        }
+        found
+    }
+
+    /// Whether any interval from `first` to `last`, or one next to them, has
+    /// a jump. The neighbours count because derivative estimates reach a
+    /// little way past the ends of a bracket.
+    fn any_jump(&self, eval: &mut dyn FnMut(f64) -> f64, first: usize, last: usize) -> bool {
+        let last = (last + 1).min(self.xs.len() - 2);
+        (first.saturating_sub(1)..=last).any(|i| self.is_jump(eval, i))
+    }
+
+    /// Whether the function jumps, or has a pole, between grid points `i`
+    /// and `i + 1`.
+    fn is_jump(&self, eval: &mut dyn FnMut(f64) -> f64, i: usize) -> bool {
+        let (a, b) = ((self.xs[i], self.ys[i]), (self.xs[i + 1], self.ys[i + 1]));
+        if !(a.1.is_finite() && b.1.is_finite()) {
+            return true;
+        }
+        let mut midpoint = |(a, _): (f64, f64), (b, _): (f64, f64)| {
+            let mid = 0.5 * (a + b);
+            (mid, eval(mid))
+        };
+        !matches!(plot::find_jump(a, b, &mut midpoint, &|(_, ya), (_, yb)| (yb - ya).abs()), Jump::None)
+    }
+}
+
+/// The end of a stretch where `eval` is zero, between `zero`, where it is,
+/// and `other`, where it isn't.
+fn zero_edge(eval: &mut dyn FnMut(f64) -> f64, mut zero: f64, mut other: f64) -> f64 {
+    loop {
+        let mid = 0.5 * (zero + other);
+        if mid == zero || mid == other {
+            return zero;
+        }
+        if eval(mid) == 0.0 {
+            zero = mid;
+        } else {
+            other = mid;
+        }
+    }
+}
+
+/// A root in `[a, b]`, where `eval` changes sign and `fa` is its value at `a`.
+fn bisect(eval: &mut dyn FnMut(f64) -> f64, mut a: f64, mut b: f64, mut fa: f64) -> f64 {
+    loop {
+        let mid = 0.5 * (a + b);
+        if mid <= a || mid >= b {
+            return mid;
+        }
+        let fm = eval(mid);
+        if fm == 0.0 || !fm.is_finite() {
+            return mid;
+        }
+        if (fm < 0.0) == (fa < 0.0) {
+            a = mid;
+            fa = fm;
+        } else {
+            b = mid;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::AngleMode, lexer::Lexer, parser::Parser};
+
+    fn compile(input: &str, evaluator: &mut Evaluator) -> CompiledExpr {
+        let expr = Parser::new(Lexer::new(input).tokenize().unwrap()).parse().unwrap();
+        CompiledExpr::new(&expr, &["x"], evaluator).unwrap()
+    }
+
+    fn analyze_in(input: &str, from: f64, to: f64) -> Vec<(PointKind, f64, f64)> {
+        let mut evaluator = Evaluator::new();
+        evaluator.get_context_mut().set_angle_mode(AngleMode::Radians);
+        let f = compile(input, &mut evaluator);
+        analyze(&f, &mut evaluator, from, to, &AnalysisOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|p| (p.kind, p.x, p.y))
+            .collect()
+    }
+
+    /// Points that coincide may come in either order.
+    fn assert_points(found: &[(PointKind, f64, f64)], expected: &[(PointKind, f64, f64)]) {
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for e in expected {
+            assert!(found.iter().any(|f| f.0 == e.0 && (f.1 - e.1).abs() < 1e-6 && (f.2 - e.2).abs() < 1e-6),
+                "{:?} not in {:?}", e, found);
+        }
+        assert!(found.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn test_cubic() {
+        use PointKind::*;
+        // x^3 - 3x: roots at 0 and ±√3, extrema at ±1, inflection at 0.
+        let s = 3f64.sqrt();
+        assert_points(&analyze_in("x^3 - 3*x", -2.5, 2.5), &[
+            (Root, -s, 0.0),
+            (Maximum, -1.0, 2.0),
+            (Root, 0.0, 0.0),
+            (Inflection, 0.0, 0.0),
+            (YIntercept, 0.0, 0.0),
+            (Minimum, 1.0, -2.0),
+            (Root, s, 0.0),
+        ]);
+    }
+
+    #[test]
+    fn test_double_root_and_poles() {
+        use PointKind::*;
+        // A root that only touches zero, away from the grid points.
+        assert_points(&analyze_in("(x - 0.123)^2 - 1", -0.9, 1.9), &[
+            (Root, -0.877, 0.0),
+            (YIntercept, 0.0, 0.123 * 0.123 - 1.0),
+            (Minimum, 0.123, -1.0),
+            (Root, 1.123, 0.0),
+        ]);
+        let touching = analyze_in("(x - 0.123)^2", -1.0, 1.0);
+        assert!(touching.iter().any(|p| p.0 == Root && (p.1 - 0.123).abs() < 1e-6));
+
+        // tan has roots at multiples of π and poles in between, which are
+        // neither roots nor extrema nor inflection points.
+        let pi = std::f64::consts::PI;
+        let found = analyze_in("tan(x)", 0.5, 7.0);
+        assert_points(&found, &[(Root, pi, 0.0), (Inflection, pi, 0.0), (Root, 2.0 * pi, 0.0), (Inflection, 2.0 * pi, 0.0)]);
+        assert!(analyze_in("1 / x^2", -1.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_values_far_from_zero_stay() {
+        use PointKind::*;
+        // Far out the graph reaches 10^18, which must not swamp the -3 at 0.
+        let r = 3f64.powf(1.0 / 6.0);
+        let expected = [(Root, -r, 0.0), (Minimum, 0.0, -3.0), (YIntercept, 0.0, -3.0), (Root, r, 0.0)];
+        assert_points(&analyze_in("x^6 - 3", -1000.0, 1000.0), &expected);
+        let s = 2f64.sqrt();
+        assert_points(&analyze_in("x^2 - 2", -1e7, 1e7), &[
+            (Root, -s, 0.0),
+            (Minimum, 0.0, -2.0),
+            (YIntercept, 0.0, -2.0),
+            (Root, s, 0.0),
+        ]);
+    }
+
+    #[test]
+    fn test_zero_stretches() {
+        use PointKind::*;
+        // floor is zero on [0, 1): one stretch, given by its two ends.
+        let found = analyze_in("floor(x)", -2.5, 2.5);
+        let roots: Vec<f64> = found.iter().filter(|p| p.0 == Root).map(|p| p.1).collect();
+        assert_eq!(roots.len(), 2, "{:?}", found);
+        assert_eq!(roots[0], 0.0);
+        assert!(roots[1] < 1.0 && roots[1] > 1.0 - 1e-12);
+        assert_points(&analyze_in("max(x, 0) - 1", -3.0, 3.0), &[(YIntercept, 0.0, -1.0), (Root, 1.0, 0.0)]);
+
+        let mut evaluator = Evaluator::new();
+        let f = compile("0 * x", &mut evaluator);
+        let error = analyze(&f, &mut evaluator, -1.0, 1.0, &AnalysisOptions::default()).unwrap_err();
+        assert_eq!(error, EvalError::MathError("the function is zero everywhere on [-1, 1]".to_string()));
+        let g = compile("x", &mut evaluator);
+        assert!(intersect(&g, &g, &mut evaluator, 0.0, 1.0, &AnalysisOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let mut evaluator = Evaluator::new();
+        let (f, g) = (compile("x^2", &mut evaluator), compile("x + 2", &mut evaluator));
+        let points = intersect(&f, &g, &mut evaluator, -5.0, 5.0, &AnalysisOptions::default()).unwrap();
+        assert_eq!(points.len(), 2);
+        assert!((points[0].x + 1.0).abs() < 1e-9 && (points[0].y - 1.0).abs() < 1e-9);
+        assert!((points[1].x - 2.0).abs() < 1e-9 && (points[1].y - 4.0).abs() < 1e-9);
+        assert!(points.iter().all(|p| p.kind == PointKind::Intersection));
+        assert!(intersect(&f, &g, &mut evaluator, 1.0, -1.0, &AnalysisOptions::default()).is_err());
+    }
+}
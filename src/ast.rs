@@ -1,8 +1,15 @@
-/// Abastract Syntax Tree (AST) module for the calculator language.
+//! Abastract Syntax Tree (AST) module for the calculator language.
+
+use std::fmt;
+
+use crate::{datetime::DateTime, distributions::{DistFunction, Distribution}, units::Unit};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     Number(f64),
+    Integer(i128),
+    Bool(bool),
+    DateTime(DateTime),
     BinaryOp {
         left: Box<Expr>,
         op: BinOp,
@@ -25,6 +32,162 @@ pub enum Expr {
         name: String,
         value: Box<Expr>,
     },
+    /// `f(x, y) = body`
+    FunctionDef {
+        name: String,
+        params: Vec<String>,
+        body: Box<Expr>,
+    },
+    /// `x^2 + y^2 = 25`, from `Parser::parse_equation`. It has no value of
+    /// its own; solvers and plotters work with `left - right`.
+    Equation {
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// `[1, 2, 3]`
+    List(Vec<Expr>),
+    /// `{x^2 if x < 0; x otherwise}`: the first piece whose condition holds
+    /// gives the value.
+    Piecewise {
+        pieces: Vec<(Expr, Expr)>,
+        otherwise: Option<Box<Expr>>,
+    },
+    Quantity {
+        value: Box<Expr>,
+        unit: Unit,
+    },
+    Conversion {
+        expr: Box<Expr>,
+        target: ConversionTarget,
+    },
+    Money {
+        value: Box<Expr>,
+        currency: String,
+    },
+}
+
+impl Expr {
+    /// Whether the expression always evaluates to the same value: it reads no
+    /// variables and calls only pure functions, so its value can be computed
+    /// once and reused.
+    pub fn is_constant(&self) -> bool {
+        match self {
+            Expr::Number(_) | Expr::Integer(_) | Expr::Bool(_) | Expr::DateTime(_) => true,
+            Expr::Variable(_) | Expr::Assignment { .. } | Expr::FunctionDef { .. } | Expr::Equation { .. } => false,
+            Expr::BinaryOp { left, right, .. } => left.is_constant() && right.is_constant(),
+            Expr::UnaryOp { expr, .. } | Expr::PostfixOp { expr, .. } | Expr::Conversion { expr, .. } => expr.is_constant(),
+            Expr::Quantity { value, .. } | Expr::Money { value, .. } => value.is_constant(),
+            Expr::FunctionCall { func, args } => func.is_pure() && args.iter().all(Expr::is_constant),
+            Expr::List(items) => items.iter().all(Expr::is_constant),
+            Expr::Piecewise { pieces, otherwise } => {
+                pieces.iter().all(|(value, condition)| value.is_constant() && condition.is_constant())
+                    && otherwise.as_ref().is_none_or(|value| value.is_constant())
+            },
+        }
+    }
+}
+
+/// Prints the expression back as input the parser reads to the same tree,
+/// with only the parentheses that precedence requires.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Integer(i) => write!(f, "{}", i),
+            Expr::Bool(b) => write!(f, "{}", b),
+            Expr::DateTime(dt) => write!(f, "{}", dt),
+            Expr::BinaryOp { left, op, right } => {
+                let level = op.precedence();
+                // `^` groups to the right and comparisons do not chain.
+                let (left_level, right_level) = match op {
+                    BinOp::Power => (level + 1, level),
+                    op if op.is_comparison() => (level + 1, level + 1),
+                    _ => (level, level + 1),
+                };
+                write!(f, "{} {} {}", Grouped(left, left_level), op.symbol(), Grouped(right, right_level))
+            },
+            Expr::UnaryOp { op: UnOp::Not, expr } => write!(f, "not {}", Grouped(expr, NOT)),
+            Expr::UnaryOp { op, expr } => write!(f, "{}{}", op.symbol(), Grouped(expr, POSTFIX)),
+            Expr::PostfixOp { expr, op } => write!(f, "{}{}", Grouped(expr, PRIMARY), op.symbol()),
+            Expr::FunctionCall { func, args } => write!(f, "{}({})", func.name(), join(args)),
+            Expr::Variable(name) => write!(f, "{}", name),
+            Expr::Assignment { name, value } => write!(f, "{} = {}", name, value),
+            Expr::FunctionDef { name, params, body } => write!(f, "{}({}) = {}", name, params.join(", "), body),
+            Expr::Equation { left, right } => write!(f, "{} = {}", left, right),
+            Expr::List(items) => write!(f, "[{}]", join(items)),
+            Expr::Piecewise { pieces, otherwise } => {
+                let mut parts: Vec<String> = pieces.iter()
+                    .map(|(value, condition)| format!("{} if {}", value, condition))
+                    .collect();
+                if let Some(value) = otherwise {
+                    parts.push(format!("{} otherwise", value));
+                }
+                write!(f, "{{{}}}", parts.join("; "))
+            },
+            Expr::Quantity { value, unit } => write!(f, "{} {}", Grouped(value, PRIMARY), unit.name),
+            Expr::Money { value, currency } => write!(f, "{} {}", Grouped(value, PRIMARY), currency),
+            Expr::Conversion { expr, target } => {
+                let expr = Grouped(expr, EXPRESSION);
+                match target {
+                    ConversionTarget::Unit(unit) => write!(f, "{} to {}", expr, unit.name),
+                    ConversionTarget::Currency(currency) => write!(f, "{} to {}", expr, currency),
+                    ConversionTarget::Base(2) => write!(f, "{} in bin", expr),
+                    ConversionTarget::Base(8) => write!(f, "{} in oct", expr),
+                    ConversionTarget::Base(10) => write!(f, "{} in dec", expr),
+                    ConversionTarget::Base(16) => write!(f, "{} in hex", expr),
+                    ConversionTarget::Base(base) => write!(f, "{} in base {}", expr, base),
+                }
+            },
+        }
+    }
+}
+
+// Precedence levels, loosest first, following the grammar in `parser`.
+const EXPRESSION: u8 = 2;
+const NOT: u8 = 4;
+const UNARY: u8 = 13;
+const POSTFIX: u8 = 14;
+const PRIMARY: u8 = 15;
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Assignment { .. } | Expr::FunctionDef { .. } | Expr::Equation { .. } => 0,
+            Expr::Conversion { .. } => 1,
+            Expr::BinaryOp { op, .. } => op.precedence(),
+            Expr::UnaryOp { op: UnOp::Not, .. } => NOT,
+            Expr::UnaryOp { .. } => UNARY,
+            Expr::Number(n) if n.is_sign_negative() => UNARY,
+            Expr::Integer(i) if *i < 0 => UNARY,
+            Expr::PostfixOp { .. } => POSTFIX,
+            _ => PRIMARY,
+        }
+    }
+}
+
+/// An operand, in parentheses if it binds more loosely than `level`.
+struct Grouped<'a>(&'a Expr, u8);
+
+impl fmt::Display for Grouped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.precedence() < self.1 {
+            write!(f, "({})", self.0)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+fn join(items: &[Expr]) -> String {
+    items.iter().map(Expr::to_string).collect::<Vec<_>>().join(", ")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionTarget {
+    Unit(Unit),
+    Currency(String),
+    /// Display in another base, 2 to 36.
+    Base(u32),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -33,7 +196,79 @@ pub enum BinOp {
     Subtract,
     Multiply,
     Divide,
+    /// Floor division, `a // b`.
+    FloorDivide,
+    /// Euclidean remainder, `a mod b`: never negative.
+    Modulo,
+    /// Truncated remainder, `a rem b`: takes the sign of `a`.
+    Remainder,
     Power,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+impl BinOp {
+    /// Operators that act on the bits of fixed-width integers.
+    pub fn is_bitwise(&self) -> bool {
+        matches!(self, BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor | BinOp::ShiftLeft | BinOp::ShiftRight)
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(self,
+            BinOp::Equal | BinOp::NotEqual | BinOp::Less | BinOp::LessEqual | BinOp::Greater | BinOp::GreaterEqual)
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            BinOp::Or => 2,
+            BinOp::And => 3,
+            op if op.is_comparison() => 5,
+            BinOp::BitOr => 6,
+            BinOp::BitXor => 7,
+            BinOp::BitAnd => 8,
+            BinOp::ShiftLeft | BinOp::ShiftRight => 9,
+            BinOp::Add | BinOp::Subtract => 10,
+            BinOp::Power => 12,
+            _ => 11,
+        }
+    }
+
+    pub(crate) fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Subtract => "-",
+            BinOp::Multiply => "*",
+            BinOp::Divide => "/",
+            BinOp::FloorDivide => "//",
+            BinOp::Modulo => "mod",
+            BinOp::Remainder => "rem",
+            BinOp::Power => "^",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::BitXor => "xor",
+            BinOp::ShiftLeft => "<<",
+            BinOp::ShiftRight => ">>",
+            BinOp::Equal => "==",
+            BinOp::NotEqual => "!=",
+            BinOp::Less => "<",
+            BinOp::LessEqual => "<=",
+            BinOp::Greater => ">",
+            BinOp::GreaterEqual => ">=",
+            BinOp::And => "and",
+            BinOp::Or => "or",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -41,6 +276,22 @@ pub enum UnOp {
     Negate,
     Positive,
     Factorial,
+    Percent,
+    BitNot,
+    Not,
+}
+
+impl UnOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            UnOp::Negate => "-",
+            UnOp::Positive => "+",
+            UnOp::Factorial => "!",
+            UnOp::Percent => "%",
+            UnOp::BitNot => "~",
+            UnOp::Not => "not ",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -51,10 +302,26 @@ pub enum Function {
     Asin,
     Acos,
     Atan,
+    Sec,
+    Csc,
+    Cot,
+    Asec,
+    Acsc,
+    Acot,
+    Sinh,
+    Cosh,
+    Tanh,
+    Asinh,
+    Acosh,
+    Atanh,
     Log,
+    Log2,
     Ln,
+    Log1p,
     Exp,
+    Expm1,
     Sqrt,
+    Cbrt,
     Abs,
     Floor,
     Ceil,
@@ -63,29 +330,209 @@ pub enum Function {
     Pow,
     Max,
     Min,
+    Atan2,
+    Hypot,
+    Root,
+    // Special functions
+    Erf,
+    Erfc,
+    Erfinv,
+    BesselJ,
+    BesselY,
+    BesselI,
+    BesselK,
+    Zeta,
+    LambertW,
+    EllipK,
+    EllipE,
+    Ei,
+    Expint,
+    // Random numbers
+    Rand,
+    RandInt,
+    RandN,
+    Choice,
+    /// `normpdf`, `chi2inv` and the other probability distribution functions.
+    Distribution(Distribution, DistFunction),
+    // Calendar functions
+    Today,
+    Now,
+    Date,
+    Year,
+    Month,
+    Day,
+    Weekday,
+    IsoWeek,
+    BusDays,
+    Workday,
+    // IEEE-754 inspection
+    FloatBits,
+    DoubleBits,
+    FromBits,
+    Ulp,
+    NextAfter,
+    // Special forms whose arguments are evaluated on demand
+    If,
+    /// `table(expr, x, from, to[, step])`: a table of values of `expr`.
+    Table,
+    /// `analyze(expr, x, from, to)`: roots, extrema and inflection points.
+    Analyze,
+    /// `intersect(f, g, x, from, to)`: where two graphs meet.
+    Intersect,
+    /// `minimize(expr, [x, y], [x0, y0][, lower, upper])`: a local minimum.
+    Minimize,
+    /// `maximize(expr, [x, y], [x0, y0][, lower, upper])`: a local maximum.
+    Maximize,
+    /// `odesolve(y' = f, y, t, from, to, y0[, step])`: an initial value problem.
+    OdeSolve,
+    /// `taylor(expr, x, a, n)`: the Taylor polynomial of order `n` about `a`.
+    Taylor,
+    /// `plot_param(x, y, t, from, to)`: points along a parametric curve.
+    PlotParam,
+    /// `plot_polar(r, θ, from, to)`: points along a polar curve.
+    PlotPolar,
+    /// A function defined with `f(x) = ...`.
+    User(String),
 }
 
+/// The names of the built-in functions, as typed.
+const NAMES: &[(&str, Function)] = &[
+    ("sin", Function::Sin),
+    ("cos", Function::Cos),
+    ("tan", Function::Tan),
+    ("asin", Function::Asin),
+    ("acos", Function::Acos),
+    ("atan", Function::Atan),
+    ("sec", Function::Sec),
+    ("csc", Function::Csc),
+    ("cot", Function::Cot),
+    ("asec", Function::Asec),
+    ("acsc", Function::Acsc),
+    ("acot", Function::Acot),
+    ("sinh", Function::Sinh),
+    ("cosh", Function::Cosh),
+    ("tanh", Function::Tanh),
+    ("asinh", Function::Asinh),
+    ("acosh", Function::Acosh),
+    ("atanh", Function::Atanh),
+    ("log", Function::Log),
+    ("log2", Function::Log2),
+    ("ln", Function::Ln),
+    ("log1p", Function::Log1p),
+    ("exp", Function::Exp),
+    ("expm1", Function::Expm1),
+    ("sqrt", Function::Sqrt),
+    ("cbrt", Function::Cbrt),
+    ("root", Function::Root),
+    ("erf", Function::Erf),
+    ("erfc", Function::Erfc),
+    ("erfinv", Function::Erfinv),
+    ("besselj", Function::BesselJ),
+    ("bessely", Function::BesselY),
+    ("besseli", Function::BesselI),
+    ("besselk", Function::BesselK),
+    ("zeta", Function::Zeta),
+    ("lambertw", Function::LambertW),
+    ("ellipk", Function::EllipK),
+    ("ellipe", Function::EllipE),
+    ("ei", Function::Ei),
+    ("expint", Function::Expint),
+    ("rand", Function::Rand),
+    ("randint", Function::RandInt),
+    ("randn", Function::RandN),
+    ("choice", Function::Choice),
+    ("abs", Function::Abs),
+    ("floor", Function::Floor),
+    ("ceil", Function::Ceil),
+    ("round", Function::Round),
+    ("pow", Function::Pow),
+    ("max", Function::Max),
+    ("min", Function::Min),
+    ("atan2", Function::Atan2),
+    ("hypot", Function::Hypot),
+    ("today", Function::Today),
+    ("now", Function::Now),
+    ("date", Function::Date),
+    ("year", Function::Year),
+    ("month", Function::Month),
+    ("day", Function::Day),
+    ("weekday", Function::Weekday),
+    ("isoweek", Function::IsoWeek),
+    ("busdays", Function::BusDays),
+    ("workday", Function::Workday),
+    ("float_bits", Function::FloatBits),
+    ("double_bits", Function::DoubleBits),
+    ("from_bits", Function::FromBits),
+    ("ulp", Function::Ulp),
+    ("nextafter", Function::NextAfter),
+    ("if", Function::If),
+    ("table", Function::Table),
+    ("analyze", Function::Analyze),
+    ("intersect", Function::Intersect),
+    ("minimize", Function::Minimize),
+    ("maximize", Function::Maximize),
+    ("odesolve", Function::OdeSolve),
+    ("taylor", Function::Taylor),
+    ("plot_param", Function::PlotParam),
+    ("plot_polar", Function::PlotPolar),
+];
+
 impl Function {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
-            "sin" => Some(Function::Sin),
-            "cos" => Some(Function::Cos),
-            "tan" => Some(Function::Tan),
-            "asin" => Some(Function::Asin),
-            "acos" => Some(Function::Acos),
-            "atan" => Some(Function::Atan),
-            "log" => Some(Function::Log),
-            "ln" => Some(Function::Ln),
-            "exp" => Some(Function::Exp),
-            "sqrt" => Some(Function::Sqrt),
-            "abs" => Some(Function::Abs),
-            "floor" => Some(Function::Floor),
-            "ceil" => Some(Function::Ceil),
-            "round" => Some(Function::Round),
-            "pow" => Some(Function::Pow),
-            "max" => Some(Function::Max),
-            "min" => Some(Function::Min),
-            _ => None,
+        let name = s.to_lowercase();
+        NAMES.iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, function)| function.clone())
+            .or_else(|| Distribution::parse(&name).map(|(dist, function)| Function::Distribution(dist, function)))
+    }
+
+    /// The name the function is called by, as `from_str` accepts it.
+    pub fn name(&self) -> String {
+        match self {
+            Function::User(name) => name.clone(),
+            Function::Distribution(dist, function) => dist.name(*function),
+            _ => NAMES.iter()
+                .find(|(_, function)| function == self)
+                .map(|(name, _)| name.to_string())
+                .expect("every built-in function has a name"),
         }
     }
+
+    /// Functions that take or return dates rather than plain numbers.
+    pub fn is_calendar(&self) -> bool {
+        matches!(self,
+            Function::Today | Function::Now | Function::Date | Function::Year | Function::Month
+            | Function::Day | Function::Weekday | Function::IsoWeek | Function::BusDays | Function::Workday)
+    }
+
+    /// Special forms that receive their arguments unevaluated, so untaken
+    /// branches cannot raise errors.
+    pub fn is_lazy(&self) -> bool {
+        matches!(
+            self,
+            Function::If | Function::Table | Function::Analyze | Function::Intersect | Function::Minimize | Function::Maximize
+                | Function::OdeSolve | Function::Taylor | Function::PlotParam | Function::PlotPolar
+        )
+    }
+
+    /// Functions that draw from the context's random number generator.
+    pub fn is_random(&self) -> bool {
+        matches!(self,
+            Function::Rand | Function::RandInt | Function::RandN | Function::Choice
+            | Function::Distribution(_, DistFunction::Rnd))
+    }
+
+    /// Functions whose result depends only on their arguments. Random
+    /// functions, the clock and user functions (which may read variables)
+    /// are impure and must be evaluated every time.
+    pub fn is_pure(&self) -> bool {
+        !self.is_random() && !matches!(self, Function::Today | Function::Now | Function::User(_))
+    }
+
+    /// Functions that work on the bit patterns of floating-point numbers.
+    pub fn is_ieee754(&self) -> bool {
+        matches!(self,
+            Function::FloatBits | Function::DoubleBits | Function::FromBits | Function::Ulp | Function::NextAfter)
+    }
 }
\ No newline at end of file
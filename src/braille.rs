@@ -0,0 +1,241 @@
+//! Charts drawn with Braille characters, for plotting in a terminal.
+//!
+//! Each character cell holds a 2 by 4 grid of dots, so a chart 80 columns
+//! wide has 160 dots across. Curves are drawn as lines between their
+//! sampled points, each series in its own ANSI colour; where series cross,
+//! the cell takes the colour of the one drawn last.
+
+use crate::chart::{self, Series, View};
+
+/// ANSI colour codes for the series, in the same order as the SVG palette.
+const COLORS: [u8; 8] = [34, 31, 32, 33, 35, 36, 91, 94];
+
+/// The bit for each dot of a Braille cell, by row and then column.
+const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TerminalOptions {
+    /// Size of the whole chart, labels and legend included, in characters.
+    pub width: usize,
+    pub height: usize,
+    /// Colour the series with ANSI escape codes.
+    pub color: bool,
+}
+
+impl Default for TerminalOptions {
+    fn default() -> Self {
+        TerminalOptions { width: 80, height: 24, color: true }
+    }
+}
+
+/// Draws `series` in `view` as lines of text.
+pub fn render(series: &[Series], view: View, options: &TerminalOptions) -> String {
+    let rows = options.height.saturating_sub(3).max(4);
+    let y_step = chart::tick_step(view.y.0, view.y.1, (rows / 4).max(2));
+    let y_ticks = chart::nice_ticks(view.y.0, view.y.1, (rows / 4).max(2));
+    let y_labels: Vec<String> = y_ticks.iter().map(|&y| chart::format_tick(y, y_step)).collect();
+    let label_width = y_labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let columns = options.width.saturating_sub(label_width + 1).max(10);
+
+    let mut canvas = Canvas::new(columns, rows, view);
+    if view.y.0 < 0.0 && view.y.1 > 0.0 {
+        canvas.line((view.x.0, 0.0), (view.x.1, 0.0), None);
+    }
+    if view.x.0 < 0.0 && view.x.1 > 0.0 {
+        canvas.line((0.0, view.y.0), (0.0, view.y.1), None);
+    }
+    for (index, s) in series.iter().enumerate() {
+        for segment in &s.curve.segments {
+            for part in view.clip(segment) {
+                for pair in part.windows(2) {
+                    canvas.line((pair[0].x, pair[0].y), (pair[1].x, pair[1].y), Some(index));
+                }
+            }
+        }
+    }
+
+    let mut out = String::new();
+    let tick_rows: Vec<(usize, &String)> = y_ticks.iter()
+        .map(|&y| canvas.cell_of(view.x.0, y).1)
+        .zip(&y_labels)
+        .collect();
+    for row in 0..rows {
+        match tick_rows.iter().find(|(r, _)| *r == row) {
+            Some((_, label)) => out.push_str(&format!("{:>width$}┤", label, width = label_width)),
+            None => out.push_str(&format!("{:width$}│", "", width = label_width)),
+        }
+        canvas.write_row(&mut out, row, options.color);
+        out.push('\n');
+    }
+
+    // The horizontal axis: tick marks, then labels that don't overlap.
+    let x_count = (columns / 10).max(2);
+    let x_step = chart::tick_step(view.x.0, view.x.1, x_count);
+    let x_ticks: Vec<(usize, String)> = chart::nice_ticks(view.x.0, view.x.1, x_count).iter()
+        .map(|&x| (canvas.cell_of(x, view.y.0).0, chart::format_tick(x, x_step)))
+        .collect();
+    let mut axis: Vec<char> = vec!['─'; columns];
+    for (column, _) in &x_ticks {
+        axis[*column] = '┬';
+    }
+    out.push_str(&format!("{:width$}└{}\n", "", axis.iter().collect::<String>(), width = label_width));
+
+    let mut labels: Vec<char> = vec![' '; label_width + 1 + columns];
+    let mut free_from = 0;
+    for (column, label) in &x_ticks {
+        let length = label.chars().count();
+        let start = (label_width + 1 + column).saturating_sub(length / 2);
+        if start >= free_from && start + length <= labels.len() {
+            labels.splice(start..start + length, label.chars());
+            free_from = start + length + 1;
+        }
+    }
+    out.push_str(labels.iter().collect::<String>().trim_end());
+    out.push('\n');
+
+    let legend: Vec<String> = series.iter().enumerate()
+        .filter(|(_, s)| !s.label.is_empty())
+        .map(|(index, s)| format!("{} {}", paint("━━", Some(index), options.color), s.label))
+        .collect();
+    if !legend.is_empty() {
+        out.push_str(&format!("{:width$} {}\n", "", legend.join("   "), width = label_width));
+    }
+    out
+}
+
+fn paint(text: &str, series: Option<usize>, color: bool) -> String {
+    match series {
+        Some(index) if color => format!("\x1b[{}m{}\x1b[0m", COLORS[index % COLORS.len()], text),
+        _ => text.to_string(),
+    }
+}
+
+/// A grid of Braille cells, each with its dots and the series that last
+/// drew in it.
+struct Canvas {
+    columns: usize,
+    rows: usize,
+    view: View,
+    cells: Vec<(u32, Option<usize>)>,
+}
+
+impl Canvas {
+    fn new(columns: usize, rows: usize, view: View) -> Self {
+        Canvas { columns, rows, view, cells: vec![(0, None); columns * rows] }
+    }
+
+    /// The dot nearest to a point of the plane, which may be off the canvas.
+    fn dot_of(&self, x: f64, y: f64) -> (i64, i64) {
+        let (width, height) = ((self.columns * 2 - 1) as f64, (self.rows * 4 - 1) as f64);
+        let dx = (x - self.view.x.0) / (self.view.x.1 - self.view.x.0) * width;
+        let dy = (self.view.y.1 - y) / (self.view.y.1 - self.view.y.0) * height;
+        (dx.round() as i64, dy.round() as i64)
+    }
+
+    /// The cell holding the dot nearest to a point of the view.
+    fn cell_of(&self, x: f64, y: f64) -> (usize, usize) {
+        let (dx, dy) = self.dot_of(x, y);
+        ((dx.max(0) as usize / 2).min(self.columns - 1), (dy.max(0) as usize / 4).min(self.rows - 1))
+    }
+
+    fn set(&mut self, dx: i64, dy: i64, series: Option<usize>) {
+        if dx < 0 || dy < 0 || dx >= (self.columns * 2) as i64 || dy >= (self.rows * 4) as i64 {
+            return;
+        }
+        let (dx, dy) = (dx as usize, dy as usize);
+        let cell = &mut self.cells[(dy / 4) * self.columns + dx / 2];
+        cell.0 |= DOTS[dy % 4][dx % 2];
+        if series.is_some() {
+            cell.1 = series;
+        }
+    }
+
+    /// Bresenham's line between two points of the plane.
+    fn line(&mut self, from: (f64, f64), to: (f64, f64), series: Option<usize>) {
+        let (mut x, mut y) = self.dot_of(from.0, from.1);
+        let (x1, y1) = self.dot_of(to.0, to.1);
+        let (dx, dy) = ((x1 - x).abs(), -(y1 - y).abs());
+        let (sx, sy) = ((x1 - x).signum(), (y1 - y).signum());
+        let mut error = dx + dy;
+        loop {
+            self.set(x, y, series);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += sx;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += sy;
+            }
+        }
+    }
+
+    /// Appends a row of cells, with a colour change only where the series
+    /// changes.
+    fn write_row(&self, out: &mut String, row: usize, color: bool) {
+        let cells = &self.cells[row * self.columns..(row + 1) * self.columns];
+        let mut run = String::new();
+        let mut run_series = None;
+        for &(dots, series) in cells {
+            let series = if dots == 0 { None } else { series };
+            if series != run_series && !run.is_empty() {
+                out.push_str(&paint(&run, run_series, color));
+                run.clear();
+            }
+            run_series = series;
+            run.push(char::from_u32(0x2800 + dots).unwrap_or(' '));
+        }
+        out.push_str(&paint(&run, run_series, color));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::{Curve, Point};
+
+    fn line(label: &str, points: &[(f64, f64)]) -> Series {
+        let points = points.iter().map(|&(x, y)| Point { x, y }).collect();
+        Series { label: label.to_string(), curve: Curve { segments: vec![points] } }
+    }
+
+    #[test]
+    fn test_layout() {
+        let view = View { x: (0.0, 10.0), y: (0.0, 10.0) };
+        let options = TerminalOptions { width: 40, height: 12, color: false };
+        let text = render(&[line("diagonal", &[(0.0, 0.0), (10.0, 10.0)])], view, &options);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 12);
+        assert!(lines.iter().all(|l| l.chars().count() <= 40));
+        // Nine rows of plot, the axis, its labels and the legend.
+        assert!(lines[0].starts_with("10┤"));
+        assert!(lines[8].starts_with(" 0┤"));
+        assert!(lines[9].starts_with("  └┬"));
+        assert!(lines[10].trim_start().starts_with("0 "));
+        assert!(lines[10].ends_with("10"));
+        assert_eq!(lines[11], "   ━━ diagonal");
+
+        // The diagonal runs from the bottom-left to the top-right dot.
+        let cells: Vec<char> = lines[8].chars().skip(3).collect();
+        assert_eq!(cells[0], '⣀');
+        assert!(lines[0].chars().last().is_some_and(|c| c != '⠀'));
+        assert!(lines[1..8].iter().all(|l| l.ends_with('⠀')));
+        assert!(!text.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_colors_and_axes() {
+        let view = View { x: (-1.0, 1.0), y: (-1.0, 1.0) };
+        let series = [line("a", &[(-1.0, 0.5), (1.0, 0.5)]), line("b", &[(-1.0, -0.5), (1.0, -0.5)])];
+        let text = render(&series, view, &TerminalOptions { width: 30, height: 10, color: true });
+        assert!(text.contains("\x1b[34m") && text.contains("\x1b[31m"));
+        assert!(text.contains("\x1b[34m━━\x1b[0m a") && text.contains("\x1b[31m━━\x1b[0m b"));
+        // Both axes cross the middle of the chart.
+        let rows: Vec<&str> = text.lines().take(7).collect();
+        assert!(rows.iter().all(|row| row.chars().any(|c| ('\u{2801}'..='\u{28FF}').contains(&c))));
+    }
+}
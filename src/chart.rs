@@ -0,0 +1,253 @@
+//! Layout shared by the chart renderers: which part of the plane a set of
+//! curves is shown in, where the ticks go and how they are labelled, and
+//! clipping curves to the visible area.
+
+use crate::plot::{Curve, Point};
+
+/// A curve with the label it is listed under in the legend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub label: String,
+    pub curve: Curve,
+}
+
+/// The visible part of the plane, as `(from, to)` on each axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    pub x: (f64, f64),
+    pub y: (f64, f64),
+}
+
+impl View {
+    /// A view of all of `series`, leaving out the far outliers a pole
+    /// produces so they don't flatten the rest of the picture, with a small
+    /// margin. `None` if there is nothing to show.
+    ///
+    /// Samplers put many points close to a pole, so each y counts by the
+    /// horizontal distance to its neighbours, the share of the graph it
+    /// stands for.
+    pub fn fit(series: &[Series]) -> Option<View> {
+        let mut xs = Vec::new();
+        let mut ys = Vec::new();
+        for segment in series.iter().flat_map(|s| &s.curve.segments) {
+            for (i, p) in segment.iter().enumerate() {
+                let before = &segment[i.saturating_sub(1)];
+                let after = &segment[(i + 1).min(segment.len() - 1)];
+                xs.push((p.x, 1.0));
+                ys.push((p.y, (after.x - before.x).abs()));
+            }
+        }
+        if xs.is_empty() {
+            return None;
+        }
+        Some(View { x: fit_range(xs), y: pad(fit_range(ys)) })
+    }
+
+    /// Widens one axis so that a unit is as long on both when drawn in an
+    /// area `width` by `height`, which keeps circles round.
+    pub fn equal_scale(self, width: f64, height: f64) -> View {
+        let (dx, dy) = (self.x.1 - self.x.0, self.y.1 - self.y.0);
+        if dx / width > dy / height {
+            let extra = (dx * height / width - dy) / 2.0;
+            View { y: (self.y.0 - extra, self.y.1 + extra), ..self }
+        } else {
+            let extra = (dy * width / height - dx) / 2.0;
+            View { x: (self.x.0 - extra, self.x.1 + extra), ..self }
+        }
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= self.x.0 && p.x <= self.x.1 && p.y >= self.y.0 && p.y <= self.y.1
+    }
+
+    /// The parts of a polyline inside the view.
+    pub fn clip(&self, polyline: &[Point]) -> Vec<Vec<Point>> {
+        let mut parts: Vec<Vec<Point>> = Vec::new();
+        let mut current: Vec<Point> = Vec::new();
+        for pair in polyline.windows(2) {
+            match self.clip_segment(pair[0], pair[1]) {
+                Some((start, end)) => {
+                    if current.last() != Some(&start) {
+                        if current.len() > 1 {
+                            parts.push(std::mem::take(&mut current));
+                        }
+                        current = vec![start];
+                    }
+                    current.push(end);
+                },
+                None => {
+                    if current.len() > 1 {
+                        parts.push(std::mem::take(&mut current));
+                    }
+                    current.clear();
+                },
+            }
+        }
+        if current.len() > 1 {
+            parts.push(current);
+        }
+        parts
+    }
+
+    /// Liang–Barsky clipping of the segment from `a` to `b`.
+    fn clip_segment(&self, a: Point, b: Point) -> Option<(Point, Point)> {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+        for (p, q) in [
+            (-dx, a.x - self.x.0),
+            (dx, self.x.1 - a.x),
+            (-dy, a.y - self.y.0),
+            (dy, self.y.1 - a.y),
+        ] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+        if t0 > t1 {
+            return None;
+        }
+        let at = |t: f64| if t == 0.0 { a } else if t == 1.0 { b } else { Point { x: a.x + t * dx, y: a.y + t * dy } };
+        Some((at(t0), at(t1)))
+    }
+}
+
+/// The range of weighted `values`, ignoring the outermost 5% of the weight
+/// on a side when it lies far beyond the rest.
+fn fit_range(mut values: Vec<(f64, f64)>) -> (f64, f64) {
+    values.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut total: f64 = values.iter().map(|&(_, weight)| weight).sum();
+    if !(total > 0.0 && total.is_finite()) {
+        values.iter_mut().for_each(|(_, weight)| *weight = 1.0);
+        total = values.len() as f64;
+    }
+    let quantile = |q: f64| {
+        let mut sum = 0.0;
+        for &(value, weight) in &values {
+            sum += weight;
+            if sum >= q * total {
+                return value;
+            }
+        }
+        values[values.len() - 1].0
+    };
+    let (min, max) = (values[0].0, values[values.len() - 1].0);
+    let (low, high) = (quantile(0.05), quantile(0.95));
+    let spread = high - low;
+    let from = if low - min <= 2.0 * spread { min } else { low };
+    let to = if max - high <= 2.0 * spread { max } else { high };
+    if from < to {
+        (from, to)
+    } else {
+        (from - 1.0, to + 1.0)
+    }
+}
+
+fn pad((from, to): (f64, f64)) -> (f64, f64) {
+    let margin = (to - from) * 0.05;
+    (from - margin, to + margin)
+}
+
+/// Round tick positions covering `[from, to]`: multiples of 1, 2 or 5 times
+/// a power of ten, about `count` of them.
+pub fn nice_ticks(from: f64, to: f64, count: usize) -> Vec<f64> {
+    if from >= to || !from.is_finite() || !to.is_finite() {
+        return Vec::new();
+    }
+    let step = tick_step(from, to, count);
+    let first = (from / step).ceil() as i64;
+    let last = (to / step).floor() as i64;
+    (first..=last).map(|k| k as f64 * step).collect()
+}
+
+/// The distance between the ticks `nice_ticks` places.
+pub fn tick_step(from: f64, to: f64, count: usize) -> f64 {
+    let rough = (to - from) / count.max(1) as f64;
+    let magnitude = 10f64.powf(rough.log10().floor());
+    let fraction = rough / magnitude;
+    let nice = if fraction < 1.5 {
+        1.0
+    } else if fraction < 3.0 {
+        2.0
+    } else if fraction < 7.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+/// A tick label with just enough decimals to tell ticks `step` apart.
+pub fn format_tick(value: f64, step: f64) -> String {
+    let magnitude = value.abs().max(step);
+    if magnitude >= 1e6 || step < 1e-4 {
+        return format!("{:e}", (value / step).round() * step);
+    }
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    let label = format!("{:.*}", decimals, value);
+    if label.trim_start_matches('-').chars().all(|c| c == '0' || c == '.') {
+        label.trim_start_matches('-').to_string()
+    } else {
+        label
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(points: &[(f64, f64)]) -> Series {
+        let points = points.iter().map(|&(x, y)| Point { x, y }).collect();
+        Series { label: String::new(), curve: Curve { segments: vec![points] } }
+    }
+
+    #[test]
+    fn test_nice_ticks() {
+        assert_eq!(nice_ticks(0.0, 10.0, 5), vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0]);
+        assert_eq!(nice_ticks(-1.1, 1.1, 4), vec![-1.0, -0.5, 0.0, 0.5, 1.0]);
+        assert_eq!(nice_ticks(0.0, 360.0, 6), vec![0.0, 50.0, 100.0, 150.0, 200.0, 250.0, 300.0, 350.0]);
+        assert!(nice_ticks(1.0, 1.0, 5).is_empty());
+
+        assert_eq!(format_tick(0.5, 0.5), "0.5");
+        assert_eq!(format_tick(-0.0, 0.5), "0.0");
+        assert_eq!(format_tick(100.0, 50.0), "100");
+        assert_eq!(format_tick(0.30000000000000004, 0.1), "0.3");
+        assert_eq!(format_tick(2e6, 1e6), "2e6");
+    }
+
+    #[test]
+    fn test_fit_ignores_poles() {
+        let mut points: Vec<(f64, f64)> = (0..=100).map(|i| (i as f64, (i as f64 / 10.0).sin())).collect();
+        let around_one = |view: View| view.y.0 < -1.0 && view.y.0 > -1.2 && view.y.1 > 1.0 && view.y.1 < 1.2;
+        let view = View::fit(&[series(&points)]).unwrap();
+        assert_eq!(view.x, (0.0, 100.0));
+        assert!(around_one(view), "{:?}", view);
+
+        points[50].1 = 1e16;
+        assert!(around_one(View::fit(&[series(&points)]).unwrap()));
+        assert!(View::fit(&[]).is_none());
+    }
+
+    #[test]
+    fn test_clip() {
+        let view = View { x: (0.0, 10.0), y: (0.0, 10.0) };
+        let line: Vec<Point> = [(-5.0, 5.0), (5.0, 5.0), (5.0, 20.0), (7.0, 20.0), (7.0, 5.0)]
+            .iter()
+            .map(|&(x, y)| Point { x, y })
+            .collect();
+        let parts = view.clip(&line);
+        assert_eq!(parts, vec![
+            vec![Point { x: 0.0, y: 5.0 }, Point { x: 5.0, y: 5.0 }, Point { x: 5.0, y: 10.0 }],
+            vec![Point { x: 7.0, y: 10.0 }, Point { x: 7.0, y: 5.0 }],
+        ]);
+
+        let square = View { x: (0.0, 1.0), y: (0.0, 1.0) }.equal_scale(400.0, 200.0);
+        assert_eq!(square.x, (-0.5, 1.5));
+        assert_eq!(square.y, (0.0, 1.0));
+    }
+}
@@ -0,0 +1,179 @@
+//! Expressions prepared for repeated evaluation at many points, as plotting
+//! and tabulating need.
+//!
+//! Compiling checks that every variable is either one of the free variables
+//! or already defined, and folds constant subexpressions such as `2*PI` or
+//! `sqrt(2)` so they are computed once instead of at every sample.
+
+use crate::{ast::{Expr, Function, UnOp}, error::EvalError, evaluator::Evaluator, value::Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledExpr {
+    expr: Expr,
+    variables: Vec<String>,
+}
+
+impl CompiledExpr {
+    pub fn new(expr: &Expr, variables: &[&str], evaluator: &mut Evaluator) -> Result<Self, EvalError> {
+        check_variables(expr, variables, evaluator)?;
+        Ok(CompiledExpr {
+            expr: fold_constants(expr, evaluator),
+            variables: variables.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Evaluates with the free variables bound, in order, to `point`.
+    pub fn eval_value(&self, evaluator: &mut Evaluator, point: &[f64]) -> Result<Value, EvalError> {
+        let bindings: Vec<(&str, Value)> = self.variables.iter()
+            .zip(point)
+            .map(|(name, &x)| (name.as_str(), Value::Number(x)))
+            .collect();
+        match evaluator.eval_with(&bindings, &self.expr)? {
+            Value::Expression(expr) => evaluator.eval_with(&bindings, &expr),
+            value => Ok(value),
+        }
+    }
+
+    /// Evaluates to a plain number. Points where the expression is undefined
+    /// (division by zero, a domain error, a non-numeric result) give NaN, so
+    /// callers can treat them as gaps.
+    pub fn eval(&self, evaluator: &mut Evaluator, point: &[f64]) -> f64 {
+        self.eval_value(evaluator, point)
+            .and_then(|value| value.into_plain().as_number())
+            .unwrap_or(f64::NAN)
+    }
+}
+
+fn check_variables(expr: &Expr, variables: &[&str], evaluator: &Evaluator) -> Result<(), EvalError> {
+    let check = |e: &Expr| check_variables(e, variables, evaluator);
+    match expr {
+        Expr::Number(_) | Expr::Integer(_) | Expr::Bool(_) | Expr::DateTime(_) => Ok(()),
+        Expr::Variable(name) => {
+            if variables.contains(&name.as_str()) || evaluator.get_context().get(name).is_some() {
+                Ok(())
+            } else {
+                Err(EvalError::UndefinedVariable(name.clone()))
+            }
+        },
+        Expr::Assignment { .. } | Expr::FunctionDef { .. } => Err(EvalError::InvalidArguments(
+            "expected an expression, found a definition".to_string()
+        )),
+        Expr::BinaryOp { left, right, .. } | Expr::Equation { left, right } => check(left).and_then(|_| check(right)),
+        Expr::UnaryOp { expr, .. } | Expr::PostfixOp { expr, .. } | Expr::Conversion { expr, .. } => check(expr),
+        Expr::Quantity { value, .. } | Expr::Money { value, .. } => check(value),
+        Expr::FunctionCall { args, .. } | Expr::List(args) => args.iter().try_for_each(check),
+        Expr::Piecewise { pieces, otherwise } => {
+            pieces.iter().try_for_each(|(value, condition)| check(value).and_then(|_| check(condition)))?;
+            otherwise.as_deref().map_or(Ok(()), check)
+        },
+    }
+}
+
+/// Replaces constant subexpressions with their values. Subexpressions that
+/// fail to evaluate are kept, so the error still surfaces (or stays in an
+/// untaken branch) at evaluation time. A bare percentage is kept too, since
+/// `x + 15%` means something different from `x + 0.15`. A `taylor(...)`
+/// call about a constant point is replaced by its polynomial, which would
+/// otherwise be worked out again at every point.
+fn fold_constants(expr: &Expr, evaluator: &mut Evaluator) -> Expr {
+    if let Expr::FunctionCall { func: Function::Taylor, args } = expr
+        && args.get(2..).is_some_and(|rest| rest.iter().all(Expr::is_constant))
+        && let Ok(Value::Expression(polynomial)) = evaluator.eval_value(expr)
+    {
+        return fold_constants(&polynomial, evaluator);
+    }
+
+    let is_literal = matches!(expr, Expr::Number(_) | Expr::Integer(_) | Expr::Bool(_));
+    let is_percent = matches!(expr, Expr::PostfixOp { op: UnOp::Percent, .. });
+    if expr.is_constant() && !is_literal && !is_percent {
+        match evaluator.eval_value(expr) {
+            Ok(Value::Number(n)) => return Expr::Number(n),
+            Ok(Value::Integer(i)) => return Expr::Integer(i),
+            Ok(Value::Bool(b)) => return Expr::Bool(b),
+            _ => {},
+        }
+    }
+
+    let mut fold = |e: &Expr| Box::new(fold_constants(e, evaluator));
+    match expr {
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp { left: fold(left), op: op.clone(), right: fold(right) },
+        Expr::Equation { left, right } => Expr::Equation { left: fold(left), right: fold(right) },
+        Expr::UnaryOp { op, expr } => Expr::UnaryOp { op: op.clone(), expr: fold(expr) },
+        Expr::PostfixOp { expr, op } => Expr::PostfixOp { expr: fold(expr), op: op.clone() },
+        Expr::Conversion { expr, target } => Expr::Conversion { expr: fold(expr), target: target.clone() },
+        Expr::Quantity { value, unit } => Expr::Quantity { value: fold(value), unit: unit.clone() },
+        Expr::Money { value, currency } => Expr::Money { value: fold(value), currency: currency.clone() },
+        Expr::FunctionCall { func, args } => Expr::FunctionCall {
+            func: func.clone(),
+            args: args.iter().map(|arg| *fold(arg)).collect(),
+        },
+        Expr::List(items) => Expr::List(items.iter().map(|item| *fold(item)).collect()),
+        Expr::Piecewise { pieces, otherwise } => Expr::Piecewise {
+            pieces: pieces.iter().map(|(value, condition)| (*fold(value), *fold(condition))).collect(),
+            otherwise: otherwise.as_deref().map(fold),
+        },
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::{BinOp, Function}, lexer::Lexer, parser::Parser};
+
+    fn compile(input: &str, variables: &[&str], evaluator: &mut Evaluator) -> Result<CompiledExpr, EvalError> {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let expr = Parser::new(tokens).parse().unwrap();
+        CompiledExpr::new(&expr, variables, evaluator)
+    }
+
+    #[test]
+    fn test_constants_are_folded() {
+        let mut evaluator = Evaluator::new();
+        let compiled = compile("x * (2 + 3) + sqrt(4)", &["x"], &mut evaluator).unwrap();
+        assert_eq!(compiled.expr(), &Expr::BinaryOp {
+            left: Box::new(Expr::BinaryOp {
+                left: Box::new(Expr::Variable("x".to_string())),
+                op: BinOp::Multiply,
+                right: Box::new(Expr::Number(5.0)),
+            }),
+            op: BinOp::Add,
+            right: Box::new(Expr::Number(2.0)),
+        });
+        assert_eq!(compiled.eval(&mut evaluator, &[1.5]), 9.5);
+
+        // Random calls are not constant and must not be frozen.
+        let random = compile("rand() + x", &["x"], &mut evaluator).unwrap();
+        assert!(matches!(random.expr(), Expr::BinaryOp { left, .. }
+            if matches!(**left, Expr::FunctionCall { func: Function::Rand, .. })));
+    }
+
+    #[test]
+    fn test_variables_are_checked() {
+        let mut evaluator = Evaluator::new();
+        assert!(compile("x + PI", &["x"], &mut evaluator).is_ok());
+        assert_eq!(compile("x + y", &["x"], &mut evaluator), Err(EvalError::UndefinedVariable("y".to_string())));
+        assert!(compile("x = 2", &["x"], &mut evaluator).is_err());
+
+        let compiled = compile("x * y", &["x", "y"], &mut evaluator).unwrap();
+        assert_eq!(compiled.eval(&mut evaluator, &[3.0, 4.0]), 12.0);
+        // Bindings don't leak into the session.
+        assert!(evaluator.get_context().get("x").is_none());
+    }
+
+    #[test]
+    fn test_undefined_points_are_nan() {
+        let mut evaluator = Evaluator::new();
+        let compiled = compile("1 / x", &["x"], &mut evaluator).unwrap();
+        assert!(compiled.eval(&mut evaluator, &[0.0]).is_nan());
+        assert_eq!(compiled.eval(&mut evaluator, &[4.0]), 0.25);
+    }
+}
@@ -1,36 +1,71 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
+
+use crate::{ast::Expr, currency::RateTable, error::{EvalError, RateTableError}, integer::WordSize, radix, random::Rng, value::Value};
+
+/// A function defined in the session with `f(x) = ...`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserFunction {
+    pub params: Vec<String>,
+    pub body: Expr,
+}
 
 pub struct Context {
-    variables: HashMap<String, f64>,
+    variables: HashMap<String, Value>,
+    functions: HashMap<String, UserFunction>,
     angle_mode: AngleMode,
+    rates: Option<RateTable>,
+    word_size: WordSize,
+    output_base: u32,
+    rng: Rng,
 }
 
 impl Context {
     pub fn new() -> Self {
         let mut variables = HashMap::new();
 
-        variables.insert("PI".to_string(), std::f64::consts::PI);
-        variables.insert("E".to_string(), std::f64::consts::E);
+        variables.insert("PI".to_string(), Value::Number(std::f64::consts::PI));
+        variables.insert("E".to_string(), Value::Number(std::f64::consts::E));
 
         Context { 
             variables,
-            angle_mode: AngleMode::Degrees
+            functions: HashMap::new(),
+            angle_mode: AngleMode::Degrees,
+            rates: None,
+            word_size: WordSize::DEFAULT,
+            output_base: 10,
+            rng: Rng::from_time(),
         }
     }
 
-    pub fn get(&self, name: &str) -> Option<f64>{
-        self.variables.get(name).copied()
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
     }
 
-    pub fn set_ans(&mut self, value: f64) {
+    pub fn set_ans(&mut self, value: Value) {
         self.set("ans".to_string(), value);
     }
 
-    pub fn set(&mut self, name: String, value: f64) {
+    pub fn set(&mut self, name: String, value: Value) {
         self.variables.insert(name, value);
     }
 
-    pub fn get_variables(&self) -> &HashMap<String, f64> {
+    pub fn remove(&mut self, name: &str) -> Option<Value> {
+        self.variables.remove(name)
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<&UserFunction> {
+        self.functions.get(name)
+    }
+
+    pub fn define_function(&mut self, name: String, function: UserFunction) {
+        self.functions.insert(name, function);
+    }
+
+    pub fn get_functions(&self) -> &HashMap<String, UserFunction> {
+        &self.functions
+    }
+
+    pub fn get_variables(&self) -> &HashMap<String, Value> {
         &self.variables
     }
 
@@ -41,6 +76,66 @@ impl Context {
     pub fn set_angle_mode(&mut self, mode: AngleMode) {
         self.angle_mode = mode;
     }
+
+    pub fn get_word_size(&self) -> WordSize {
+        self.word_size
+    }
+
+    pub fn set_word_size(&mut self, word_size: WordSize) {
+        self.word_size = word_size;
+    }
+
+    pub fn get_output_base(&self) -> u32 {
+        self.output_base
+    }
+
+    pub fn set_output_base(&mut self, base: u32) -> Result<(), EvalError> {
+        self.output_base = radix::check_base(base)?;
+        Ok(())
+    }
+
+    /// Restarts the random number generator from a seed, making `rand()` and
+    /// the other random functions reproducible.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    pub fn get_rng_mut(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+
+    /// Formats a value in the current output base.
+    pub fn format_value(&self, value: &Value) -> String {
+        value.to_string_in(self.output_base, self.word_size)
+    }
+
+    pub fn get_rates(&self) -> Option<&RateTable> {
+        self.rates.as_ref()
+    }
+
+    pub fn set_rates(&mut self, table: RateTable) {
+        self.rates = Some(table);
+    }
+
+    pub fn load_rates(&mut self, path: &Path) -> Result<&RateTable, RateTableError> {
+        let table = RateTable::load(path)?;
+        Ok(self.rates.insert(table))
+    }
+
+    /// Reads the current rate table again from the file it was loaded from.
+    pub fn reload_rates(&mut self) -> Result<&RateTable, RateTableError> {
+        let path = self.rates.as_ref()
+            .and_then(|table| table.source())
+            .map(Path::to_path_buf)
+            .ok_or(RateTableError::NotLoaded)?;
+        self.load_rates(&path)
+    }
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -0,0 +1,228 @@
+//! Offline currency conversion from a user-supplied rate table.
+//!
+//! Tables are read from local files, never from the network. Two formats are
+//! accepted:
+//!
+//! JSON, with rates expressed as units of each currency per one unit of `base`:
+//! ```json
+//! { "base": "EUR", "timestamp": "2026-10-17", "rates": { "USD": 1.08, "GBP": 0.86 } }
+//! ```
+//!
+//! CSV, one `currency,rate` pair per line, with optional `# base: EUR` and
+//! `# timestamp: 2026-10-17` comment lines and an optional header row.
+
+use std::{collections::HashMap, fmt, fs, path::{Path, PathBuf}};
+
+use serde::Deserialize;
+
+use crate::error::{EvalError, RateTableError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateTable {
+    pub base: String,
+    pub timestamp: Option<String>,
+    rates: HashMap<String, f64>,
+    source: Option<PathBuf>,
+}
+
+#[derive(Deserialize)]
+struct JsonTable {
+    base: String,
+    timestamp: Option<String>,
+    rates: HashMap<String, f64>,
+}
+
+impl RateTable {
+    pub fn new(base: &str, timestamp: Option<String>, rates: HashMap<String, f64>) -> Result<Self, RateTableError> {
+        let base = base.trim().to_uppercase();
+        if !is_currency_code(&base) {
+            return Err(RateTableError::InvalidFormat(format!("invalid base currency '{}'", base)));
+        }
+
+        let mut normalized = HashMap::new();
+        for (code, rate) in rates {
+            let code = code.trim().to_uppercase();
+            if !is_currency_code(&code) {
+                return Err(RateTableError::InvalidFormat(format!("invalid currency code '{}'", code)));
+            }
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(RateTableError::InvalidFormat(format!("invalid rate for {}: {}", code, rate)));
+            }
+            normalized.insert(code, rate);
+        }
+        normalized.insert(base.clone(), 1.0);
+
+        Ok(RateTable { base, timestamp, rates: normalized, source: None })
+    }
+
+    /// Loads a table from disk, choosing the format from the file extension.
+    pub fn load(path: &Path) -> Result<Self, RateTableError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| RateTableError::Io(format!("{}: {}", path.display(), e)))?;
+
+        let is_csv = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let mut table = if is_csv {
+            Self::from_csv(&contents)?
+        } else {
+            Self::from_json(&contents)?
+        };
+        table.source = Some(path.to_path_buf());
+        Ok(table)
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, RateTableError> {
+        let table: JsonTable = serde_json::from_str(contents)
+            .map_err(|e| RateTableError::InvalidFormat(e.to_string()))?;
+        Self::new(&table.base, table.timestamp, table.rates)
+    }
+
+    pub fn from_csv(contents: &str) -> Result<Self, RateTableError> {
+        let mut base = None;
+        let mut timestamp = None;
+        let mut rates = HashMap::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some((key, value)) = comment.split_once(':') {
+                    match key.trim().to_lowercase().as_str() {
+                        "base" => base = Some(value.trim().to_string()),
+                        "timestamp" => timestamp = Some(value.trim().to_string()),
+                        _ => {},
+                    }
+                }
+                continue;
+            }
+
+            let (code, rate) = line.split_once(',').ok_or_else(|| RateTableError::InvalidFormat(
+                format!("line {}: expected 'currency,rate'", number + 1)
+            ))?;
+            match rate.trim().parse::<f64>() {
+                Ok(rate) => { rates.insert(code.to_string(), rate); },
+                // A header row such as `currency,rate` is allowed before any data.
+                Err(_) if rates.is_empty() => continue,
+                Err(_) => return Err(RateTableError::InvalidFormat(
+                    format!("line {}: invalid rate '{}'", number + 1, rate.trim())
+                )),
+            }
+        }
+
+        let base = base.ok_or_else(|| RateTableError::InvalidFormat("missing '# base: XXX' line".to_string()))?;
+        Self::new(&base, timestamp, rates)
+    }
+
+    /// Path the table was loaded from, if any, so it can be reloaded.
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    pub fn currencies(&self) -> Vec<&str> {
+        let mut codes: Vec<&str> = self.rates.keys().map(String::as_str).collect();
+        codes.sort();
+        codes
+    }
+
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Result<f64, EvalError> {
+        if from == to {
+            return Ok(amount);
+        }
+        Ok(amount / self.rate(from)? * self.rate(to)?)
+    }
+
+    fn rate(&self, code: &str) -> Result<f64, EvalError> {
+        self.rates.get(code).copied().ok_or_else(|| EvalError::CurrencyError(format!(
+            "no exchange rate for {} in the table{}", code, self.as_of()
+        )))
+    }
+
+    fn as_of(&self) -> String {
+        match &self.timestamp {
+            Some(ts) => format!(" (as of {})", ts),
+            None => String::new(),
+        }
+    }
+}
+
+/// An amount of money. `rates_as_of` records the timestamp of the rate table
+/// whenever a conversion was involved in producing the amount.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Money {
+    pub amount: f64,
+    pub currency: String,
+    pub rates_as_of: Option<String>,
+}
+
+impl Money {
+    pub fn new(amount: f64, currency: &str) -> Self {
+        Money { amount, currency: currency.to_string(), rates_as_of: None }
+    }
+
+    /// Converts to another currency, failing if no table is loaded or a rate is missing.
+    pub fn convert_to(&self, currency: &str, table: Option<&RateTable>) -> Result<Money, EvalError> {
+        if self.currency == currency {
+            return Ok(self.clone());
+        }
+        let table = table.ok_or_else(|| EvalError::CurrencyError(
+            "no exchange rate table loaded; use 'rates load <file>'".to_string()
+        ))?;
+        Ok(Money {
+            amount: table.convert(self.amount, &self.currency, currency)?,
+            currency: currency.to_string(),
+            rates_as_of: table.timestamp.clone().or_else(|| self.rates_as_of.clone()),
+        })
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.2} {}", self.amount, self.currency)?;
+        if let Some(ts) = &self.rates_as_of {
+            write!(f, " (rates as of {})", ts)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `s` looks like an ISO 4217 code: three uppercase ASCII letters.
+pub fn is_currency_code(s: &str) -> bool {
+    s.len() == 3 && s.chars().all(|c| c.is_ascii_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_table() {
+        let table = RateTable::from_json(
+            r#"{"base": "EUR", "timestamp": "2026-10-17", "rates": {"USD": 1.25, "GBP": 0.8}}"#
+        ).unwrap();
+        assert_eq!(table.timestamp.as_deref(), Some("2026-10-17"));
+        assert_eq!(table.convert(125.0, "USD", "EUR").unwrap(), 100.0);
+        assert_eq!(table.convert(100.0, "USD", "GBP").unwrap(), 64.0);
+        assert!(matches!(table.convert(1.0, "USD", "JPY"), Err(EvalError::CurrencyError(_))));
+    }
+
+    #[test]
+    fn test_csv_table() {
+        let table = RateTable::from_csv("# base: usd\n# timestamp: 2026-10-17\ncurrency,rate\nEUR,0.8\nJPY,150\n").unwrap();
+        assert_eq!(table.base, "USD");
+        assert_eq!(table.currencies(), vec!["EUR", "JPY", "USD"]);
+        assert_eq!(table.convert(2.0, "EUR", "JPY").unwrap(), 375.0);
+        assert!(RateTable::from_csv("EUR,0.8").is_err());
+        assert!(RateTable::from_csv("# base: USD\nEUR,-1").is_err());
+    }
+
+    #[test]
+    fn test_money_conversion_records_timestamp() {
+        let table = RateTable::from_csv("# base: EUR\n# timestamp: 2026-10-17\nUSD,1.25").unwrap();
+        let converted = Money::new(100.0, "USD").convert_to("EUR", Some(&table)).unwrap();
+        assert_eq!(converted.to_string(), "80.00 EUR (rates as of 2026-10-17)");
+        assert!(Money::new(1.0, "USD").convert_to("EUR", None).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Calendar dates, times of day and timestamps.
+//!
+//! Everything is in UTC; there is no time zone database. Durations are plain
+//! time quantities (`3 d`, `90 min`), so `now() + 90 min` and
+//! `2026-12-25 - today()` reuse the units machinery.
+
+use std::{fmt, time::{SystemTime, UNIX_EPOCH}};
+
+pub const SECONDS_PER_DAY: f64 = 86400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateKind {
+    /// A calendar day, `2026-10-17`.
+    Date,
+    /// A point in time, `2026-10-17T14:30:00Z`.
+    DateTime,
+    /// A time of day, `14:30`.
+    Time,
+}
+
+/// Dates and timestamps count seconds since 1970-01-01T00:00:00Z; times of day
+/// count seconds since midnight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateTime {
+    pub seconds: f64,
+    pub kind: DateKind,
+}
+
+impl DateTime {
+    pub fn date(year: i64, month: u32, day: u32) -> Result<Self, String> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return Err(format!("invalid date {:04}-{:02}-{:02}", year, month, day));
+        }
+        Ok(DateTime {
+            seconds: days_from_civil(year, month, day) as f64 * SECONDS_PER_DAY,
+            kind: DateKind::Date,
+        })
+    }
+
+    pub fn time(hour: u32, minute: u32, second: f64) -> Result<Self, String> {
+        if hour > 23 || minute > 59 || !(0.0..60.0).contains(&second) {
+            return Err(format!("invalid time {:02}:{:02}:{:02}", hour, minute, second));
+        }
+        Ok(DateTime {
+            seconds: hour as f64 * 3600.0 + minute as f64 * 60.0 + second,
+            kind: DateKind::Time,
+        })
+    }
+
+    pub fn now() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        DateTime { seconds, kind: DateKind::DateTime }
+    }
+
+    pub fn today() -> Self {
+        DateTime { seconds: Self::now().day_number() as f64 * SECONDS_PER_DAY, kind: DateKind::Date }
+    }
+
+    /// Parses `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM[:SS]` or `HH:MM[:SS]`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        if let Some((date, time)) = s.split_once('T') {
+            let date = Self::parse(date)?;
+            let time = Self::parse(time)?;
+            if date.kind != DateKind::Date || time.kind != DateKind::Time {
+                return Err(format!("invalid timestamp {}", s));
+            }
+            return Ok(date.at(time));
+        }
+
+        let invalid = || format!("invalid date or time {}", s);
+        if s.contains(':') {
+            let parts: Vec<&str> = s.split(':').collect();
+            let field = |i: usize| parts.get(i).map_or(Ok(0), |p| p.parse::<u32>()).map_err(|_| invalid());
+            if parts.len() > 3 {
+                return Err(invalid());
+            }
+            return Self::time(field(0)?, field(1)?, field(2)? as f64);
+        }
+
+        let parts: Vec<&str> = s.split('-').collect();
+        if parts.len() != 3 {
+            return Err(invalid());
+        }
+        let year = parts[0].parse().map_err(|_| invalid())?;
+        let month = parts[1].parse().map_err(|_| invalid())?;
+        let day = parts[2].parse().map_err(|_| invalid())?;
+        Self::date(year, month, day)
+    }
+
+    /// Combines a date with a time of day into a timestamp.
+    pub fn at(self, time: DateTime) -> DateTime {
+        DateTime { seconds: self.seconds + time.seconds, kind: DateKind::DateTime }
+    }
+
+    /// Moves by a number of seconds. Dates stay dates when moved by whole days,
+    /// and times of day wrap around midnight.
+    pub fn add_seconds(self, seconds: f64) -> DateTime {
+        match self.kind {
+            DateKind::Time => DateTime {
+                seconds: (self.seconds + seconds).rem_euclid(SECONDS_PER_DAY),
+                kind: DateKind::Time,
+            },
+            DateKind::Date if (seconds / SECONDS_PER_DAY).fract() == 0.0 => DateTime {
+                seconds: self.seconds + seconds,
+                kind: DateKind::Date,
+            },
+            _ => DateTime { seconds: self.seconds + seconds, kind: DateKind::DateTime },
+        }
+    }
+
+    /// Days since 1970-01-01.
+    pub fn day_number(&self) -> i64 {
+        (self.seconds / SECONDS_PER_DAY).floor() as i64
+    }
+
+    pub fn civil(&self) -> (i64, u32, u32) {
+        civil_from_days(self.day_number())
+    }
+
+    /// ISO weekday: 1 for Monday through 7 for Sunday.
+    pub fn weekday(&self) -> u32 {
+        // 1970-01-01 was a Thursday.
+        ((self.day_number() + 3).rem_euclid(7) + 1) as u32
+    }
+
+    /// ISO 8601 week number (1–53); the week belongs to the year of its Thursday.
+    pub fn iso_week(&self) -> u32 {
+        let thursday = self.day_number() - self.weekday() as i64 + 4;
+        let (year, _, _) = civil_from_days(thursday);
+        let ordinal = thursday - days_from_civil(year, 1, 1);
+        (ordinal / 7 + 1) as u32
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let clock = |f: &mut fmt::Formatter, seconds: f64| {
+            let seconds = seconds.rem_euclid(SECONDS_PER_DAY);
+            let whole = seconds.floor() as u64;
+            write!(f, "{:02}:{:02}:{:02}", whole / 3600, whole % 3600 / 60, whole % 60)?;
+            let millis = ((seconds - whole as f64) * 1000.0).round() as u64;
+            if millis > 0 && millis < 1000 {
+                write!(f, ".{:03}", millis)?;
+            }
+            Ok(())
+        };
+
+        match self.kind {
+            DateKind::Time => clock(f, self.seconds),
+            DateKind::Date => {
+                let (y, m, d) = self.civil();
+                write!(f, "{:04}-{:02}-{:02}", y, m, d)
+            },
+            DateKind::DateTime => {
+                let (y, m, d) = self.civil();
+                write!(f, "{:04}-{:02}-{:02}T", y, m, d)?;
+                clock(f, self.seconds)?;
+                write!(f, "Z")
+            },
+        }
+    }
+}
+
+/// Counts Monday–Friday days in `[from, to)`, negated when `to` comes first.
+pub fn business_days(from: &DateTime, to: &DateTime) -> i64 {
+    let (start, end) = (from.day_number(), to.day_number());
+    if end < start {
+        return -business_days(to, from);
+    }
+
+    let total = end - start;
+    let mut count = total / 7 * 5;
+    let start_weekday = from.weekday() as i64;
+    for offset in 0..total % 7 {
+        if (start_weekday - 1 + offset) % 7 < 5 {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// The most business days `add_business_days` moves by, which keeps the
+/// result well within the range of dates.
+pub const MAX_BUSINESS_DAYS: i64 = 1_000_000_000_000_000;
+
+/// Moves a date by `n` business days, skipping weekends. Whole weeks are
+/// taken at once, so only the last few days are walked.
+pub fn add_business_days(date: &DateTime, n: i64) -> DateTime {
+    let as_date = |day: i64| DateTime { seconds: day as f64 * SECONDS_PER_DAY, kind: DateKind::Date };
+    let mut day = date.day_number();
+    // From a weekend, counting goes as if from the Friday before, or going
+    // back, from the Monday after.
+    let weekday = date.weekday() as i64;
+    if n != 0 && weekday > 5 {
+        day += if n > 0 { 5 - weekday } else { 8 - weekday };
+    }
+
+    day += n / 5 * 7;
+    let mut remaining = (n % 5).abs();
+    while remaining > 0 {
+        day += n.signum();
+        if as_date(day).weekday() <= 5 {
+            remaining -= 1;
+        }
+    }
+    as_date(day)
+}
+
+/// Formats a number of seconds as an ISO 8601 duration, e.g. `P3DT4H` or `PT1M30S`.
+pub fn iso_duration(seconds: f64) -> String {
+    let sign = if seconds < 0.0 { "-" } else { "" };
+    let mut rest = seconds.abs();
+    let days = (rest / SECONDS_PER_DAY).floor();
+    rest -= days * SECONDS_PER_DAY;
+    let hours = (rest / 3600.0).floor();
+    rest -= hours * 3600.0;
+    let minutes = (rest / 60.0).floor();
+    rest -= minutes * 60.0;
+    let secs = (rest * 1000.0).round() / 1000.0;
+
+    let mut out = format!("{}P", sign);
+    if days > 0.0 {
+        out.push_str(&format!("{}D", days));
+    }
+    if hours > 0.0 || minutes > 0.0 || secs > 0.0 || days == 0.0 {
+        out.push('T');
+        if hours > 0.0 {
+            out.push_str(&format!("{}H", hours));
+        }
+        if minutes > 0.0 {
+            out.push_str(&format!("{}M", minutes));
+        }
+        if secs > 0.0 || (hours == 0.0 && minutes == 0.0) {
+            out.push_str(&format!("{}S", secs));
+        }
+    }
+    out
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_round_trip() {
+        let date = DateTime::parse("2026-10-17").unwrap();
+        assert_eq!(date.civil(), (2026, 10, 17));
+        assert_eq!(date.to_string(), "2026-10-17");
+        assert_eq!(DateTime::parse("2024-02-29").unwrap().to_string(), "2024-02-29");
+        assert!(DateTime::parse("2025-02-29").is_err());
+        assert!(DateTime::parse("2026-13-01").is_err());
+    }
+
+    #[test]
+    fn test_weekday_and_iso_week() {
+        let date = DateTime::parse("2026-10-17").unwrap();
+        assert_eq!(date.weekday(), 6);
+        assert_eq!(date.iso_week(), 42);
+        // 2027-01-01 is a Friday, still in week 53 of 2026.
+        assert_eq!(DateTime::parse("2027-01-01").unwrap().iso_week(), 53);
+        assert_eq!(DateTime::parse("2024-12-30").unwrap().iso_week(), 1);
+    }
+
+    #[test]
+    fn test_business_days() {
+        let monday = DateTime::parse("2026-10-12").unwrap();
+        let next_monday = DateTime::parse("2026-10-19").unwrap();
+        assert_eq!(business_days(&monday, &next_monday), 5);
+        assert_eq!(business_days(&next_monday, &monday), -5);
+        let friday = DateTime::parse("2026-10-16").unwrap();
+        assert_eq!(add_business_days(&friday, 1).to_string(), "2026-10-19");
+        // Whole weeks, and starts on a weekend.
+        assert_eq!(add_business_days(&friday, 10).to_string(), "2026-10-30");
+        assert_eq!(add_business_days(&friday, -7).to_string(), "2026-10-07");
+        let saturday = friday.add_seconds(SECONDS_PER_DAY);
+        assert_eq!(add_business_days(&saturday, 1).to_string(), "2026-10-19");
+        assert_eq!(add_business_days(&saturday, 5).to_string(), "2026-10-23");
+        assert_eq!(add_business_days(&saturday, -1).to_string(), "2026-10-16");
+        assert_eq!(add_business_days(&saturday, 0).to_string(), "2026-10-17");
+        assert_eq!(add_business_days(&friday, 5_000_000).day_number() - friday.day_number(), 7_000_000);
+    }
+
+    #[test]
+    fn test_timestamps_and_durations() {
+        let ts = DateTime::parse("2026-10-17T14:30").unwrap();
+        assert_eq!(ts.to_string(), "2026-10-17T14:30:00Z");
+        assert_eq!(DateTime::parse("23:30").unwrap().add_seconds(3600.0).to_string(), "00:30:00");
+        assert_eq!(iso_duration(3.0 * SECONDS_PER_DAY + 4.0 * 3600.0), "P3DT4H");
+        assert_eq!(iso_duration(90.0), "PT1M30S");
+        assert_eq!(iso_duration(-SECONDS_PER_DAY), "-P1D");
+    }
+}
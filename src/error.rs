@@ -1,4 +1,4 @@
-use std::{error::Error, fmt, usize};
+use std::{error::Error, fmt};
 
 /// Error types for the application.
 
@@ -6,6 +6,7 @@ use std::{error::Error, fmt, usize};
 pub enum LexError {
     UnexpectedCharacter(char, usize),
     InvalidNumber(String),
+    InvalidDate(String),
 }
 
 impl fmt::Display for LexError {
@@ -17,6 +18,9 @@ impl fmt::Display for LexError {
             LexError::InvalidNumber(num_str) => {
                 write!(f, "Invalid number format: '{}'", num_str)
             },
+            LexError::InvalidDate(date_str) => {
+                write!(f, "Invalid date or time: '{}'", date_str)
+            },
         }
     }
 }
@@ -63,9 +67,13 @@ impl Error for ParseError {}
 #[derive(Debug, Clone, PartialEq)]
 pub enum EvalError {
     UndefinedVariable(String),
+    UndefinedFunction(String),
     DivisionByZero,
     InvalidArguments(String),
-    MathError(String)
+    MathError(String),
+    UnitError(String),
+    CurrencyError(String),
+    TypeError(String),
 }
 
 impl fmt::Display for EvalError {
@@ -74,6 +82,9 @@ impl fmt::Display for EvalError {
             EvalError::UndefinedVariable(var) => {
                 write!(f, "Undefined variable encountered: '{}'", var)
             },
+            EvalError::UndefinedFunction(name) => {
+                write!(f, "Undefined function encountered: '{}'", name)
+            },
             EvalError::DivisionByZero => {
                 write!(f, "Division by zero error")
             },
@@ -83,8 +94,43 @@ impl fmt::Display for EvalError {
             EvalError::MathError(msg) => {
                 write!(f, "Mathematical error: '{}'", msg)
             },
+            EvalError::UnitError(msg) => {
+                write!(f, "Unit error: {}", msg)
+            },
+            EvalError::CurrencyError(msg) => {
+                write!(f, "Currency error: {}", msg)
+            },
+            EvalError::TypeError(msg) => {
+                write!(f, "Type error: {}", msg)
+            },
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateTableError {
+    Io(String),
+    InvalidFormat(String),
+    NotLoaded,
+}
+
+impl fmt::Display for RateTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RateTableError::Io(msg) => {
+                write!(f, "Could not read rate table: {}", msg)
+            },
+            RateTableError::InvalidFormat(msg) => {
+                write!(f, "Invalid rate table: {}", msg)
+            },
+            RateTableError::NotLoaded => {
+                write!(f, "No rate table has been loaded from a file")
+            },
         }
     }
 }
 
-impl Error for EvalError {}
\ No newline at end of file
+impl Error for RateTableError {}
\ No newline at end of file
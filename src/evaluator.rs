@@ -1,15 +1,36 @@
-/// Evaluator module for processing expressions.
+//! Evaluator module for processing expressions.
 
-use crate::{ast::{BinOp, Expr, Function, UnOp}, context::Context, error::EvalError, functions};
+use std::cmp::Ordering;
+
+use crate::{analysis::{self, AnalysisOptions}, ast::{BinOp, ConversionTarget, Expr, Function, UnOp}, context::{Context, UserFunction}, currency::Money, datetime::{self, DateKind, DateTime}, distributions::{self, Distribution}, compile::CompiledExpr, error::EvalError, functions, ieee754, ode::{self, OdeOptions}, optimize::{self, OptimizeOptions}, plot::{self, SampleOptions}, symbolic, table, units::{Dimension, Quantity, Unit}, value::Value};
+
+/// Nesting limit for calls to user-defined functions, so runaway recursion
+/// is an error instead of using up memory.
+const MAX_CALL_DEPTH: usize = 10_000;
+
+/// Stack that must be left before evaluating a subexpression, enough for the
+/// largest frames between two calls to `eval_value`; below it the stack is
+/// extended by another `STACK_SEGMENT`. Deeply nested expressions and deep
+/// recursion then need memory rather than a large thread stack.
+const STACK_RED_ZONE: usize = 128 * 1024;
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
 
 pub struct Evaluator {
     context: Context,
+    depth: usize,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Evaluator {
     pub fn new() -> Self {
         Evaluator {
-            context: Context::new()
+            context: Context::new(),
+            depth: 0,
         }
     }
 
@@ -21,53 +42,443 @@ impl Evaluator {
         &mut self.context
     }
 
-    pub fn evaluate(&mut self, expr: &Expr) -> Result<f64, EvalError> {
-        let result = self.eval(expr)?;
-        self.context.set_ans(result);
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value, EvalError> {
+        let result = self.eval_value(expr)?;
+        self.context.set_ans(result.clone());
         Ok(result)
     }
 
+    /// Evaluates an expression that must produce a plain number.
     pub fn eval(&mut self, expr: &Expr) -> Result<f64, EvalError> {
+        self.eval_value(expr)?.as_number()
+    }
+
+    /// Evaluates an expression with some variables temporarily bound, restoring
+    /// their previous values (or absence) afterwards.
+    pub fn eval_with(&mut self, bindings: &[(&str, Value)], expr: &Expr) -> Result<Value, EvalError> {
+        let saved: Vec<Option<Value>> = bindings.iter()
+            .map(|(name, value)| {
+                let previous = self.context.get(name).cloned();
+                self.context.set(name.to_string(), value.clone());
+                previous
+            })
+            .collect();
+
+        let result = self.eval_value(expr);
+
+        for ((name, _), previous) in bindings.iter().zip(saved).rev() {
+            match previous {
+                Some(value) => self.context.set(name.to_string(), value),
+                None => {
+                    self.context.remove(name);
+                },
+            }
+        }
+        result
+    }
+
+    pub fn eval_value(&mut self, expr: &Expr) -> Result<Value, EvalError> {
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || self.eval_expr(expr))
+    }
+
+    fn eval_expr(&mut self, expr: &Expr) -> Result<Value, EvalError> {
         match expr {
-            Expr::Number(n) => Ok(*n),
+            Expr::Number(n) => Ok(Value::Number(*n)),
+
+            Expr::Integer(i) => Ok(Value::Integer(self.context.get_word_size().wrap(*i))),
+
+            Expr::Bool(b) => Ok(Value::Bool(*b)),
+
+            Expr::DateTime(dt) => Ok(Value::DateTime(*dt)),
 
             Expr::Variable(name) => {
                 self.context.get(name)
+                    .cloned()
                     .ok_or_else(|| EvalError::UndefinedVariable(name.clone()))
             },
 
+            Expr::BinaryOp { left, op: op @ (BinOp::And | BinOp::Or), right } => self.eval_logical(op, left, right),
+
+            Expr::BinaryOp { left, op: op @ (BinOp::Add | BinOp::Subtract), right }
+                if matches!(**right, Expr::PostfixOp { op: UnOp::Percent, .. }) => {
+                self.eval_percent_change(op, left, right)
+            },
+
             Expr::BinaryOp { left, op, right } => {
-                let left_val = self.eval(left)?;
-                let right_val = self.eval(right)?;
-                Self::eval_binary_op(op, left_val, right_val)
+                let left_val = self.eval_value(left)?;
+                let right_val = self.eval_value(right)?;
+                self.eval_binary_op(op, left_val, right_val)
             },
 
             Expr::UnaryOp { op, expr } => {
-                let val = self.eval(expr)?;
-                Self::eval_unary_op(op, val)
+                let val = self.eval_value(expr)?;
+                self.eval_unary_op(op, val)
             },
 
             Expr::PostfixOp { expr, op } => {
-                let val = self.eval(expr)?;
-                Self::eval_unary_op(op, val)
+                let val = self.eval_value(expr)?;
+                self.eval_unary_op(op, val)
             },
 
-            Expr::FunctionCall { func, args } => {
-                let arg_values: Result<Vec<f64>, _> = args.iter().map(|arg| self.eval(arg)).collect();
+            Expr::FunctionCall { func, args } => self.eval_function_call(func, args),
 
-                let arg_values = arg_values?;
-                self.eval_function(func, &arg_values)
-            },
+            Expr::List(items) => items.iter()
+                .map(|item| self.eval_value(item))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
 
             Expr::Assignment { name, value } => {
-                let result = self.eval(value)?;
-                self.context.set(name.clone(), result);
+                let result = self.eval_value(value)?;
+                self.context.set(name.clone(), result.clone());
                 Ok(result)
+            },
+
+            Expr::FunctionDef { name, params, body } => {
+                self.context.define_function(name.clone(), UserFunction {
+                    params: params.clone(),
+                    body: (**body).clone(),
+                });
+                Ok(Value::Function { name: name.clone(), params: params.clone() })
+            },
+
+            Expr::Equation { .. } => Err(EvalError::TypeError(
+                "an equation has no value; use == to compare both sides".to_string()
+            )),
+
+            Expr::Piecewise { pieces, otherwise } => self.eval_piecewise(pieces, otherwise.as_deref()),
+
+            Expr::Quantity { value, unit } => {
+                let magnitude = self.eval(value)?;
+                Ok(Value::Quantity(Quantity::new(magnitude, unit)))
+            },
+
+            Expr::Money { value, currency } => {
+                let amount = self.eval(value)?;
+                Ok(Value::Money(Money::new(amount, currency)))
+            },
+
+            Expr::Conversion { expr, target } => self.eval_conversion(expr, target),
+        }
+    }
+
+    /// Short-circuit: the right side is only evaluated when it decides the result.
+    fn eval_logical(&mut self, op: &BinOp, left: &Expr, right: &Expr) -> Result<Value, EvalError> {
+        let left_val = self.eval_value(left)?.as_bool()?;
+        let decided = if *op == BinOp::And { !left_val } else { left_val };
+        if decided {
+            return Ok(Value::Bool(left_val));
+        }
+        Ok(Value::Bool(self.eval_value(right)?.as_bool()?))
+    }
+
+    fn eval_function_call(&mut self, func: &Function, args: &[Expr]) -> Result<Value, EvalError> {
+        if func.is_lazy() {
+            return self.eval_lazy_function(func, args);
+        }
+
+        let arg_values: Result<Vec<Value>, _> = args.iter().map(|arg| self.eval_value(arg)).collect();
+
+        let arg_values = arg_values?;
+        if let Function::User(name) = func {
+            return self.call_user_function(name, &arg_values);
+        }
+        if func.is_random() {
+            return self.eval_random_function(func, &arg_values);
+        }
+        if func.is_calendar() {
+            return Self::eval_calendar_function(func, &arg_values);
+        }
+        if func.is_ieee754() {
+            return self.eval_ieee754_function(func, &arg_values);
+        }
+        if arg_values.iter().any(|v| v.as_number().is_err()) {
+            return Self::eval_quantity_function(func, arg_values);
+        }
+        let numbers: Result<Vec<f64>, _> = arg_values.iter().map(Value::as_number).collect();
+        self.eval_function(func, &numbers?).map(Value::Number)
+    }
+
+    fn eval_piecewise(&mut self, pieces: &[(Expr, Expr)], otherwise: Option<&Expr>) -> Result<Value, EvalError> {
+        for (value, condition) in pieces {
+            if self.eval_value(condition)?.as_bool()? {
+                return self.eval_value(value);
             }
         }
+        match otherwise {
+            Some(value) => self.eval_value(value),
+            None => Err(EvalError::MathError("no piece of the piecewise expression applies".to_string())),
+        }
+    }
+
+    /// `a + p%` and `a - p%` add or subtract `p/100` of `a`, so the percentage is
+    /// taken of whatever the left side is, including money and quantities.
+    fn eval_percent_change(&mut self, op: &BinOp, left: &Expr, right: &Expr) -> Result<Value, EvalError> {
+        let base = self.eval_value(left)?;
+        let fraction = self.eval_value(right)?;
+        let change = self.eval_binary_op(&BinOp::Multiply, base.clone(), fraction)?;
+        self.eval_binary_op(op, base, change)
+    }
+
+    fn eval_conversion(&mut self, expr: &Expr, target: &ConversionTarget) -> Result<Value, EvalError> {
+        let value = self.eval_value(expr)?.into_plain();
+        match (target, value) {
+            (ConversionTarget::Currency(code), Value::Money(money)) => {
+                Ok(Value::Money(money.convert_to(code, self.context.get_rates())?))
+            },
+            (ConversionTarget::Currency(code), value) => Err(EvalError::CurrencyError(format!(
+                "cannot convert {} to {}", value, code
+            ))),
+            (ConversionTarget::Unit(unit), value) => {
+                Ok(Value::Quantity(value.into_quantity()?.convert_to(unit)?))
+            },
+            (ConversionTarget::Base(base), value) => {
+                value.in_base(*base, self.context.get_word_size())
+            },
+        }
     }
 
-    fn eval_binary_op(op: &BinOp, left: f64, right: f64) -> Result<f64, EvalError> {
+    fn eval_binary_op(&self, op: &BinOp, left: Value, right: Value) -> Result<Value, EvalError> {
+        let (left, right) = (left.into_plain(), right.into_plain());
+        if matches!(left, Value::List(_)) || matches!(right, Value::List(_)) {
+            return Err(EvalError::TypeError(format!("cannot apply {:?} to {} and {}", op, left, right)));
+        }
+        if op.is_comparison() {
+            return self.eval_comparison(op, left, right);
+        }
+        if op.is_bitwise() {
+            return self.eval_bitwise_op(op, &left, &right);
+        }
+        if matches!(left, Value::Integer(_)) || matches!(right, Value::Integer(_)) {
+            let word = self.context.get_word_size();
+            let whole = |v: &Value| match v {
+                Value::Integer(i) => Some(*i),
+                Value::Number(n) => word.from_f64(*n).ok(),
+                _ => None,
+            };
+            if let (Some(l), Some(r)) = (whole(&left), whole(&right)) {
+                return self.eval_integer_op(op, l, r);
+            }
+        }
+        // Mixing an integer with fractions or units drops back to floating point.
+        let as_float = |v: Value| match v {
+            Value::Integer(i) => Value::Number(i as f64),
+            other => other,
+        };
+        let (left, right) = (as_float(left), as_float(right));
+
+        if let (Value::Number(l), Value::Number(r)) = (&left, &right) {
+            return Self::eval_numeric_op(op, *l, *r).map(Value::Number);
+        }
+        if matches!(left, Value::Money(_)) || matches!(right, Value::Money(_)) {
+            return self.eval_money_op(op, left, right);
+        }
+        if matches!(left, Value::DateTime(_)) || matches!(right, Value::DateTime(_)) {
+            return Self::eval_datetime_op(op, left, right);
+        }
+
+        let (left, right) = (left.into_quantity()?, right.into_quantity()?);
+        let result = match op {
+            BinOp::Add => left.checked_add(right)?,
+            BinOp::Subtract => left.checked_sub(right)?,
+            BinOp::Multiply => left.checked_mul(right)?,
+            BinOp::Divide => left.checked_div(right)?,
+            BinOp::Power => {
+                if !right.dim.is_dimensionless() {
+                    return Err(EvalError::UnitError("exponent must be dimensionless".to_string()));
+                }
+                left.checked_pow(right.value)?
+            },
+            BinOp::FloorDivide | BinOp::Modulo | BinOp::Remainder => {
+                if left.dim != right.dim {
+                    return Err(EvalError::UnitError(format!(
+                        "cannot apply {:?} to {} and {}", op, left, right
+                    )));
+                }
+                let value = Self::eval_numeric_op(op, left.value, right.value)?;
+                if *op == BinOp::FloorDivide {
+                    return Ok(Value::Number(value));
+                }
+                Quantity { value, ..left }
+            },
+            _ => unreachable!("bitwise, comparison and logical operators are handled above"),
+        };
+        Ok(Value::from_quantity(result))
+    }
+
+    /// Numbers, quantities of the same dimension, money and dates compare by
+    /// value (see `functions::compare` for the tolerance); booleans only support
+    /// `==` and `!=`.
+    fn eval_comparison(&self, op: &BinOp, left: Value, right: Value) -> Result<Value, EvalError> {
+        if let (Value::Bool(a), Value::Bool(b)) = (&left, &right) {
+            return match op {
+                BinOp::Equal => Ok(Value::Bool(a == b)),
+                BinOp::NotEqual => Ok(Value::Bool(a != b)),
+                _ => Err(EvalError::TypeError("booleans can only be compared with == and !=".to_string())),
+            };
+        }
+
+        let ordering = self.order(left, right)?;
+        let result = match op {
+            BinOp::Equal => ordering == Some(Ordering::Equal),
+            BinOp::NotEqual => ordering != Some(Ordering::Equal),
+            BinOp::Less => ordering == Some(Ordering::Less),
+            BinOp::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            BinOp::Greater => ordering == Some(Ordering::Greater),
+            BinOp::GreaterEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            _ => unreachable!("not a comparison operator"),
+        };
+        Ok(Value::Bool(result))
+    }
+
+    fn order(&self, left: Value, right: Value) -> Result<Option<Ordering>, EvalError> {
+        match (left, right) {
+            (Value::Integer(a), Value::Integer(b)) => Ok(Some(a.cmp(&b))),
+            (Value::Money(a), Value::Money(b)) => {
+                let b = b.convert_to(&a.currency, self.context.get_rates())?;
+                Ok(functions::compare(a.amount, b.amount))
+            },
+            (Value::DateTime(a), Value::DateTime(b))
+                if (a.kind == DateKind::Time) == (b.kind == DateKind::Time) => {
+                Ok(functions::compare(a.seconds, b.seconds))
+            },
+            (left @ (Value::DateTime(_) | Value::Bool(_)), right)
+            | (left, right @ (Value::DateTime(_) | Value::Bool(_))) => Err(EvalError::TypeError(format!(
+                "cannot compare {} with {}", left, right
+            ))),
+            (left, right) => {
+                let (a, b) = (left.into_quantity()?, right.into_quantity()?);
+                if a.dim != b.dim {
+                    return Err(EvalError::UnitError(format!("cannot compare {} with {}", a, b)));
+                }
+                Ok(functions::compare(a.value, b.value))
+            },
+        }
+    }
+
+    /// Bitwise operators accept integers and whole numbers and always produce an
+    /// integer wrapped to the context's word size.
+    fn eval_bitwise_op(&self, op: &BinOp, left: &Value, right: &Value) -> Result<Value, EvalError> {
+        let word = self.context.get_word_size();
+        let (a, b) = (self.to_integer(left)?, self.to_integer(right)?);
+        let shift_amount = || {
+            if b < 0 {
+                return Err(EvalError::MathError(format!("negative shift amount {}", b)));
+            }
+            Ok(b.min(word.bits as i128) as u32)
+        };
+
+        let result = match op {
+            BinOp::BitAnd => a & b,
+            BinOp::BitOr => a | b,
+            BinOp::BitXor => a ^ b,
+            BinOp::ShiftLeft => word.shift_left(a, shift_amount()?),
+            BinOp::ShiftRight => word.shift_right(a, shift_amount()?),
+            _ => unreachable!("not a bitwise operator"),
+        };
+        Ok(Value::Integer(word.wrap(result)))
+    }
+
+    /// Integer arithmetic wraps around in two's complement; division yields a plain number,
+    /// while `//`, `mod` and `rem` stay integral.
+    fn eval_integer_op(&self, op: &BinOp, left: i128, right: i128) -> Result<Value, EvalError> {
+        let word = self.context.get_word_size();
+        let result = match op {
+            BinOp::Add => left.wrapping_add(right),
+            BinOp::Subtract => left.wrapping_sub(right),
+            BinOp::Multiply => left.wrapping_mul(right),
+            BinOp::Power if (0..=u32::MAX as i128).contains(&right) => left.wrapping_pow(right as u32),
+            BinOp::FloorDivide | BinOp::Modulo | BinOp::Remainder if right == 0 => {
+                return Err(EvalError::DivisionByZero);
+            },
+            BinOp::FloorDivide => {
+                let quotient = left.wrapping_div(right);
+                if left.wrapping_rem(right) != 0 && (left < 0) != (right < 0) { quotient - 1 } else { quotient }
+            },
+            BinOp::Modulo => left.wrapping_rem_euclid(right),
+            BinOp::Remainder => left.wrapping_rem(right),
+            _ => return Self::eval_numeric_op(op, left as f64, right as f64).map(Value::Number),
+        };
+        Ok(Value::Integer(word.wrap(result)))
+    }
+
+    fn to_integer(&self, value: &Value) -> Result<i128, EvalError> {
+        let word = self.context.get_word_size();
+        match value {
+            Value::Integer(i) => Ok(word.wrap(*i)),
+            other => word.from_f64(other.as_number()?),
+        }
+    }
+
+    /// Money can be added to or divided by money (converting the right side to the
+    /// left side's currency) and scaled by plain numbers.
+    fn eval_money_op(&self, op: &BinOp, left: Value, right: Value) -> Result<Value, EvalError> {
+        let rates = self.context.get_rates();
+        match (op, left, right) {
+            (BinOp::Add | BinOp::Subtract, Value::Money(a), Value::Money(b)) => {
+                let b = b.convert_to(&a.currency, rates)?;
+                let amount = if *op == BinOp::Add { a.amount + b.amount } else { a.amount - b.amount };
+                Ok(Value::Money(Money {
+                    amount,
+                    currency: a.currency,
+                    rates_as_of: b.rates_as_of.or(a.rates_as_of),
+                }))
+            },
+            (BinOp::Multiply, Value::Money(m), Value::Number(n))
+            | (BinOp::Multiply, Value::Number(n), Value::Money(m)) => {
+                Ok(Value::Money(Money { amount: m.amount * n, ..m }))
+            },
+            (BinOp::Divide, Value::Money(m), Value::Number(n)) => {
+                if n == 0.0 {
+                    return Err(EvalError::DivisionByZero);
+                }
+                Ok(Value::Money(Money { amount: m.amount / n, ..m }))
+            },
+            (BinOp::Divide, Value::Money(a), Value::Money(b)) => {
+                let b = b.convert_to(&a.currency, rates)?;
+                if b.amount == 0.0 {
+                    return Err(EvalError::DivisionByZero);
+                }
+                Ok(Value::Number(a.amount / b.amount))
+            },
+            (op, left, right) => Err(EvalError::CurrencyError(format!(
+                "cannot apply {:?} to {} and {}", op, left, right
+            ))),
+        }
+    }
+
+    /// Dates move by time quantities, a date plus a time of day gives a timestamp,
+    /// and the difference of two dates is a duration in days.
+    fn eval_datetime_op(op: &BinOp, left: Value, right: Value) -> Result<Value, EvalError> {
+        match (op, left, right) {
+            (BinOp::Add, Value::DateTime(dt), Value::Quantity(q))
+            | (BinOp::Add, Value::Quantity(q), Value::DateTime(dt)) if q.dim == Dimension::TIME => {
+                Ok(Value::DateTime(dt.add_seconds(q.value)))
+            },
+            (BinOp::Subtract, Value::DateTime(dt), Value::Quantity(q)) if q.dim == Dimension::TIME => {
+                Ok(Value::DateTime(dt.add_seconds(-q.value)))
+            },
+            (BinOp::Add, Value::DateTime(date), Value::DateTime(time))
+            | (BinOp::Add, Value::DateTime(time), Value::DateTime(date))
+                if date.kind == DateKind::Date && time.kind == DateKind::Time => {
+                Ok(Value::DateTime(date.at(time)))
+            },
+            (BinOp::Subtract, Value::DateTime(a), Value::DateTime(b))
+                if (a.kind == DateKind::Time) == (b.kind == DateKind::Time) => {
+                let unit = if a.kind == DateKind::Time { "h" } else { "d" };
+                Ok(Value::Quantity(Quantity {
+                    value: a.seconds - b.seconds,
+                    dim: Dimension::TIME,
+                    unit: Unit::lookup(unit),
+                    temperature: None,
+                }))
+            },
+            (op, left, right) => Err(EvalError::TypeError(format!(
+                "cannot apply {:?} to {} and {}", op, left, right
+            ))),
+        }
+    }
+
+    fn eval_numeric_op(op: &BinOp, left: f64, right: f64) -> Result<f64, EvalError> {
         match op {
             BinOp::Add => Ok(left + right),
             BinOp::Subtract => Ok(left - right),
@@ -80,14 +491,57 @@ impl Evaluator {
                 }
             },
             BinOp::Power => Ok(left.powf(right)),
+            BinOp::FloorDivide | BinOp::Modulo | BinOp::Remainder if right == 0.0 => Err(EvalError::DivisionByZero),
+            BinOp::FloorDivide => Ok((left / right).floor()),
+            BinOp::Modulo => Ok(left.rem_euclid(right)),
+            BinOp::Remainder => Ok(left % right),
+            _ => Err(EvalError::InvalidArguments(format!("{:?} expects integer operands", op))),
         }
     }
 
-    fn eval_unary_op(op: &UnOp, val: f64) -> Result<f64, EvalError> {
-        match op {
-            UnOp::Positive => Ok(val),
-            UnOp::Negate => Ok(-val),
-            UnOp::Factorial => functions::factorial(val),
+    fn eval_unary_op(&self, op: &UnOp, val: Value) -> Result<Value, EvalError> {
+        let word = self.context.get_word_size();
+        match (op, val.into_plain()) {
+            (UnOp::Positive, val) => Ok(val),
+            (UnOp::Not, val) => Ok(Value::Bool(!val.as_bool()?)),
+            (UnOp::BitNot, val) => Ok(Value::Integer(word.wrap(!self.to_integer(&val)?))),
+            (UnOp::Negate, Value::Number(n)) => Ok(Value::Number(-n)),
+            (UnOp::Negate, Value::Integer(i)) => Ok(Value::Integer(word.wrap(i.wrapping_neg()))),
+            (UnOp::Negate, Value::Quantity(q)) => Ok(Value::Quantity(q.negate())),
+            (UnOp::Negate, Value::Money(m)) => Ok(Value::Money(Money { amount: -m.amount, ..m })),
+            (UnOp::Negate, Value::DateTime(dt)) => Err(EvalError::TypeError(format!("cannot negate the date {}", dt))),
+            (UnOp::Negate, other) => Err(EvalError::TypeError(format!("cannot negate {}", other))),
+            (UnOp::Factorial, val) => functions::factorial(val.as_number()?).map(Value::Number),
+            (UnOp::Percent, val) => Ok(Value::Number(val.as_number()? / 100.0)),
+        }
+    }
+
+    /// Functions that accept dimensioned arguments; everything else requires plain numbers.
+    fn eval_quantity_function(func: &Function, args: Vec<Value>) -> Result<Value, EvalError> {
+        match func {
+            Function::Abs => {
+                Self::validate_args(&args, 1, "abs")?;
+                Ok(Value::Quantity(args[0].clone().into_quantity()?.abs()))
+            },
+            Function::Sqrt => {
+                Self::validate_args(&args, 1, "sqrt")?;
+                let q = args[0].clone().into_quantity()?;
+                if q.value < 0.0 {
+                    return Err(EvalError::MathError("sqrt of negative number".to_string()));
+                }
+                Ok(Value::from_quantity(q.checked_pow(0.5)?))
+            },
+            Function::Hypot => {
+                Self::validate_args(&args, 2, "hypot")?;
+                let (a, b) = (args[0].clone().into_quantity()?, args[1].clone().into_quantity()?);
+                if a.dim != b.dim {
+                    return Err(EvalError::UnitError(format!("cannot take hypot of {} and {}", a, b)));
+                }
+                Ok(Value::from_quantity(Quantity { value: a.value.hypot(b.value), ..a }))
+            },
+            _ => Err(args.iter()
+                .find_map(|v| v.as_number().err())
+                .unwrap_or_else(|| EvalError::InvalidArguments(format!("{:?}", func)))),
         }
     }
 
@@ -147,6 +601,120 @@ impl Evaluator {
                     self.get_context().get_angle_mode()
                 ))
             },
+            Function::Sec => {
+                Self::validate_args(args, 1, "sec")?;
+                functions::sec(args[0], self.get_context().get_angle_mode())
+            },
+            Function::Csc => {
+                Self::validate_args(args, 1, "csc")?;
+                functions::csc(args[0], self.get_context().get_angle_mode())
+            },
+            Function::Cot => {
+                Self::validate_args(args, 1, "cot")?;
+                functions::cot(args[0], self.get_context().get_angle_mode())
+            },
+            Function::Asec => {
+                Self::validate_args(args, 1, "asec")?;
+                Ok(functions::from_radians(functions::asec(args[0])?, self.get_context().get_angle_mode()))
+            },
+            Function::Acsc => {
+                Self::validate_args(args, 1, "acsc")?;
+                Ok(functions::from_radians(functions::acsc(args[0])?, self.get_context().get_angle_mode()))
+            },
+            Function::Acot => {
+                Self::validate_args(args, 1, "acot")?;
+                Ok(functions::from_radians(functions::acot(args[0]), self.get_context().get_angle_mode()))
+            },
+            Function::Atan2 => {
+                Self::validate_args(args, 2, "atan2")?;
+                Ok(functions::from_radians(functions::atan2(args[0], args[1])?, self.get_context().get_angle_mode()))
+            },
+            Function::Hypot => {
+                Self::validate_args(args, 2, "hypot")?;
+                Ok(args[0].hypot(args[1]))
+            },
+            Function::Erf => {
+                Self::validate_args(args, 1, "erf")?;
+                Ok(functions::erf(args[0]))
+            },
+            Function::Erfc => {
+                Self::validate_args(args, 1, "erfc")?;
+                Ok(functions::erfc(args[0]))
+            },
+            Function::Erfinv => {
+                Self::validate_args(args, 1, "erfinv")?;
+                functions::erfinv(args[0])
+            },
+            Function::BesselJ => {
+                Self::validate_args(args, 2, "besselj")?;
+                functions::bessel_j(args[0], args[1])
+            },
+            Function::BesselY => {
+                Self::validate_args(args, 2, "bessely")?;
+                functions::bessel_y(args[0], args[1])
+            },
+            Function::BesselI => {
+                Self::validate_args(args, 2, "besseli")?;
+                functions::bessel_i(args[0], args[1])
+            },
+            Function::BesselK => {
+                Self::validate_args(args, 2, "besselk")?;
+                functions::bessel_k(args[0], args[1])
+            },
+            Function::Zeta => {
+                Self::validate_args(args, 1, "zeta")?;
+                functions::zeta(args[0])
+            },
+            Function::LambertW => match args {
+                [x] => functions::lambert_w(*x, 0),
+                [x, branch] if branch.fract() == 0.0 => functions::lambert_w(*x, *branch as i32),
+                [_, branch] => Err(EvalError::MathError(format!("lambertw branch must be 0 or -1, got {}", branch))),
+                _ => Err(EvalError::InvalidArguments(
+                    format!("lambertw expects 1 or 2 argument(s), got {}", args.len())
+                )),
+            },
+            Function::EllipK => {
+                Self::validate_args(args, 1, "ellipk")?;
+                functions::elliptic_k(args[0])
+            },
+            Function::EllipE => {
+                Self::validate_args(args, 1, "ellipe")?;
+                functions::elliptic_e(args[0])
+            },
+            Function::Ei => {
+                Self::validate_args(args, 1, "ei")?;
+                functions::exp_integral_ei(args[0])
+            },
+            Function::Expint => {
+                Self::validate_args(args, 1, "expint")?;
+                functions::exp_integral_e1(args[0])
+            },
+            Function::Distribution(dist, function) => distributions::evaluate(*dist, *function, args),
+            // Hyperbolic functions take plain numbers, not angles.
+            Function::Sinh => {
+                Self::validate_args(args, 1, "sinh")?;
+                Ok(args[0].sinh())
+            },
+            Function::Cosh => {
+                Self::validate_args(args, 1, "cosh")?;
+                Ok(args[0].cosh())
+            },
+            Function::Tanh => {
+                Self::validate_args(args, 1, "tanh")?;
+                Ok(args[0].tanh())
+            },
+            Function::Asinh => {
+                Self::validate_args(args, 1, "asinh")?;
+                Ok(args[0].asinh())
+            },
+            Function::Acosh => {
+                Self::validate_args(args, 1, "acosh")?;
+                functions::acosh(args[0])
+            },
+            Function::Atanh => {
+                Self::validate_args(args, 1, "atanh")?;
+                functions::atanh(args[0])
+            },
             Function::Ln => {
                 Self::validate_args(args, 1, "ln")?;
                 if args[0] <= 0.0 {
@@ -154,12 +722,35 @@ impl Evaluator {
                 }
                 Ok(args[0].ln())
             },
-            Function::Log => {
-                Self::validate_args(args, 1, "log")?;
-                if args[0] <= 0.0 {
-                    return Err(EvalError::MathError("log of non-positive number".to_string()));
+            Function::Log => match args {
+                [x] => functions::log(*x, 10.0),
+                [x, base] => functions::log(*x, *base),
+                _ => Err(EvalError::InvalidArguments(
+                    format!("log expects 1 or 2 argument(s), got {}", args.len())
+                )),
+            },
+            Function::Log2 => {
+                Self::validate_args(args, 1, "log2")?;
+                functions::log(args[0], 2.0)
+            },
+            Function::Log1p => {
+                Self::validate_args(args, 1, "log1p")?;
+                if args[0] <= -1.0 {
+                    return Err(EvalError::MathError("log1p domain error: input must be greater than -1".to_string()));
                 }
-                Ok(args[0].log10())
+                Ok(args[0].ln_1p())
+            },
+            Function::Expm1 => {
+                Self::validate_args(args, 1, "expm1")?;
+                Ok(args[0].exp_m1())
+            },
+            Function::Cbrt => {
+                Self::validate_args(args, 1, "cbrt")?;
+                Ok(args[0].cbrt())
+            },
+            Function::Root => {
+                Self::validate_args(args, 2, "root")?;
+                functions::root(args[0], args[1])
             },
             Function::Sqrt => {
                 if args.len() != 1 {
@@ -206,10 +797,407 @@ impl Evaluator {
                 }
                 Ok(args.iter().cloned().fold(f64::INFINITY, f64::min))
             },
+            Function::Today | Function::Now | Function::Date | Function::Year | Function::Month
+            | Function::Day | Function::Weekday | Function::IsoWeek | Function::BusDays | Function::Workday => {
+                Err(EvalError::InvalidArguments(format!("{:?} expects date arguments", func)))
+            },
+            Function::FloatBits | Function::DoubleBits | Function::FromBits | Function::Ulp | Function::NextAfter => {
+                Err(EvalError::InvalidArguments(format!("{:?} is evaluated on values", func)))
+            },
+            Function::If
+            | Function::Table
+            | Function::Analyze
+            | Function::Intersect
+            | Function::Minimize
+            | Function::Maximize
+            | Function::OdeSolve
+            | Function::Taylor
+            | Function::PlotParam
+            | Function::PlotPolar
+            | Function::User(_) => {
+                Err(EvalError::InvalidArguments(format!("{:?} is evaluated on expressions", func)))
+            },
+            Function::Rand | Function::RandInt | Function::RandN | Function::Choice => {
+                Err(EvalError::InvalidArguments(format!("{:?} needs the random number generator", func)))
+            },
+        }
+    }
+
+    /// Functions that draw from the context's generator; `seed` makes them repeatable.
+    fn eval_random_function(&mut self, func: &Function, args: &[Value]) -> Result<Value, EvalError> {
+        if let Function::Choice = func {
+            Self::validate_args(args, 1, "choice")?;
+            let Value::List(items) = args[0].clone().into_plain() else {
+                return Err(EvalError::TypeError(format!("choice expects a list, found {}", args[0])));
+            };
+            if items.is_empty() {
+                return Err(EvalError::MathError("choice of an empty list".to_string()));
+            }
+            let index = self.context.get_rng_mut().below(items.len() as u64) as usize;
+            return Ok(items[index].clone());
+        }
+
+        let args: Vec<f64> = args.iter().map(Value::as_number).collect::<Result<_, _>>()?;
+        let rng = self.context.get_rng_mut();
+        let value = match func {
+            Function::Rand => {
+                Self::validate_args(&args, 0, "rand")?;
+                rng.next_f64()
+            },
+            Function::RandInt => {
+                Self::validate_args(&args, 2, "randint")?;
+                let (low, high) = (args[0], args[1]);
+                if low.fract() != 0.0 || high.fract() != 0.0 || low > high || (high - low) >= 2f64.powi(63) {
+                    return Err(EvalError::MathError(format!(
+                        "randint expects integer bounds a <= b, got {} and {}", low, high
+                    )));
+                }
+                low + rng.below((high - low) as u64 + 1) as f64
+            },
+            Function::RandN => {
+                let (mean, sd) = match args[..] {
+                    [] => (0.0, 1.0),
+                    [mean, sd] => (mean, sd),
+                    _ => return Err(EvalError::InvalidArguments(
+                        format!("randn expects 0 or 2 argument(s), got {}", args.len())
+                    )),
+                };
+                distributions::sample(Distribution::Normal, &[mean, sd], rng)?
+            },
+            Function::Distribution(dist, _) => distributions::sample(*dist, &args, rng)?,
+            _ => unreachable!("not a random function"),
+        };
+        Ok(Value::Number(value))
+    }
+
+    fn eval_calendar_function(func: &Function, args: &[Value]) -> Result<Value, EvalError> {
+        let date_arg = |name: &str| -> Result<DateTime, EvalError> {
+            Self::validate_args(args, 1, name)?;
+            args[0].as_datetime()
+        };
+
+        match func {
+            Function::Today => {
+                Self::validate_args(args, 0, "today")?;
+                Ok(Value::DateTime(DateTime::today()))
+            },
+            Function::Now => {
+                Self::validate_args(args, 0, "now")?;
+                Ok(Value::DateTime(DateTime::now()))
+            },
+            Function::Date => {
+                Self::validate_args(args, 3, "date")?;
+                let (y, m, d) = (args[0].as_number()?, args[1].as_number()?, args[2].as_number()?);
+                if [y, m, d].iter().any(|v| v.fract() != 0.0) || m < 1.0 || d < 1.0 {
+                    return Err(EvalError::MathError("date expects whole year, month and day".to_string()));
+                }
+                DateTime::date(y as i64, m as u32, d as u32)
+                    .map(Value::DateTime)
+                    .map_err(EvalError::MathError)
+            },
+            Function::Year => Ok(Value::Number(date_arg("year")?.civil().0 as f64)),
+            Function::Month => Ok(Value::Number(date_arg("month")?.civil().1 as f64)),
+            Function::Day => Ok(Value::Number(date_arg("day")?.civil().2 as f64)),
+            Function::Weekday => Ok(Value::Number(date_arg("weekday")?.weekday() as f64)),
+            Function::IsoWeek => Ok(Value::Number(date_arg("isoweek")?.iso_week() as f64)),
+            Function::BusDays => {
+                Self::validate_args(args, 2, "busdays")?;
+                let (from, to) = (args[0].as_datetime()?, args[1].as_datetime()?);
+                Ok(Value::Number(datetime::business_days(&from, &to) as f64))
+            },
+            Function::Workday => {
+                Self::validate_args(args, 2, "workday")?;
+                let (date, n) = (args[0].as_datetime()?, args[1].as_number()?);
+                if n.fract() != 0.0 {
+                    return Err(EvalError::MathError("workday expects a whole number of days".to_string()));
+                }
+                if n.abs() > datetime::MAX_BUSINESS_DAYS as f64 {
+                    return Err(EvalError::MathError(format!(
+                        "workday moves a date by at most {} business days, got {}", datetime::MAX_BUSINESS_DAYS, n
+                    )));
+                }
+                Ok(Value::DateTime(datetime::add_business_days(&date, n as i64)))
+            },
+            _ => Err(EvalError::InvalidArguments(format!("{:?} is not a calendar function", func))),
+        }
+    }
+
+    /// Special forms get their argument expressions and evaluate only what they need.
+    fn eval_lazy_function(&mut self, func: &Function, args: &[Expr]) -> Result<Value, EvalError> {
+        match func {
+            Function::If => {
+                Self::validate_args(args, 3, "if")?;
+                if self.eval_value(&args[0])?.as_bool()? {
+                    self.eval_value(&args[1])
+                } else {
+                    self.eval_value(&args[2])
+                }
+            },
+            // Kept apart so that recursion through `if` doesn't carry the
+            // stack frame of every other special form.
+            _ => self.eval_special_form(func, args),
+        }
+    }
+
+    fn eval_special_form(&mut self, func: &Function, args: &[Expr]) -> Result<Value, EvalError> {
+        match func {
+            Function::Table => {
+                if args.len() != 4 && args.len() != 5 {
+                    return Err(EvalError::InvalidArguments(format!(
+                        "table expects an expression, a variable, a start, an end and an optional step, got {} argument(s)", args.len()
+                    )));
+                }
+                let variable = Self::variable_name(&args[1], "table")?;
+                let from = self.eval_value(&args[2])?.as_number()?;
+                let to = self.eval_value(&args[3])?.as_number()?;
+                let step = match args.get(4) {
+                    Some(step) => self.eval_value(step)?.as_number()?,
+                    None => 1.0,
+                };
+                let f = self.compile_function(&args[0], &[variable])?;
+                table::tabulate(&f, self, from, to, step).map(Value::Table)
+            },
+            Function::Analyze => {
+                Self::validate_args(args, 4, "analyze")?;
+                let variable = Self::variable_name(&args[1], "analyze")?;
+                let from = self.eval_value(&args[2])?.as_number()?;
+                let to = self.eval_value(&args[3])?.as_number()?;
+                let f = self.compile_function(&args[0], &[variable])?;
+                let points = analysis::analyze(&f, self, from, to, &AnalysisOptions::default())?;
+                Ok(Value::Table(analysis::to_table(&points)))
+            },
+            Function::Intersect => {
+                Self::validate_args(args, 5, "intersect")?;
+                let variable = Self::variable_name(&args[2], "intersect")?;
+                let from = self.eval_value(&args[3])?.as_number()?;
+                let to = self.eval_value(&args[4])?.as_number()?;
+                let f = self.compile_function(&args[0], &[variable])?;
+                let g = self.compile_function(&args[1], &[variable])?;
+                let points = analysis::intersect(&f, &g, self, from, to, &AnalysisOptions::default())?;
+                Ok(Value::Table(analysis::to_table(&points)))
+            },
+            Function::Minimize | Function::Maximize => {
+                let name = if let Function::Minimize = func { "minimize" } else { "maximize" };
+                if args.len() != 3 && args.len() != 5 {
+                    return Err(EvalError::InvalidArguments(format!(
+                        "{} expects an expression, its variables, a starting point and optional lower and upper bounds, got {} argument(s)",
+                        name, args.len()
+                    )));
+                }
+                let variables: Vec<&str> = match &args[1] {
+                    Expr::List(items) => items.iter().map(|item| Self::variable_name(item, name)).collect::<Result<_, _>>()?,
+                    other => vec![Self::variable_name(other, name)?],
+                };
+                let start = self.coordinates(&args[2], variables.len(), "starting point")?;
+                let bounds = match args.get(3..5) {
+                    Some([lower, upper]) => {
+                        let lower = self.coordinates(lower, variables.len(), "lower bound")?;
+                        let upper = self.coordinates(upper, variables.len(), "upper bound")?;
+                        Some(lower.into_iter().zip(upper).collect::<Vec<_>>())
+                    },
+                    _ => None,
+                };
+                let f = self.compile_function(&args[0], &variables)?;
+                let options = OptimizeOptions::default();
+                let optimum = if let Function::Minimize = func {
+                    optimize::minimize(&f, self, &start, bounds.as_deref(), &options)?
+                } else {
+                    optimize::maximize(&f, self, &start, bounds.as_deref(), &options)?
+                };
+                Ok(Value::Table(optimize::to_table(f.variables(), &optimum)))
+            },
+            Function::OdeSolve => {
+                if args.len() != 6 && args.len() != 7 {
+                    return Err(EvalError::InvalidArguments(format!(
+                        "odesolve expects equations, the unknowns, the time variable, a start, an end, initial values and an optional step, got {} argument(s)",
+                        args.len()
+                    )));
+                }
+                let equations = match &args[0] {
+                    Expr::List(items) => items.clone(),
+                    other => vec![other.clone()],
+                };
+                let unknowns: Vec<&str> = match &args[1] {
+                    Expr::List(items) => items.iter().map(|item| Self::variable_name(item, "odesolve")).collect::<Result<_, _>>()?,
+                    other => vec![Self::variable_name(other, "odesolve")?],
+                };
+                let variable = Self::variable_name(&args[2], "odesolve")?;
+                let from = self.eval_value(&args[3])?.as_number()?;
+                let to = self.eval_value(&args[4])?.as_number()?;
+                let initial = self.coordinates(&args[5], unknowns.len(), "starting state")?;
+                let step = match args.get(6) {
+                    Some(step) => self.eval_value(step)?.as_number()?,
+                    None => (to - from) / 10.0,
+                };
+                let mut variables = vec![variable];
+                variables.extend(&unknowns);
+                let rhs = ode::right_hand_sides(&equations, &unknowns)?.iter()
+                    .map(|side| self.compile_function(side, &variables))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let trajectory = ode::solve(&rhs, self, &initial, from, to, step, &OdeOptions::default())?;
+                Ok(Value::Table(trajectory.to_table()))
+            },
+            Function::Taylor => {
+                Self::validate_args(args, 4, "taylor")?;
+                let variable = Self::variable_name(&args[1], "taylor")?;
+                let center = self.eval_value(&args[2])?.as_number()?;
+                let order = self.eval_value(&args[3])?.as_number()?;
+                if !(0.0..=symbolic::MAX_ORDER as f64).contains(&order) || order.fract() != 0.0 {
+                    return Err(EvalError::InvalidArguments(format!(
+                        "taylor expects a whole number order from 0 to {}, got {}", symbolic::MAX_ORDER, order
+                    )));
+                }
+                let f = self.compile_function(&args[0], &[variable])?;
+                symbolic::taylor(f.expr(), variable, center, order as usize, self).map(Value::Expression)
+            },
+            Function::PlotParam => {
+                Self::validate_args(args, 5, "plot_param")?;
+                let variable = Self::variable_name(&args[2], "plot_param")?;
+                let from = self.eval_value(&args[3])?.as_number()?;
+                let to = self.eval_value(&args[4])?.as_number()?;
+                let x = self.compile_function(&args[0], &[variable])?;
+                let y = self.compile_function(&args[1], &[variable])?;
+                let curve = plot::sample_parametric(&x, &y, self, from, to, &SampleOptions::default())?;
+                Ok(Value::Table(curve.to_table()))
+            },
+            Function::PlotPolar => {
+                Self::validate_args(args, 4, "plot_polar")?;
+                let variable = Self::variable_name(&args[1], "plot_polar")?;
+                let from = self.eval_value(&args[2])?.as_number()?;
+                let to = self.eval_value(&args[3])?.as_number()?;
+                let r = self.compile_function(&args[0], &[variable])?;
+                let curve = plot::sample_polar(&r, self, from, to, &SampleOptions::default())?;
+                Ok(Value::Table(curve.to_table()))
+            },
+            _ => Err(EvalError::InvalidArguments(format!("{:?} is not a special form", func))),
+        }
+    }
+
+    /// The name in a special form's variable argument, such as the `x` of
+    /// `table(x^2, x, 0, 10)`.
+    fn variable_name<'a>(arg: &'a Expr, func_name: &str) -> Result<&'a str, EvalError> {
+        match arg {
+            Expr::Variable(name) => Ok(name),
+            _ => Err(EvalError::InvalidArguments(format!("{} expects a variable name, such as x", func_name))),
+        }
+    }
+
+    /// A point given as a number or, for several variables, a list of
+    /// them, such as the `[1, 2]` of `minimize(f, [x, y], [1, 2])`.
+    fn coordinates(&mut self, arg: &Expr, count: usize, what: &str) -> Result<Vec<f64>, EvalError> {
+        let coordinates = match self.eval_value(arg)?.into_plain() {
+            Value::List(items) => items.iter().map(Value::as_number).collect::<Result<Vec<_>, _>>()?,
+            other => vec![other.as_number()?],
+        };
+        if coordinates.len() != count {
+            return Err(EvalError::InvalidArguments(format!(
+                "expected a {} with {} coordinate(s), got {}", what, count, coordinates.len()
+            )));
+        }
+        Ok(coordinates)
+    }
+
+    /// Compiles a special form's function argument. Besides an expression
+    /// in `variables`, this can be the bare name of a function defined in
+    /// the session with as many parameters: `analyze(f, x, 0, 1)` for
+    /// `f(x) = ...`.
+    fn compile_function(&mut self, arg: &Expr, variables: &[&str]) -> Result<CompiledExpr, EvalError> {
+        let call;
+        let expr = match arg {
+            Expr::Variable(name) if self.context.get(name).is_none()
+                && self.context.get_function(name).is_some_and(|f| f.params.len() == variables.len()) => {
+                call = Expr::FunctionCall {
+                    func: Function::User(name.clone()),
+                    args: variables.iter().map(|v| Expr::Variable(v.to_string())).collect(),
+                };
+                &call
+            },
+            _ => arg,
+        };
+        CompiledExpr::new(expr, variables, self)
+    }
+
+    fn call_user_function(&mut self, name: &str, args: &[Value]) -> Result<Value, EvalError> {
+        let function = self.context.get_function(name)
+            .cloned()
+            .ok_or_else(|| EvalError::UndefinedFunction(name.to_string()))?;
+        Self::validate_args(args, function.params.len(), name)?;
+
+        let bindings: Vec<(&str, Value)> = function.params.iter()
+            .map(String::as_str)
+            .zip(args.iter().cloned())
+            .collect();
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(EvalError::MathError(format!("{} calls itself too deeply", name)));
+        }
+        self.depth += 1;
+        let result = self.eval_with(&bindings, &function.body);
+        self.depth -= 1;
+        match result? {
+            Value::Expression(expr) => self.eval_with(&bindings, &expr),
+            value => Ok(value),
+        }
+    }
+
+    /// Bit patterns come back as integers wrapped to the word size, so they
+    /// print naturally with `in hex`. A word too narrow to hold the whole
+    /// pattern is an error rather than a truncated pattern.
+    fn eval_ieee754_function(&self, func: &Function, args: &[Value]) -> Result<Value, EvalError> {
+        let word = self.context.get_word_size();
+        let pattern = |bits: u64, width: u32, name: &str| {
+            if word.bits < width {
+                return Err(EvalError::MathError(format!(
+                    "{} needs a word size of at least {} bits, the current one is {}", name, width, word.bits
+                )));
+            }
+            Ok(Value::Integer(word.wrap(bits as i128)))
+        };
+        match func {
+            Function::FloatBits => {
+                Self::validate_args(args, 1, "float_bits")?;
+                pattern((args[0].as_number()? as f32).to_bits() as u64, 32, "float_bits")
+            },
+            Function::DoubleBits => {
+                Self::validate_args(args, 1, "double_bits")?;
+                pattern(args[0].as_number()?.to_bits(), 64, "double_bits")
+            },
+            Function::FromBits => {
+                if args.is_empty() || args.len() > 2 {
+                    return Err(EvalError::InvalidArguments("from_bits expects a bit pattern and an optional width".to_string()));
+                }
+                let bits = match args[0].clone().into_plain() {
+                    Value::Integer(i) => word.to_unsigned(i) as u64,
+                    other => {
+                        let n = other.as_number()?;
+                        if n.fract() != 0.0 || !(0.0..18446744073709551616.0).contains(&n) {
+                            return Err(EvalError::MathError(format!("{} is not a 64-bit pattern", n)));
+                        }
+                        n as u64
+                    },
+                };
+                let width = match args.get(1).map(Value::as_number).transpose()? {
+                    Some(32.0) => 32,
+                    Some(64.0) => 64,
+                    Some(w) => {
+                        return Err(EvalError::InvalidArguments(format!("from_bits width must be 32 or 64, got {}", w)));
+                    },
+                    None => 64,
+                };
+                ieee754::from_bits(bits, width).map(Value::Number)
+            },
+            Function::Ulp => {
+                Self::validate_args(args, 1, "ulp")?;
+                ieee754::ulp(args[0].as_number()?).map(Value::Number)
+            },
+            Function::NextAfter => {
+                Self::validate_args(args, 2, "nextafter")?;
+                Ok(Value::Number(ieee754::next_after(args[0].as_number()?, args[1].as_number()?)))
+            },
+            _ => Err(EvalError::InvalidArguments(format!("{:?} is not an IEEE-754 function", func))),
         }
     }
 
-    fn validate_args(args: &[f64], expected: usize, func_name: &str) -> Result<(), EvalError> {
+    fn validate_args<T>(args: &[T], expected: usize, func_name: &str) -> Result<(), EvalError> {
         if args.len() != expected {
             Err(EvalError::InvalidArguments(
                 format!("{} expects {} argument(s), got {}", func_name, expected, args.len())
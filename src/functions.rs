@@ -1,4 +1,6 @@
-/// Module for mathematical functions.  
+//! Module for mathematical functions.
+
+use std::{cmp::Ordering, f64::consts::{FRAC_PI_2, PI}};
 
 use crate::{context::AngleMode, error::EvalError};
 
@@ -29,4 +31,788 @@ pub fn from_radians(angle: f64, mode: AngleMode) -> f64 {
         AngleMode::Radians => angle,
         AngleMode::Gradians => angle * 200.0 / std::f64::consts::PI,
     }
-}
\ No newline at end of file
+}
+
+/// The number of quarter turns in `angle` when it is an exact multiple of one.
+/// Used to report the poles of sec, csc and cot instead of returning ±1e16
+/// from the rounding error in `cos(π/2)`.
+fn quarter_turns(angle: f64, mode: AngleMode) -> Option<f64> {
+    let turns = match mode {
+        AngleMode::Degrees => angle / 90.0,
+        AngleMode::Radians => angle / FRAC_PI_2,
+        AngleMode::Gradians => angle / 100.0,
+    };
+    (turns.fract() == 0.0).then_some(turns)
+}
+
+fn is_odd(turns: f64) -> bool {
+    turns.rem_euclid(2.0) == 1.0
+}
+
+pub fn sec(angle: f64, mode: AngleMode) -> Result<f64, EvalError> {
+    if quarter_turns(angle, mode).is_some_and(is_odd) {
+        return Err(EvalError::MathError(format!("sec is undefined at {}", angle)));
+    }
+    Ok(1.0 / to_radians(angle, mode).cos())
+}
+
+pub fn csc(angle: f64, mode: AngleMode) -> Result<f64, EvalError> {
+    if quarter_turns(angle, mode).is_some_and(|turns| !is_odd(turns)) {
+        return Err(EvalError::MathError(format!("csc is undefined at {}", angle)));
+    }
+    Ok(1.0 / to_radians(angle, mode).sin())
+}
+
+pub fn cot(angle: f64, mode: AngleMode) -> Result<f64, EvalError> {
+    match quarter_turns(angle, mode) {
+        Some(turns) if is_odd(turns) => Ok(0.0),
+        Some(_) => Err(EvalError::MathError(format!("cot is undefined at {}", angle))),
+        None => Ok(1.0 / to_radians(angle, mode).tan()),
+    }
+}
+
+/// Inverse secant in radians, in [0, π].
+pub fn asec(x: f64) -> Result<f64, EvalError> {
+    if x.abs() < 1.0 {
+        return Err(EvalError::MathError("asec domain error: |input| must be at least 1".to_string()));
+    }
+    Ok((1.0 / x).acos())
+}
+
+/// Inverse cosecant in radians, in [-π/2, π/2].
+pub fn acsc(x: f64) -> Result<f64, EvalError> {
+    if x.abs() < 1.0 {
+        return Err(EvalError::MathError("acsc domain error: |input| must be at least 1".to_string()));
+    }
+    Ok((1.0 / x).asin())
+}
+
+/// Inverse cotangent in radians, in (-π/2, π/2] so that it is `atan(1/x)`.
+pub fn acot(x: f64) -> f64 {
+    if x == 0.0 { FRAC_PI_2 } else { (1.0 / x).atan() }
+}
+
+pub fn acosh(x: f64) -> Result<f64, EvalError> {
+    if x < 1.0 {
+        return Err(EvalError::MathError("acosh domain error: input must be at least 1".to_string()));
+    }
+    Ok(x.acosh())
+}
+
+pub fn atanh(x: f64) -> Result<f64, EvalError> {
+    if x.abs() >= 1.0 {
+        return Err(EvalError::MathError("atanh domain error: input must be in (-1, 1)".to_string()));
+    }
+    Ok(x.atanh())
+}
+
+/// Angle of the point (x, y) from the positive x axis, in radians in (-π, π].
+pub fn atan2(y: f64, x: f64) -> Result<f64, EvalError> {
+    if x == 0.0 && y == 0.0 {
+        return Err(EvalError::MathError("atan2 is undefined at the origin".to_string()));
+    }
+    Ok(y.atan2(x))
+}
+/// Logarithm of `x` to an arbitrary base.
+pub fn log(x: f64, base: f64) -> Result<f64, EvalError> {
+    if x <= 0.0 {
+        return Err(EvalError::MathError("log of non-positive number".to_string()));
+    }
+    if base <= 0.0 || base == 1.0 {
+        return Err(EvalError::MathError(format!("log base must be positive and not 1, got {}", base)));
+    }
+    Ok(match base {
+        2.0 => x.log2(),
+        10.0 => x.log10(),
+        _ => x.ln() / base.ln(),
+    })
+}
+
+/// The real `n`th root of `x`. Negative `x` has one only for odd integer `n`.
+pub fn root(x: f64, n: f64) -> Result<f64, EvalError> {
+    if n == 0.0 {
+        return Err(EvalError::MathError("root of degree 0 is undefined".to_string()));
+    }
+    if x >= 0.0 {
+        return Ok(x.powf(1.0 / n));
+    }
+    if n.fract() != 0.0 || n % 2.0 == 0.0 {
+        return Err(EvalError::MathError(format!("{} has no real root of degree {}", x, n)));
+    }
+    if n == 3.0 {
+        return Ok(x.cbrt());
+    }
+    Ok(-(-x).powf(1.0 / n))
+}
+
+/// Relative tolerance below which two numbers compare as equal, so that
+/// `0.1 + 0.2 == 0.3` holds despite rounding.
+pub const COMPARISON_TOLERANCE: f64 = 1e-12;
+
+/// Orders two numbers, treating values within `COMPARISON_TOLERANCE` of each
+/// other as equal. Infinities compare exactly, since no tolerance relative
+/// to them means anything. Returns `None` if either is NaN.
+pub fn compare(a: f64, b: f64) -> Option<Ordering> {
+    if !a.is_finite() || !b.is_finite() {
+        return a.partial_cmp(&b);
+    }
+    if a == b || (a - b).abs() <= COMPARISON_TOLERANCE * a.abs().max(b.abs()) {
+        return Some(Ordering::Equal);
+    }
+    a.partial_cmp(&b)
+}
+
+// Special functions. Unless noted otherwise each is accurate to within about
+// 1e-13 relative error, and to about 1e-14 absolute error near its zeros.
+
+const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+/// `sin(πx)`, exactly zero at integers.
+fn sin_pi(x: f64) -> f64 {
+    let r = x.rem_euclid(2.0);
+    match r {
+        0.0 | 1.0 => 0.0,
+        0.5 => 1.0,
+        1.5 => -1.0,
+        _ => (PI * r).sin(),
+    }
+}
+
+/// `cos(πx)`, exactly zero at half-integers.
+fn cos_pi(x: f64) -> f64 {
+    sin_pi(x + 0.5)
+}
+
+fn is_integer(x: f64) -> bool {
+    x.fract() == 0.0
+}
+
+const LANCZOS_G: f64 = 7.0;
+const LANCZOS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7,
+];
+
+fn lanczos_sum(x: f64) -> f64 {
+    LANCZOS[1..].iter().enumerate().fold(LANCZOS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0))
+}
+
+/// The gamma function (Lanczos approximation, about 1e-15 relative error).
+/// Poles at zero and the negative integers come out as ±infinity.
+pub fn gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        return PI / (sin_pi(x) * gamma(1.0 - x));
+    }
+    if x > 171.7 {
+        return f64::INFINITY;
+    }
+    let x = x - 1.0;
+    let t = x + LANCZOS_G + 0.5;
+    // Split the power so it doesn't overflow before e^-t brings it back down.
+    let half = t.powf((x + 0.5) / 2.0);
+    (2.0 * PI).sqrt() * half * ((-t).exp() * half) * lanczos_sum(x)
+}
+
+/// `ln Γ(x)` for positive `x`.
+pub fn ln_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        return (PI / sin_pi(x)).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + LANCZOS_G + 0.5;
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + lanczos_sum(x).ln()
+}
+
+/// The error function. Uses the power series below 2 and a continued
+/// fraction for `erfc` above.
+pub fn erf(x: f64) -> f64 {
+    if x.abs() < 2.0 {
+        erf_series(x)
+    } else {
+        x.signum() * (1.0 - erfc_fraction(x.abs()))
+    }
+}
+
+/// The complementary error function `1 - erf(x)`, keeping full relative
+/// accuracy in its tail (`erfc(10)` is about 2e-45).
+pub fn erfc(x: f64) -> f64 {
+    if x >= 2.0 {
+        erfc_fraction(x)
+    } else if x <= -2.0 {
+        2.0 - erfc_fraction(-x)
+    } else {
+        1.0 - erf_series(x)
+    }
+}
+
+/// `erf(x) = 2/√π · e^(-x²) · Σ 2ⁿ x^(2n+1) / (1·3·…·(2n+1))`, whose terms
+/// are all positive.
+fn erf_series(x: f64) -> f64 {
+    let mut term = x;
+    let mut sum = x;
+    for n in 1..200 {
+        term *= 2.0 * x * x / (2 * n + 1) as f64;
+        sum += term;
+        if term.abs() <= f64::EPSILON * sum.abs() {
+            break;
+        }
+    }
+    2.0 / PI.sqrt() * (-x * x).exp() * sum
+}
+
+/// `erfc(x) = e^(-x²)/√π · 1/(x + (1/2)/(x + 1/(x + (3/2)/(x + …))))` for
+/// `x ≥ 2`, evaluated with Lentz's method.
+fn erfc_fraction(x: f64) -> f64 {
+    if x > 27.0 {
+        return 0.0;
+    }
+    let tiny = 1e-300;
+    let mut f = x;
+    let mut c = x;
+    let mut d = 0.0;
+    for n in 1..500 {
+        let a = n as f64 / 2.0;
+        d = x + a * d;
+        d = if d == 0.0 { 1.0 / tiny } else { 1.0 / d };
+        c = x + a / c;
+        if c == 0.0 {
+            c = tiny;
+        }
+        let delta = c * d;
+        f *= delta;
+        if (delta - 1.0).abs() <= f64::EPSILON {
+            break;
+        }
+    }
+    (-x * x).exp() / (PI.sqrt() * f)
+}
+
+/// The inverse error function on [-1, 1]. Starts from Giles' single-precision
+/// approximation and refines it with Halley's method, working with `erfc`
+/// near ±1 so that results stay accurate in the tails.
+pub fn erfinv(y: f64) -> Result<f64, EvalError> {
+    if !(-1.0..=1.0).contains(&y) {
+        return Err(EvalError::MathError("erfinv domain error: input must be in [-1, 1]".to_string()));
+    }
+    if y.abs() == 1.0 {
+        return Ok(y * f64::INFINITY);
+    }
+    if y == 0.0 {
+        return Ok(y);
+    }
+
+    let w = -((1.0 - y) * (1.0 + y)).ln();
+    let mut x = if w < 5.0 {
+        let w = w - 2.5;
+        [
+            3.432_739_39e-7, -3.523_387_7e-6, -4.391_506_54e-6, 2.185_808_7e-4,
+            -1.253_725_03e-3, -4.177_681_64e-3, 2.466_407_27e-1, 1.501_409_41,
+        ].iter().fold(2.810_226_36e-8, |p, c| c + p * w)
+    } else {
+        let w = w.sqrt() - 3.0;
+        [
+            1.009_505_58e-4, 1.349_343_22e-3, -3.673_428_44e-3, 5.739_507_73e-3,
+            -7.622_461_3e-3, 9.438_870_47e-3, 1.001_674_06, 2.832_976_82,
+        ].iter().fold(-2.002_142_57e-4, |p, c| c + p * w)
+    } * y;
+
+    let (target, sign) = (y.abs(), y.signum());
+    x = x.abs();
+    for _ in 0..4 {
+        // Residual of erf(x) - target, taken as a difference of erfc values in the tail.
+        let residual = if target > 0.5 { (1.0 - target) - erfc(x) } else { erf(x) - target };
+        let slope = 2.0 / PI.sqrt() * (-x * x).exp();
+        if slope == 0.0 {
+            break;
+        }
+        let step = residual / slope;
+        x -= step / (1.0 + x * step);
+        if step.abs() <= f64::EPSILON * x {
+            break;
+        }
+    }
+    Ok(sign * x)
+}
+
+const KRONROD_NODES: [f64; 8] = [
+    0.991_455_371_120_812_6, 0.949_107_912_342_758_5, 0.864_864_423_359_769_1, 0.741_531_185_599_394_4,
+    0.586_087_235_467_691_1, 0.405_845_151_377_397_2, 0.207_784_955_007_898_5, 0.0,
+];
+const KRONROD_WEIGHTS: [f64; 8] = [
+    0.022_935_322_010_529_22, 0.063_092_092_629_978_55, 0.104_790_010_322_250_18, 0.140_653_259_715_525_92,
+    0.169_004_726_639_267_9, 0.190_350_578_064_785_4, 0.204_432_940_075_298_9, 0.209_482_141_084_727_83,
+];
+/// Weights of the 7-point Gauss rule, on the odd Kronrod nodes.
+const GAUSS_WEIGHTS: [f64; 4] = [
+    0.129_484_966_168_869_7, 0.279_705_391_489_276_7, 0.381_830_050_505_118_9, 0.417_959_183_673_469_4,
+];
+
+/// One 15-point Gauss–Kronrod panel: the integral, its error estimate and the integral of `|f|`.
+fn gauss_kronrod(f: &dyn Fn(f64) -> f64, a: f64, b: f64) -> (f64, f64, f64) {
+    let (center, half) = ((a + b) / 2.0, (b - a) / 2.0);
+    let mid = f(center);
+    let mut kronrod = mid * KRONROD_WEIGHTS[7];
+    let mut gauss = mid * GAUSS_WEIGHTS[3];
+    let mut absolute = mid.abs() * KRONROD_WEIGHTS[7];
+    for i in 0..7 {
+        let (lo, hi) = (f(center - half * KRONROD_NODES[i]), f(center + half * KRONROD_NODES[i]));
+        kronrod += KRONROD_WEIGHTS[i] * (lo + hi);
+        absolute += KRONROD_WEIGHTS[i] * (lo.abs() + hi.abs());
+        if i % 2 == 1 {
+            gauss += GAUSS_WEIGHTS[i / 2] * (lo + hi);
+        }
+    }
+    (kronrod * half, ((kronrod - gauss) * half).abs(), absolute * half.abs())
+}
+
+/// Integrates `f` over [a, b], repeatedly splitting the panel with the largest
+/// error until the total error is below 1e-14 of the integral of `|f|`.
+fn integrate(f: &dyn Fn(f64) -> f64, a: f64, b: f64) -> f64 {
+    const MAX_PANELS: usize = 2000;
+    let mut panels = vec![(a, b, gauss_kronrod(f, a, b))];
+    loop {
+        let (total, error, absolute) = panels.iter()
+            .fold((0.0, 0.0, 0.0), |(t, e, s), (_, _, (k, err, abs))| (t + k, e + err, s + abs));
+        if error <= 1e-14 * absolute || panels.len() >= MAX_PANELS || !total.is_finite() {
+            return total;
+        }
+        let worst = panels.iter().enumerate()
+            .max_by(|(_, x), (_, y)| x.2.1.total_cmp(&y.2.1))
+            .map(|(i, _)| i)
+            .unwrap();
+        let (lo, hi, _) = panels.swap_remove(worst);
+        let mid = (lo + hi) / 2.0;
+        panels.push((lo, mid, gauss_kronrod(f, lo, mid)));
+        panels.push((mid, hi, gauss_kronrod(f, mid, hi)));
+    }
+}
+
+/// `∫₀^∞ e^h(t) dt` for a concave exponent `h` with its maximum at `peak`.
+fn integrate_exp(h: &dyn Fn(f64) -> f64, peak: f64) -> f64 {
+    let top = h(peak);
+    let mut width = 1.0;
+    while h(peak + width) > top - 50.0 {
+        width *= 2.0;
+    }
+    let f = |t: f64| h(t).exp();
+    integrate(&f, 0.0, peak) + integrate(&f, peak, peak + width)
+}
+
+/// Hankel's asymptotic expansion `(P, Q)` for large `x`, summed until the
+/// terms stop shrinking.
+fn hankel(nu: f64, x: f64) -> (f64, f64) {
+    let mu = 4.0 * nu * nu;
+    let (mut p, mut q, mut term) = (1.0, 0.0, 1.0_f64);
+    for k in 1..200 {
+        let next = term * (mu - ((2 * k - 1) * (2 * k - 1)) as f64) / (k as f64 * 8.0 * x);
+        if next == 0.0 || next.abs() > term.abs() {
+            break;
+        }
+        term = next;
+        match k % 4 {
+            1 => q += term,
+            2 => p -= term,
+            3 => q -= term,
+            _ => p += term,
+        }
+        if term.abs() <= f64::EPSILON * 1e-2 {
+            break;
+        }
+    }
+    (p, q)
+}
+
+fn use_hankel(nu: f64, x: f64) -> bool {
+    x >= 25.0 + nu * nu / 2.0
+}
+
+/// `Σ (±x²/4)^k / (k! Γ(k+ν+1)) · (x/2)^ν`, the series for J (`sign` = -1) and I (`sign` = 1).
+fn bessel_series(nu: f64, x: f64, sign: f64) -> f64 {
+    let q = sign * x * x / 4.0;
+    let mut term = (nu * (x / 2.0).ln() - ln_gamma(nu + 1.0)).exp();
+    let mut sum = term;
+    for k in 1..2000 {
+        term *= q / (k as f64 * (k as f64 + nu));
+        sum += term;
+        if term.abs() <= 1e-2 * f64::EPSILON * sum.abs() {
+            break;
+        }
+    }
+    sum
+}
+
+fn bessel_j_positive(nu: f64, x: f64) -> f64 {
+    if use_hankel(nu, x) {
+        let (p, q) = hankel(nu, x);
+        let chi = x - (nu / 2.0 + 0.25) * PI;
+        return (2.0 / (PI * x)).sqrt() * (p * chi.cos() - q * chi.sin());
+    }
+    if x <= 8.0 || x * x / 4.0 <= nu + 1.0 {
+        return bessel_series(nu, x, -1.0);
+    }
+    if nu > x {
+        return bessel_j_recurrence(nu, x);
+    }
+    bessel_j_schlafli(nu, x)
+}
+
+/// Schläfli's integral for `J_ν(x)`. Its integrand is of order one, so it
+/// cancels catastrophically once `J_ν(x)` is small, as it is for `ν > x`.
+fn bessel_j_schlafli(nu: f64, x: f64) -> f64 {
+    let oscillating = integrate(&|t| (nu * t - x * t.sin()).cos(), 0.0, PI) / PI;
+    let sin = sin_pi(nu);
+    if sin == 0.0 {
+        return oscillating;
+    }
+    oscillating - sin / PI * integrate_exp(&|t| -x * t.sinh() - nu * t, 0.0)
+}
+
+/// `J_ν(x)` for `ν > x` by Miller's backward recurrence
+/// `J_(μ-1) = 2μ/x · J_μ - J_(μ+1)`, which is stable downwards. It runs down
+/// in whole steps to an order `≤ x` and is normalised there by Schläfli's
+/// integral.
+fn bessel_j_recurrence(nu: f64, x: f64) -> f64 {
+    const RESCALE: f64 = 1e250;
+    let steps = (nu - x).ceil();
+    let low = nu - steps;
+    let top = steps as usize + 20 + (10.0 * nu.cbrt()) as usize;
+    // `current` holds the unnormalised value at order `low + k`, relative to
+    // the value at `nu` once the recurrence has passed it.
+    let (mut above, mut current) = (0.0, 1.0_f64);
+    let mut log_scale = 0.0;
+    for k in (1..=top).rev() {
+        if k as f64 == steps {
+            above /= current;
+            current = 1.0;
+        }
+        (above, current) = (current, 2.0 * (low + k as f64) / x * current - above);
+        if current.abs() > RESCALE {
+            above /= RESCALE;
+            current /= RESCALE;
+            if k as f64 <= steps {
+                log_scale += RESCALE.ln();
+                // |J| ≤ 1 at every order, so `J_ν` is below e^-log_scale.
+                if log_scale > 750.0 {
+                    return 0.0;
+                }
+            }
+        }
+    }
+    bessel_j_schlafli(low, x) / current * (-log_scale).exp()
+}
+
+fn bessel_y_positive(nu: f64, x: f64) -> f64 {
+    if use_hankel(nu, x) {
+        let (p, q) = hankel(nu, x);
+        let chi = x - (nu / 2.0 + 0.25) * PI;
+        return (2.0 / (PI * x)).sqrt() * (p * chi.sin() + q * chi.cos());
+    }
+    let oscillating = integrate(&|t| (x * t.sin() - nu * t).sin(), 0.0, PI) / PI;
+    let peak = if nu > x { (nu / x).acosh() } else { 0.0 };
+    let growing = integrate_exp(&|t| nu * t - x * t.sinh(), peak);
+    let decaying = integrate_exp(&|t| -nu * t - x * t.sinh(), 0.0);
+    oscillating - (growing + cos_pi(nu) * decaying) / PI
+}
+
+fn bessel_k_positive(nu: f64, x: f64) -> f64 {
+    let growing = integrate_exp(&|t| nu * t - x * t.cosh(), (nu / x).asinh());
+    let decaying = integrate_exp(&|t| -nu * t - x * t.cosh(), 0.0);
+    (growing + decaying) / 2.0
+}
+
+fn check_bessel_argument(name: &str, x: f64) -> Result<(), EvalError> {
+    if x.is_finite() {
+        Ok(())
+    } else {
+        Err(EvalError::MathError(format!("{} requires a finite argument", name)))
+    }
+}
+
+/// Sign of `f_n(-x)` relative to `f_n(x)` for the integer-order J and I, which
+/// are the only orders with real values at negative `x`.
+fn reflect_argument(name: &str, nu: f64, x: f64) -> Result<f64, EvalError> {
+    if x >= 0.0 {
+        Ok(1.0)
+    } else if is_integer(nu) {
+        Ok(if nu.rem_euclid(2.0) == 0.0 { 1.0 } else { -1.0 })
+    } else {
+        Err(EvalError::MathError(format!("{} of non-integer order is complex for negative arguments", name)))
+    }
+}
+
+/// Bessel function of the first kind `J_ν(x)` for real order. Uses the power
+/// series for small `x`, Hankel's expansion for large `x`, backward
+/// recurrence for orders above `x` and Schläfli's integral in between;
+/// negative orders use the reflection formula.
+pub fn bessel_j(nu: f64, x: f64) -> Result<f64, EvalError> {
+    check_bessel_argument("besselj", x)?;
+    let sign = reflect_argument("besselj", nu, x)?;
+    let x = x.abs();
+    if nu < 0.0 && is_integer(nu) {
+        return Ok(sign * cos_pi(nu) * bessel_j(-nu, x)?);
+    }
+    if x == 0.0 {
+        return match nu {
+            0.0 => Ok(1.0),
+            nu if nu > 0.0 => Ok(0.0),
+            _ => Err(EvalError::MathError(format!("besselj of order {} is infinite at 0", nu))),
+        };
+    }
+    if nu < 0.0 {
+        let nu = -nu;
+        return Ok(cos_pi(nu) * bessel_j_positive(nu, x) - sin_pi(nu) * bessel_y_positive(nu, x));
+    }
+    Ok(sign * bessel_j_positive(nu, x))
+}
+
+/// Bessel function of the second kind `Y_ν(x)` for real order and `x > 0`.
+pub fn bessel_y(nu: f64, x: f64) -> Result<f64, EvalError> {
+    check_bessel_argument("bessely", x)?;
+    if x <= 0.0 {
+        return Err(EvalError::MathError("bessely domain error: input must be positive".to_string()));
+    }
+    if nu < 0.0 {
+        let nu = -nu;
+        return Ok(sin_pi(nu) * bessel_j_positive(nu, x) + cos_pi(nu) * bessel_y_positive(nu, x));
+    }
+    Ok(bessel_y_positive(nu, x))
+}
+
+/// Modified Bessel function of the first kind `I_ν(x)`, from its power
+/// series, whose terms are all positive. Overflows beyond `x ≈ 713`.
+pub fn bessel_i(nu: f64, x: f64) -> Result<f64, EvalError> {
+    check_bessel_argument("besseli", x)?;
+    let sign = reflect_argument("besseli", nu, x)?;
+    let x = x.abs();
+    let nu = if is_integer(nu) { nu.abs() } else { nu };
+    if x == 0.0 {
+        return match nu {
+            0.0 => Ok(1.0),
+            nu if nu > 0.0 => Ok(0.0),
+            _ => Err(EvalError::MathError(format!("besseli of order {} is infinite at 0", nu))),
+        };
+    }
+    if nu < 0.0 {
+        let nu = -nu;
+        return Ok(bessel_series(nu, x, 1.0) + 2.0 / PI * sin_pi(nu) * bessel_k_positive(nu, x));
+    }
+    Ok(sign * bessel_series(nu, x, 1.0))
+}
+
+/// Modified Bessel function of the second kind `K_ν(x)` for real order and
+/// `x > 0`, from `∫₀^∞ e^(-x cosh t) cosh(νt) dt`.
+pub fn bessel_k(nu: f64, x: f64) -> Result<f64, EvalError> {
+    check_bessel_argument("besselk", x)?;
+    if x <= 0.0 {
+        return Err(EvalError::MathError("besselk domain error: input must be positive".to_string()));
+    }
+    Ok(bessel_k_positive(nu.abs(), x))
+}
+
+/// The Riemann zeta function for real `s ≠ 1`. Uses Borwein's acceleration
+/// of the alternating series for `s ≥ 0` and the functional equation below.
+pub fn zeta(s: f64) -> Result<f64, EvalError> {
+    if s == 1.0 {
+        return Err(EvalError::MathError("zeta has a pole at 1".to_string()));
+    }
+    if s < 0.0 {
+        if is_integer(s / 2.0) {
+            return Ok(0.0);
+        }
+        let reflected = zeta(1.0 - s)?;
+        // Γ(1 - s) overflows long before the product does.
+        let magnitude = (s * 2f64.ln() + (s - 1.0) * PI.ln() + ln_gamma(1.0 - s)).exp();
+        return Ok(magnitude * sin_pi(s / 2.0) * reflected);
+    }
+
+    const N: usize = 60;
+    let mut d = Vec::with_capacity(N + 1);
+    let (mut term, mut sum) = (1.0, 1.0);
+    d.push(sum);
+    for i in 1..=N {
+        let (n, i) = (N as f64, i as f64);
+        term *= 4.0 * (n + i - 1.0) * (n - i + 1.0) / ((2.0 * i) * (2.0 * i - 1.0));
+        sum += term;
+        d.push(sum);
+    }
+    let series: f64 = (0..N)
+        .map(|k| {
+            let sign = if k % 2 == 0 { 1.0 } else { -1.0 };
+            sign * (d[k] - d[N]) / ((k + 1) as f64).powf(s)
+        })
+        .sum();
+    // 1 - 2^(1-s), without cancellation near s = 1.
+    let eta_factor = -((1.0 - s) * 2f64.ln()).exp_m1();
+    Ok(-series / (d[N] * eta_factor))
+}
+
+/// The Lambert W function, solving `w·e^w = x`. Branch 0 is defined for
+/// `x ≥ -1/e` and branch -1 for `-1/e ≤ x < 0`. Halley's method from a
+/// series or logarithmic starting point.
+pub fn lambert_w(x: f64, branch: i32) -> Result<f64, EvalError> {
+    let branch_point = -(-1.0f64).exp();
+    if !matches!(branch, 0 | -1) {
+        return Err(EvalError::MathError(format!("lambertw branch must be 0 or -1, got {}", branch)));
+    }
+    if x < branch_point || (branch == -1 && x >= 0.0) {
+        let domain = if branch == 0 { "[-1/e, ∞)" } else { "[-1/e, 0)" };
+        return Err(EvalError::MathError(format!("lambertw branch {} domain error: input must be in {}", branch, domain)));
+    }
+    if x == branch_point {
+        return Ok(-1.0);
+    }
+    if x == 0.0 {
+        return Ok(0.0);
+    }
+
+    let mut w = if x < -0.25 {
+        // Series about the branch point in p = ±√(2(ex + 1)).
+        let p = (2.0 * (std::f64::consts::E * x + 1.0)).sqrt() * if branch == 0 { 1.0 } else { -1.0 };
+        -1.0 + p - p * p / 3.0 + 11.0 / 72.0 * p * p * p
+    } else if branch == -1 {
+        let (l1, l2) = ((-x).ln(), (-(-x).ln()).ln());
+        l1 - l2 + l2 / l1
+    } else if x > std::f64::consts::E {
+        let l = x.ln();
+        l - l.ln()
+    } else {
+        x.ln_1p()
+    };
+
+    for _ in 0..50 {
+        let e = w.exp();
+        let f = w * e - x;
+        if f == 0.0 || w == -1.0 {
+            break;
+        }
+        let step = f / (e * (w + 1.0) - (w + 2.0) * f / (2.0 * w + 2.0));
+        w -= step;
+        if step.abs() <= 4.0 * f64::EPSILON * (1.0 + w.abs()) {
+            break;
+        }
+    }
+    Ok(w)
+}
+
+/// The arithmetic–geometric mean iteration behind the complete elliptic
+/// integrals, returning `(AGM, Σ 2^(n-1) c_n²)` with `c_0² = m`.
+fn elliptic_agm(m: f64) -> (f64, f64) {
+    let (mut a, mut b) = (1.0, (1.0 - m).sqrt());
+    let mut c2 = m;
+    let mut power = 0.5;
+    let mut sum = power * c2;
+    for _ in 0..50 {
+        let next_a = (a + b) / 2.0;
+        // c_{n+1} = (a - b)/2, computed as c_n²/(4 a_{n+1}) to avoid cancellation.
+        c2 = (c2 / (4.0 * next_a)).powi(2);
+        b = (a * b).sqrt();
+        a = next_a;
+        power *= 2.0;
+        sum += power * c2;
+        if c2 <= f64::EPSILON * f64::EPSILON {
+            break;
+        }
+    }
+    (a, sum)
+}
+
+/// Complete elliptic integral of the first kind `K(m)` with parameter
+/// `m = k²`, for `m < 1`.
+pub fn elliptic_k(m: f64) -> Result<f64, EvalError> {
+    if m >= 1.0 {
+        return Err(EvalError::MathError("ellipk domain error: parameter must be less than 1".to_string()));
+    }
+    Ok(PI / (2.0 * elliptic_agm(m).0))
+}
+
+/// Complete elliptic integral of the second kind `E(m)` with parameter
+/// `m = k²`, for `m ≤ 1`.
+pub fn elliptic_e(m: f64) -> Result<f64, EvalError> {
+    if m > 1.0 {
+        return Err(EvalError::MathError("ellipe domain error: parameter must be at most 1".to_string()));
+    }
+    if m == 1.0 {
+        return Ok(1.0);
+    }
+    let (agm, sum) = elliptic_agm(m);
+    Ok(PI / (2.0 * agm) * (1.0 - sum))
+}
+
+/// The exponential integral `E₁(x) = ∫ₓ^∞ e^(-t)/t dt` for `x > 0`: power
+/// series up to 1, continued fraction beyond.
+pub fn exp_integral_e1(x: f64) -> Result<f64, EvalError> {
+    if x <= 0.0 {
+        return Err(EvalError::MathError("expint domain error: input must be positive".to_string()));
+    }
+    if x <= 1.0 {
+        let (mut term, mut sum) = (1.0, 0.0);
+        for k in 1..100 {
+            term *= -x / k as f64;
+            sum += term / k as f64;
+            if term.abs() <= f64::EPSILON * sum.abs() {
+                break;
+            }
+        }
+        return Ok(-EULER_GAMMA - x.ln() - sum);
+    }
+    let tiny = 1e-300;
+    let mut b = x + 1.0;
+    let mut c = 1.0 / tiny;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..500 {
+        let a = -((i * i) as f64);
+        b += 2.0;
+        d = 1.0 / (a * d + b);
+        c = b + a / c;
+        let delta = c * d;
+        h *= delta;
+        if (delta - 1.0).abs() <= f64::EPSILON {
+            break;
+        }
+    }
+    Ok(h * (-x).exp())
+}
+
+/// The exponential integral `Ei(x)`, the principal value of `∫ e^t/t dt` up
+/// to `x`. Uses the power series up to 40, the asymptotic series beyond, and
+/// `-E₁(-x)` for negative `x`.
+pub fn exp_integral_ei(x: f64) -> Result<f64, EvalError> {
+    if x == 0.0 {
+        return Err(EvalError::MathError("ei is infinite at 0".to_string()));
+    }
+    if x < 0.0 {
+        return Ok(-exp_integral_e1(-x)?);
+    }
+    if x <= 40.0 {
+        let (mut term, mut sum) = (1.0, 0.0);
+        for k in 1..500 {
+            term *= x / k as f64;
+            sum += term / k as f64;
+            if term <= f64::EPSILON * sum {
+                break;
+            }
+        }
+        return Ok(EULER_GAMMA + x.ln() + sum);
+    }
+    let (mut term, mut sum) = (1.0, 1.0);
+    for k in 1..100 {
+        let next = term * k as f64 / x;
+        if next > term {
+            break;
+        }
+        term = next;
+        sum += term;
+        if term <= f64::EPSILON * sum {
+            break;
+        }
+    }
+    Ok(x.exp() / x * sum)
+}
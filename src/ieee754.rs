@@ -0,0 +1,208 @@
+//! Inspection of IEEE-754 binary32 and binary64 encodings.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::error::EvalError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Precision {
+    /// binary32, Rust's `f32`.
+    Single,
+    /// binary64, Rust's `f64`.
+    Double,
+}
+
+impl Precision {
+    fn exponent_bits(&self) -> u32 {
+        match self {
+            Precision::Single => 8,
+            Precision::Double => 11,
+        }
+    }
+
+    fn mantissa_bits(&self) -> u32 {
+        match self {
+            Precision::Single => 23,
+            Precision::Double => 52,
+        }
+    }
+
+    fn bias(&self) -> i32 {
+        (1 << (self.exponent_bits() - 1)) - 1
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FloatClass {
+    Zero,
+    Subnormal,
+    Normal,
+    Infinite,
+    Nan,
+}
+
+/// How a number is stored: its bit fields and the value they actually encode.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FloatLayout {
+    pub precision: Precision,
+    /// The value that was asked for.
+    pub input: f64,
+    /// The value the encoding holds, after rounding to the precision.
+    pub stored: f64,
+    /// The whole encoding as hexadecimal, e.g. `0x3FF0000000000000`.
+    pub bits: String,
+    pub sign: u8,
+    /// Exponent field as a bit string.
+    pub exponent: String,
+    /// Fraction field as a bit string, without the implicit leading bit.
+    pub mantissa: String,
+    pub biased_exponent: u32,
+    /// Power of two the significand is scaled by, or `None` for zero, infinity and NaN.
+    pub unbiased_exponent: Option<i32>,
+    pub class: FloatClass,
+}
+
+impl FloatLayout {
+    pub fn new(value: f64, precision: Precision) -> Self {
+        let (raw, stored) = match precision {
+            Precision::Single => ((value as f32).to_bits() as u64, value as f32 as f64),
+            Precision::Double => (value.to_bits(), value),
+        };
+        let (exp_bits, man_bits) = (precision.exponent_bits(), precision.mantissa_bits());
+        let biased = ((raw >> man_bits) & ((1 << exp_bits) - 1)) as u32;
+        let fraction = raw & ((1 << man_bits) - 1);
+        let max_exponent = (1 << exp_bits) - 1;
+
+        let class = match (biased, fraction) {
+            (0, 0) => FloatClass::Zero,
+            (0, _) => FloatClass::Subnormal,
+            (e, 0) if e == max_exponent => FloatClass::Infinite,
+            (e, _) if e == max_exponent => FloatClass::Nan,
+            _ => FloatClass::Normal,
+        };
+        let unbiased_exponent = match class {
+            FloatClass::Normal => Some(biased as i32 - precision.bias()),
+            FloatClass::Subnormal => Some(1 - precision.bias()),
+            _ => None,
+        };
+
+        let total = 1 + exp_bits + man_bits;
+        FloatLayout {
+            precision,
+            input: value,
+            stored,
+            bits: format!("0x{:0width$X}", raw, width = total as usize / 4),
+            sign: (raw >> (total - 1)) as u8,
+            exponent: format!("{:0width$b}", biased, width = exp_bits as usize),
+            mantissa: format!("{:0width$b}", fraction, width = man_bits as usize),
+            biased_exponent: biased,
+            unbiased_exponent,
+            class,
+        }
+    }
+}
+
+impl fmt::Display for FloatLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self.precision {
+            Precision::Single => "single (binary32)",
+            Precision::Double => "double (binary64)",
+        };
+        writeln!(f, "{}: {}", name, self.stored)?;
+        if self.stored != self.input && !self.input.is_nan() {
+            writeln!(f, "  rounding error  {:e}", self.stored - self.input)?;
+        }
+        writeln!(f, "  bits      {}", self.bits)?;
+        writeln!(f, "  sign      {} ({})", self.sign, if self.sign == 1 { "-" } else { "+" })?;
+        match self.unbiased_exponent {
+            Some(e) => writeln!(f, "  exponent  {} = 2^{}", self.exponent, e)?,
+            None => writeln!(f, "  exponent  {}", self.exponent)?,
+        }
+        writeln!(f, "  mantissa  {}", self.mantissa)?;
+        let class = match self.class {
+            FloatClass::Zero => "zero",
+            FloatClass::Subnormal => "subnormal",
+            FloatClass::Normal => "normal",
+            FloatClass::Infinite => "infinite",
+            FloatClass::Nan => "NaN",
+        };
+        write!(f, "  class     {}", class)
+    }
+}
+
+/// Reinterprets a bit pattern of the given width (32 or 64) as a float.
+pub fn from_bits(bits: u64, width: u32) -> Result<f64, EvalError> {
+    match width {
+        32 if bits <= u32::MAX as u64 => Ok(f32::from_bits(bits as u32) as f64),
+        32 => Err(EvalError::MathError(format!("0x{:X} does not fit in 32 bits", bits))),
+        64 => Ok(f64::from_bits(bits)),
+        _ => Err(EvalError::MathError(format!("bit width must be 32 or 64, got {}", width))),
+    }
+}
+
+/// Distance from `x` to the next double of larger magnitude.
+pub fn ulp(x: f64) -> Result<f64, EvalError> {
+    if !x.is_finite() {
+        return Err(EvalError::MathError(format!("ulp of {} is undefined", x)));
+    }
+    let x = x.abs();
+    if x == f64::MAX {
+        return Ok(x - x.next_down());
+    }
+    Ok(x.next_up() - x)
+}
+
+/// The next double after `x` in the direction of `toward`.
+pub fn next_after(x: f64, toward: f64) -> f64 {
+    if x.is_nan() || toward.is_nan() {
+        f64::NAN
+    } else if toward > x {
+        x.next_up()
+    } else if toward < x {
+        x.next_down()
+    } else {
+        toward
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_layout() {
+        let layout = FloatLayout::new(-2.5, Precision::Double);
+        assert_eq!(layout.bits, "0xC004000000000000");
+        assert_eq!(layout.sign, 1);
+        assert_eq!(layout.exponent, "10000000000");
+        assert_eq!(layout.unbiased_exponent, Some(1));
+        assert_eq!(layout.mantissa, format!("01{}", "0".repeat(50)));
+        assert_eq!(layout.class, FloatClass::Normal);
+    }
+
+    #[test]
+    fn test_single_layout() {
+        let layout = FloatLayout::new(0.1, Precision::Single);
+        assert_eq!(layout.bits, "0x3DCCCCCD");
+        assert_eq!(layout.unbiased_exponent, Some(-4));
+        assert!(layout.stored != 0.1);
+        assert_eq!(FloatLayout::new(1e-40, Precision::Single).class, FloatClass::Subnormal);
+        assert_eq!(FloatLayout::new(f64::INFINITY, Precision::Single).class, FloatClass::Infinite);
+        assert_eq!(FloatLayout::new(f64::NAN, Precision::Double).class, FloatClass::Nan);
+    }
+
+    #[test]
+    fn test_ulp_and_next_after() {
+        assert_eq!(ulp(1.0).unwrap(), f64::EPSILON);
+        assert_eq!(ulp(0.0).unwrap(), f64::from_bits(1));
+        assert!(ulp(f64::INFINITY).is_err());
+        assert_eq!(next_after(1.0, 2.0), 1.0 + f64::EPSILON);
+        assert_eq!(next_after(1.0, 0.0), 1.0 - f64::EPSILON / 2.0);
+        assert_eq!(from_bits(0x3FF0000000000000, 64).unwrap(), 1.0);
+        assert_eq!(from_bits(0x3F800000, 32).unwrap(), 1.0);
+    }
+}
@@ -0,0 +1,301 @@
+//! Tracing of implicit curves such as `x^2 + y^2 = 25` for plotting.
+//!
+//! The residual `left - right` is evaluated on a coarse grid over the view.
+//! Cells where it changes sign are split into quarters a few times, and the
+//! finest cells run marching squares: each edge whose ends differ in sign
+//! holds a point of the curve, placed by linear interpolation, and the
+//! points are joined across the cell. Ambiguous saddle cells are settled by
+//! the value at their centre, and sign changes across a pole (`tan(x) = y`)
+//! are recognised by the residual growing rather than vanishing between
+//! the corners, and dropped. The pieces are then linked into polylines.
+
+use std::collections::HashMap;
+
+use crate::{ast::{BinOp, Expr, UnOp}, compile::CompiledExpr, error::EvalError, evaluator::Evaluator, plot::{Curve, Point}};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImplicitOptions {
+    /// Cells along each axis of the coarse grid.
+    pub cells: usize,
+    /// How many times a cell the curve passes through is quartered.
+    pub refinements: u32,
+}
+
+impl Default for ImplicitOptions {
+    fn default() -> Self {
+        ImplicitOptions { cells: 64, refinements: 3 }
+    }
+}
+
+/// The expression that is zero where `equation` holds: `left - right` for
+/// an equation, the expression itself otherwise.
+pub fn residual(equation: &Expr) -> Expr {
+    match equation {
+        Expr::Equation { left, right } => Expr::BinaryOp {
+            left: left.clone(),
+            // `left - p%` would take p% of the left side; negating and adding
+            // keeps the right side's own value.
+            op: BinOp::Add,
+            right: Box::new(Expr::UnaryOp { op: UnOp::Negate, expr: right.clone() }),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Traces the curve where `f`, a function of two variables, is zero, over
+/// the view `x` by `y`.
+pub fn trace_implicit(
+    f: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    x: (f64, f64),
+    y: (f64, f64),
+    options: &ImplicitOptions,
+) -> Result<Curve, EvalError> {
+    if f.variables().len() != 2 {
+        return Err(EvalError::InvalidArguments("an implicit curve takes exactly two variables".to_string()));
+    }
+    for (from, to) in [x, y] {
+        if !from.is_finite() || !to.is_finite() || from >= to {
+            return Err(EvalError::InvalidArguments(format!(
+                "cannot trace over [{}, {}]: expected a finite range with from < to", from, to
+            )));
+        }
+    }
+
+    let cells = options.cells.max(1);
+    let size = 1 << options.refinements;
+    let fine = cells * size;
+    let mut tracer = Tracer {
+        f,
+        evaluator,
+        origin: (x.0, y.0),
+        step: ((x.1 - x.0) / fine as f64, (y.1 - y.0) / fine as f64),
+        values: HashMap::new(),
+        pieces: Vec::new(),
+    };
+    for i in 0..cells {
+        for j in 0..cells {
+            tracer.refine(i * size, j * size, size);
+        }
+    }
+    Ok(Curve { segments: link(&tracer.pieces) })
+}
+
+/// An edge of the finest grid: horizontal edges run from node `(i, j)` to
+/// `(i + 1, j)`, vertical ones from `(i, j)` to `(i, j + 1)`. A point on an
+/// edge is shared by the two cells on either side, which is how pieces are
+/// linked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Edge {
+    Horizontal(usize, usize),
+    Vertical(usize, usize),
+}
+
+/// A piece of the curve crossing one cell.
+struct Piece {
+    ends: [(Edge, Point); 2],
+}
+
+struct Tracer<'a> {
+    f: &'a CompiledExpr,
+    evaluator: &'a mut Evaluator,
+    origin: (f64, f64),
+    step: (f64, f64),
+    /// Residuals at the nodes of the finest grid, computed on demand.
+    values: HashMap<(usize, usize), f64>,
+    pieces: Vec<Piece>,
+}
+
+impl Tracer<'_> {
+    fn position(&self, i: f64, j: f64) -> Point {
+        Point { x: self.origin.0 + i * self.step.0, y: self.origin.1 + j * self.step.1 }
+    }
+
+    fn eval_at(&mut self, p: Point) -> f64 {
+        self.f.eval(self.evaluator, &[p.x, p.y])
+    }
+
+    fn value(&mut self, i: usize, j: usize) -> f64 {
+        if let Some(&v) = self.values.get(&(i, j)) {
+            return v;
+        }
+        let v = self.eval_at(self.position(i as f64, j as f64));
+        self.values.insert((i, j), v);
+        v
+    }
+
+    /// Quarters the cell with lower-left node `(i, j)` and side `size` down
+    /// to single cells, as long as the curve passes through it.
+    fn refine(&mut self, i: usize, j: usize, size: usize) {
+        let corners = [self.value(i, j), self.value(i + size, j), self.value(i + size, j + size), self.value(i, j + size)];
+        let finite: Vec<f64> = corners.into_iter().filter(|v| v.is_finite()).collect();
+        let crosses = finite.iter().any(|&v| v > 0.0) && finite.iter().any(|&v| v <= 0.0);
+        if !crosses {
+            return;
+        }
+        if size == 1 {
+            if finite.len() == 4 {
+                self.march(i, j, corners);
+            }
+            return;
+        }
+        let half = size / 2;
+        for (di, dj) in [(0, 0), (half, 0), (0, half), (half, half)] {
+            self.refine(i + di, j + dj, half);
+        }
+    }
+
+    /// Adds the pieces of curve crossing the single cell at `(i, j)`, whose
+    /// corner values run counter-clockwise from the lower left.
+    fn march(&mut self, i: usize, j: usize, [a, b, c, d]: [f64; 4]) {
+        let (fi, fj) = (i as f64, j as f64);
+        // Edges in order bottom, right, top, left, each with its end nodes.
+        let edges = [
+            (Edge::Horizontal(i, j), (fi, fj, a), (fi + 1.0, fj, b)),
+            (Edge::Vertical(i + 1, j), (fi + 1.0, fj, b), (fi + 1.0, fj + 1.0, c)),
+            (Edge::Horizontal(i, j + 1), (fi, fj + 1.0, d), (fi + 1.0, fj + 1.0, c)),
+            (Edge::Vertical(i, j), (fi, fj, a), (fi, fj + 1.0, d)),
+        ];
+        let crossings: Vec<(Edge, Point)> = edges.iter()
+            .filter(|(_, p, q)| (p.2 > 0.0) != (q.2 > 0.0))
+            .map(|&(edge, p, q)| {
+                let t = p.2 / (p.2 - q.2);
+                (edge, self.position(p.0 + t * (q.0 - p.0), p.1 + t * (q.1 - p.1)))
+            })
+            .collect();
+
+        let pairs = match crossings.len() {
+            2 => vec![(0, 1)],
+            4 => {
+                // A saddle: the centre tells which diagonal is connected.
+                let centre = self.eval_at(self.position(fi + 0.5, fj + 0.5));
+                if (centre > 0.0) == (a > 0.0) {
+                    vec![(0, 1), (2, 3)]
+                } else {
+                    vec![(3, 0), (1, 2)]
+                }
+            },
+            _ => vec![],
+        };
+
+        let largest = [a, b, c, d].into_iter().fold(0.0, |m: f64, v| m.max(v.abs()));
+        for (first, second) in pairs {
+            let ends = [crossings[first], crossings[second]];
+            let mid = Point { x: (ends[0].1.x + ends[1].1.x) / 2.0, y: (ends[0].1.y + ends[1].1.y) / 2.0 };
+            // On the curve the residual nearly vanishes; at a pole it is
+            // larger than anywhere on the cell's corners.
+            if self.eval_at(mid).abs() <= largest {
+                self.pieces.push(Piece { ends });
+            }
+        }
+    }
+}
+
+/// Joins pieces that share an edge into polylines. Open curves are walked
+/// from their loose ends first, then the remaining closed loops.
+fn link(pieces: &[Piece]) -> Vec<Vec<Point>> {
+    let mut by_edge: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (index, piece) in pieces.iter().enumerate() {
+        for (edge, _) in piece.ends {
+            by_edge.entry(edge).or_default().push(index);
+        }
+    }
+
+    let mut used = vec![false; pieces.len()];
+    let mut polylines = Vec::new();
+    let loose_ends = pieces.iter().enumerate().flat_map(|(index, piece)| {
+        piece.ends.iter()
+            .filter(|(edge, _)| by_edge[edge].len() == 1)
+            .map(move |&(edge, _)| (index, edge))
+    });
+    let loops = pieces.iter().enumerate().map(|(index, piece)| (index, piece.ends[0].0));
+
+    for (start, edge) in loose_ends.collect::<Vec<_>>().into_iter().chain(loops) {
+        if used[start] {
+            continue;
+        }
+        let mut line = vec![end_at(&pieces[start], edge)];
+        let (mut index, mut edge) = (start, edge);
+        loop {
+            used[index] = true;
+            let (next_edge, point) = other_end(&pieces[index], edge);
+            line.push(point);
+            match by_edge[&next_edge].iter().find(|&&other| !used[other]) {
+                Some(&next) => (index, edge) = (next, next_edge),
+                None => break,
+            }
+        }
+        polylines.push(line);
+    }
+    polylines
+}
+
+fn end_at(piece: &Piece, edge: Edge) -> Point {
+    piece.ends.iter().find(|(e, _)| *e == edge).map(|&(_, p)| p).unwrap_or(piece.ends[0].1)
+}
+
+fn other_end(piece: &Piece, edge: Edge) -> (Edge, Point) {
+    if piece.ends[0].0 == edge { piece.ends[1] } else { piece.ends[0] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::AngleMode, lexer::Lexer, parser::Parser};
+
+    fn trace(input: &str, x: (f64, f64), y: (f64, f64)) -> Curve {
+        let mut evaluator = Evaluator::new();
+        evaluator.get_context_mut().set_angle_mode(AngleMode::Radians);
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let equation = Parser::new(tokens).parse_equation().unwrap();
+        let f = CompiledExpr::new(&residual(&equation), &["x", "y"], &mut evaluator).unwrap();
+        trace_implicit(&f, &mut evaluator, x, y, &ImplicitOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_circle_is_one_closed_loop() {
+        let curve = trace("x^2 + y^2 = 25", (-6.0, 6.0), (-6.0, 6.0));
+        assert_eq!(curve.segments.len(), 1);
+        let line = &curve.segments[0];
+        assert_eq!(line.first(), line.last());
+        assert!(line.len() > 200);
+        // Linear interpolation on cells 12/512 wide stays close to the circle.
+        assert!(curve.points().all(|p| (p.x.hypot(p.y) - 5.0).abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_open_curves_and_clipping() {
+        // The line leaves the view at both ends.
+        let line = trace("y = 2*x + 1", (-5.0, 5.0), (-5.0, 5.0));
+        assert_eq!(line.segments.len(), 1);
+        assert!(line.points().all(|p| (p.y - 2.0 * p.x - 1.0).abs() < 1e-9));
+
+        // A hyperbola has two branches; a circle cut by the view is an arc.
+        assert_eq!(trace("x*y = 1", (-3.0, 3.0), (-3.0, 3.0)).segments.len(), 2);
+        assert_eq!(trace("x^2 + y^2 = 25", (0.0, 6.0), (-6.0, 6.0)).segments.len(), 1);
+    }
+
+    #[test]
+    fn test_saddles_and_poles() {
+        // Two lines crossing at the origin meet at a saddle.
+        let cross = trace("x^2 = y^2", (-1.03, 1.0), (-1.01, 1.0));
+        assert!(cross.points().all(|p| (p.x.abs() - p.y.abs()).abs() < 1e-3));
+        assert!(cross.segments.len() <= 4);
+
+        // tan(x) = 0 crosses zero at multiples of PI, but its sign changes at
+        // the poles in between are not part of the curve.
+        let curve = trace("tan(x) = 0", (-4.0, 4.0), (-1.0, 1.0));
+        assert_eq!(curve.segments.len(), 3);
+        let pi = std::f64::consts::PI;
+        assert!(curve.points().all(|p| (p.x - (p.x / pi).round() * pi).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut evaluator = Evaluator::new();
+        let f = CompiledExpr::new(&Expr::Variable("x".to_string()), &["x"], &mut evaluator).unwrap();
+        assert!(trace_implicit(&f, &mut evaluator, (0.0, 1.0), (0.0, 1.0), &ImplicitOptions::default()).is_err());
+        let g = CompiledExpr::new(&Expr::Variable("x".to_string()), &["x", "y"], &mut evaluator).unwrap();
+        assert!(trace_implicit(&g, &mut evaluator, (0.0, 1.0), (1.0, 0.0), &ImplicitOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+//! Fixed-width integer arithmetic for programming mode.
+//!
+//! Integer values are held in an `i128` and wrapped to the context's word
+//! size after every operation, giving two's-complement semantics for 8, 16,
+//! 32 and 64-bit, signed or unsigned words.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{error::EvalError, radix::format_integer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct WordSize {
+    pub bits: u32,
+    pub signed: bool,
+}
+
+impl WordSize {
+    pub const DEFAULT: WordSize = WordSize { bits: 64, signed: true };
+
+    pub fn new(bits: u32, signed: bool) -> Result<Self, EvalError> {
+        if !matches!(bits, 8 | 16 | 32 | 64) {
+            return Err(EvalError::MathError(format!(
+                "word size must be 8, 16, 32 or 64 bits, got {}", bits
+            )));
+        }
+        Ok(WordSize { bits, signed })
+    }
+
+    fn mask(&self) -> u128 {
+        (1u128 << self.bits) - 1
+    }
+
+    /// Reduces a value to the word size, reinterpreting the top bit as the sign for signed words.
+    pub fn wrap(&self, value: i128) -> i128 {
+        let bits = (value as u128) & self.mask();
+        if self.signed && bits >> (self.bits - 1) == 1 {
+            bits as i128 - (1i128 << self.bits)
+        } else {
+            bits as i128
+        }
+    }
+
+    /// The raw bit pattern of a value, i.e. its two's complement for negative numbers.
+    pub fn to_unsigned(&self, value: i128) -> u128 {
+        (value as u128) & self.mask()
+    }
+
+    /// Converts a number to an integer of this word size, wrapping out-of-range values.
+    pub fn from_f64(&self, value: f64) -> Result<i128, EvalError> {
+        if !value.is_finite() || value.fract() != 0.0 || value.abs() >= 2f64.powi(127) {
+            return Err(EvalError::MathError(format!(
+                "expected an integer, got {}", value
+            )));
+        }
+        Ok(self.wrap(value as i128))
+    }
+
+    /// Shifts left, discarding bits that leave the word.
+    pub fn shift_left(&self, value: i128, amount: u32) -> i128 {
+        if amount >= self.bits {
+            return 0;
+        }
+        self.wrap(((value as u128) << amount) as i128)
+    }
+
+    /// Shifts right: arithmetic for signed words, logical for unsigned ones.
+    pub fn shift_right(&self, value: i128, amount: u32) -> i128 {
+        let value = self.wrap(value);
+        if amount >= self.bits {
+            return if value < 0 { -1 } else { 0 };
+        }
+        value >> amount
+    }
+}
+
+impl Default for WordSize {
+    fn default() -> Self {
+        WordSize::DEFAULT
+    }
+}
+
+impl fmt::Display for WordSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-bit {}", self.bits, if self.signed { "signed" } else { "unsigned" })
+    }
+}
+
+/// An integer shown in every base at once, for the programming tab.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IntegerView {
+    pub dec: String,
+    pub hex: String,
+    pub bin: String,
+    pub oct: String,
+    pub bits: u32,
+    pub signed: bool,
+}
+
+impl IntegerView {
+    pub fn new(value: i128, word: WordSize) -> Self {
+        let value = word.wrap(value);
+        IntegerView {
+            dec: format_integer(value, 10, word),
+            hex: format_integer(value, 16, word),
+            bin: format_integer(value, 2, word),
+            oct: format_integer(value, 8, word),
+            bits: word.bits,
+            signed: word.signed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrapping() {
+        let i8 = WordSize::new(8, true).unwrap();
+        let u8 = WordSize::new(8, false).unwrap();
+        assert_eq!(i8.wrap(127 + 1), -128);
+        assert_eq!(u8.wrap(255 + 1), 0);
+        assert_eq!(u8.wrap(-1), 255);
+        assert_eq!(WordSize::DEFAULT.wrap(i64::MAX as i128 + 1), i64::MIN as i128);
+        assert!(WordSize::new(12, true).is_err());
+    }
+
+    #[test]
+    fn test_shifts() {
+        let i8 = WordSize::new(8, true).unwrap();
+        let u8 = WordSize::new(8, false).unwrap();
+        assert_eq!(i8.shift_left(1, 7), -128);
+        assert_eq!(i8.shift_right(-128, 7), -1);
+        assert_eq!(u8.shift_right(128, 7), 1);
+        assert_eq!(u8.shift_left(1, 8), 0);
+    }
+
+    #[test]
+    fn test_integer_view() {
+        let view = IntegerView::new(-1, WordSize::new(16, true).unwrap());
+        assert_eq!(view.dec, "-1");
+        assert_eq!(view.hex, "0xFFFF");
+        assert_eq!(view.oct, "0o177777");
+        assert_eq!(view.bin, "0b1111111111111111");
+    }
+}
@@ -1,6 +1,6 @@
-/// Lexer module responsible for tokenizing input strings.
+//! Lexer module responsible for tokenizing input strings.
 
-use crate::error::LexError;
+use crate::{datetime::DateTime, error::LexError, radix};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TokenWithPos {
@@ -18,17 +18,47 @@ impl TokenWithPos {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Number(f64),
+    /// A whole `0x`, `0b` or `0o` literal, which evaluates to a fixed-width integer.
+    Integer(i128),
+    DateTime(DateTime),
     Plus,
     Minus,
     Asterisk,
     Slash,
+    DoubleSlash,
+    Percent,
     LParen,
     RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Semicolon,
     Caret,
     Identifier(String),
     Exclamation,
     Comma,
     Equals,
+    Ampersand,
+    Pipe,
+    Tilde,
+    ShiftLeft,
+    ShiftRight,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    EqualEqual,
+    NotEqual,
+    To,
+    Xor,
+    Mod,
+    Rem,
+    And,
+    Or,
+    Not,
+    True,
+    False,
     EOF,
 }
 
@@ -77,10 +107,18 @@ impl Lexer {
                 self.advance();
                 Token::Asterisk
             },
+            Some('/') if self.peek_char() == Some('/') => {
+                self.position += 2;
+                Token::DoubleSlash
+            },
             Some('/') => {
                 self.advance();
                 Token::Slash
             },
+            Some('%') => {
+                self.advance();
+                Token::Percent
+            },
             Some('(') => {
                 self.advance();
                 Token::LParen
@@ -89,10 +127,34 @@ impl Lexer {
                 self.advance();
                 Token::RParen
             },
+            Some('[') => {
+                self.advance();
+                Token::LBracket
+            },
+            Some(']') => {
+                self.advance();
+                Token::RBracket
+            },
+            Some('{') => {
+                self.advance();
+                Token::LBrace
+            },
+            Some('}') => {
+                self.advance();
+                Token::RBrace
+            },
+            Some(';') => {
+                self.advance();
+                Token::Semicolon
+            },
             Some('^') => {
                 self.advance();
                 Token::Caret
             },
+            Some('!') if self.peek_char() == Some('=') => {
+                self.position += 2;
+                Token::NotEqual
+            },
             Some('!') => {
                 self.advance();
                 Token::Exclamation
@@ -101,11 +163,61 @@ impl Lexer {
                 self.advance();
                 Token::Comma
             },
+            Some('=') if self.peek_char() == Some('=') => {
+                self.position += 2;
+                Token::EqualEqual
+            },
             Some('=') => {
                 self.advance();
                 Token::Equals
             },
-            Some(ch) if ch == '.' || ch.is_digit(10) => {
+            Some('&') => {
+                self.advance();
+                Token::Ampersand
+            },
+            Some('|') => {
+                self.advance();
+                Token::Pipe
+            },
+            Some('~') => {
+                self.advance();
+                Token::Tilde
+            },
+            Some('<') => match self.peek_char() {
+                Some('<') => {
+                    self.position += 2;
+                    Token::ShiftLeft
+                },
+                Some('=') => {
+                    self.position += 2;
+                    Token::LessEqual
+                },
+                _ => {
+                    self.advance();
+                    Token::Less
+                },
+            },
+            Some('>') => match self.peek_char() {
+                Some('>') => {
+                    self.position += 2;
+                    Token::ShiftRight
+                },
+                Some('=') => {
+                    self.position += 2;
+                    Token::GreaterEqual
+                },
+                _ => {
+                    self.advance();
+                    Token::Greater
+                },
+            },
+            Some('0') if self.radix_prefix().is_some() => {
+                return self.read_radix_number(start_pos);
+            },
+            Some(ch) if ch.is_ascii_digit() && self.datetime_length() > 0 => {
+                return self.read_datetime(start_pos);
+            },
+            Some(ch) if ch == '.' || ch.is_ascii_digit() => {
                 return self.read_number(start_pos);
             },
             Some(ch) if ch.is_alphabetic() => {
@@ -125,6 +237,10 @@ impl Lexer {
         self.input.get(self.position).cloned()
     }
 
+    fn peek_char(&self) -> Option<char> {
+        self.input.get(self.position + 1).cloned()
+    }
+
     fn advance(&mut self) {
         self.position += 1;
     }
@@ -144,7 +260,7 @@ impl Lexer {
         let mut has_dot = false;
 
         while let Some(ch) = self.current_char() {
-            if ch.is_digit(10) {
+            if ch.is_ascii_digit() {
                 self.advance();
             } else if ch == '.' && !has_dot {
                 has_dot = true;
@@ -162,6 +278,113 @@ impl Lexer {
         }
     }
 
+    /// Base of a `0x`, `0b` or `0o` prefix at the current position, if it is
+    /// followed by a digit of that base.
+    fn radix_prefix(&self) -> Option<u32> {
+        let base = match self.input.get(self.position + 1)? {
+            'x' | 'X' => 16,
+            'b' | 'B' => 2,
+            'o' | 'O' => 8,
+            _ => return None,
+        };
+        let first_digit = *self.input.get(self.position + 2)?;
+        radix::digit_value(first_digit, base).map(|_| base)
+    }
+
+    /// Reads a prefixed literal such as `0xFF`, `0b1010_0101` or `0b0.101`.
+    /// Whole literals become integers; literals with a fraction become numbers.
+    fn read_radix_number(&mut self, start_pos: usize) -> Result<TokenWithPos, LexError> {
+        let base = self.radix_prefix().unwrap();
+        self.position += 2;
+
+        let mut whole: Option<i128> = Some(0);
+        let mut fraction = None;
+        let mut scale = 1.0;
+        while let Some(ch) = self.current_char() {
+            if ch == '_' {
+                self.advance();
+                continue;
+            }
+            if ch == '.' && fraction.is_none() {
+                fraction = Some(0.0);
+                self.advance();
+                continue;
+            }
+            let Some(digit) = radix::digit_value(ch, base) else { break };
+            match fraction.as_mut() {
+                None => whole = whole.and_then(|w| w.checked_mul(base as i128)?.checked_add(digit as i128)),
+                Some(f) => {
+                    scale /= base as f64;
+                    *f += digit as f64 * scale;
+                },
+            }
+            self.advance();
+        }
+
+        // Swallow trailing letters and digits so `0b102` is reported as a whole.
+        let digits_end = self.position;
+        while self.current_char().is_some_and(|ch| ch.is_alphanumeric()) {
+            self.advance();
+        }
+        let literal = self.input[start_pos..self.position].iter().collect::<String>();
+        let length = self.position - start_pos;
+        let invalid = || LexError::InvalidNumber(literal.clone());
+        if self.position != digits_end || self.input[digits_end - 1] == '.' {
+            return Err(invalid());
+        }
+
+        let whole = whole.ok_or_else(invalid)?;
+        let token = match fraction {
+            Some(f) => Token::Number(whole as f64 + f),
+            None => Token::Integer(whole),
+        };
+        Ok(TokenWithPos::new(token, start_pos, length))
+    }
+
+    /// Length of a date (`2026-10-17`), timestamp (`2026-10-17T14:30`) or time
+    /// (`14:30:05`) literal starting at the current position, or 0 if there is none.
+    fn datetime_length(&self) -> usize {
+        let digits_at = |pos: usize, count: usize| {
+            (pos..pos + count).all(|i| self.input.get(i).is_some_and(|c| c.is_ascii_digit()))
+                && !self.input.get(pos + count).is_some_and(|c| c.is_ascii_digit())
+        };
+        let char_at = |pos: usize, ch: char| self.input.get(pos) == Some(&ch);
+        let time_length = |pos: usize| {
+            let hour_digits = if digits_at(pos, 2) { 2 } else if digits_at(pos, 1) { 1 } else { return 0 };
+            let minutes = pos + hour_digits + 1;
+            if !char_at(pos + hour_digits, ':') || !digits_at(minutes, 2) {
+                return 0;
+            }
+            if char_at(minutes + 2, ':') && digits_at(minutes + 3, 2) {
+                hour_digits + 6
+            } else {
+                hour_digits + 3
+            }
+        };
+
+        let p = self.position;
+        if digits_at(p, 4) && char_at(p + 4, '-') && digits_at(p + 5, 2) && char_at(p + 7, '-') && digits_at(p + 8, 2) {
+            if char_at(p + 10, 'T') {
+                let time = time_length(p + 11);
+                if time > 0 {
+                    return 11 + time;
+                }
+            }
+            return 10;
+        }
+        time_length(p)
+    }
+
+    fn read_datetime(&mut self, start_pos: usize) -> Result<TokenWithPos, LexError> {
+        let length = self.datetime_length();
+        let literal = self.input[self.position..self.position + length].iter().collect::<String>();
+        self.position += length;
+        match DateTime::parse(&literal) {
+            Ok(dt) => Ok(TokenWithPos::new(Token::DateTime(dt), start_pos, length)),
+            Err(_) => Err(LexError::InvalidDate(literal)),
+        }
+    }
+
     fn read_identifier(&mut self, start_pos: usize) -> TokenWithPos {
         let start = self.position;
 
@@ -172,10 +395,27 @@ impl Lexer {
                 break;
             }
         }
+        // Primes mark derivatives, as in the `y'` of `y' = -y`. The parser
+        // keeps primed names out of assignments.
+        while self.current_char() == Some('\'') {
+            self.advance();
+        }
 
         let ident_str = self.input[start..self.position].iter().collect::<String>();
         let length = self.position - start_pos;
-        TokenWithPos::new(Token::Identifier(ident_str), start_pos, length)
+        let token = match ident_str.as_str() {
+            "to" => Token::To,
+            "xor" => Token::Xor,
+            "mod" => Token::Mod,
+            "rem" => Token::Rem,
+            "and" => Token::And,
+            "or" => Token::Or,
+            "not" => Token::Not,
+            "true" => Token::True,
+            "false" => Token::False,
+            _ => Token::Identifier(ident_str),
+        };
+        TokenWithPos::new(token, start_pos, length)
     }
 }
 
@@ -212,5 +452,127 @@ mod tests {
         assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Identifier("y".to_string()), position: 13, length: 1 });
         assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::RParen, position: 14, length: 1 });
         assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::EOF, position: 15, length: 1 });
+
+        let mut lexer = Lexer::new("y'' = -y");
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Identifier("y''".to_string()), position: 0, length: 3 });
+        assert_eq!(lexer.next_token().unwrap().token, Token::Equals);
+    }
+
+    #[test]
+    fn test_unit_conversion_tokens() {
+        let mut lexer = Lexer::new("100 km/h to m/s");
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Number(100.0), position: 0, length: 3 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Identifier("km".to_string()), position: 4, length: 2 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Slash, position: 6, length: 1 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Identifier("h".to_string()), position: 7, length: 1 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::To, position: 9, length: 2 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Identifier("m".to_string()), position: 12, length: 1 });
+    }
+
+    #[test]
+    fn test_datetime_tokens() {
+        let mut lexer = Lexer::new("2026-12-25 - 2026-10-17T08:30 + 9:05 - 2026-1");
+        let date = DateTime::parse("2026-12-25").unwrap();
+        let timestamp = DateTime::parse("2026-10-17T08:30").unwrap();
+        let time = DateTime::parse("9:05").unwrap();
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::DateTime(date), position: 0, length: 10 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Minus, position: 11, length: 1 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::DateTime(timestamp), position: 13, length: 16 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Plus, position: 30, length: 1 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::DateTime(time), position: 32, length: 4 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Minus, position: 37, length: 1 });
+        assert_eq!(lexer.next_token().unwrap(), TokenWithPos { token: Token::Number(2026.0), position: 39, length: 4 });
+        assert!(Lexer::new("2026-02-30").tokenize().is_err());
+    }
+
+    #[test]
+    fn test_bitwise_tokens() {
+        let tokens: Vec<Token> = Lexer::new("~a & b | c xor 1 << 2 >> 3")
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+        assert_eq!(tokens, vec![
+            Token::Tilde,
+            Token::Identifier("a".to_string()),
+            Token::Ampersand,
+            Token::Identifier("b".to_string()),
+            Token::Pipe,
+            Token::Identifier("c".to_string()),
+            Token::Xor,
+            Token::Number(1.0),
+            Token::ShiftLeft,
+            Token::Number(2.0),
+            Token::ShiftRight,
+            Token::Number(3.0),
+            Token::EOF,
+        ]);
+    }
+
+    #[test]
+    fn test_comparison_and_logical_tokens() {
+        let tokens: Vec<Token> = Lexer::new("a < b <= c == d != e >= f > g = 5! and not true or false")
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.token)
+            .filter(|t| !matches!(t, Token::Identifier(_)))
+            .collect();
+        assert_eq!(tokens, vec![
+            Token::Less,
+            Token::LessEqual,
+            Token::EqualEqual,
+            Token::NotEqual,
+            Token::GreaterEqual,
+            Token::Greater,
+            Token::Equals,
+            Token::Number(5.0),
+            Token::Exclamation,
+            Token::And,
+            Token::Not,
+            Token::True,
+            Token::Or,
+            Token::False,
+            Token::EOF,
+        ]);
+    }
+
+    #[test]
+    fn test_modulo_and_percent_tokens() {
+        let tokens: Vec<Token> = Lexer::new("7 mod 3 rem 2 // 1 / 15%")
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+        assert_eq!(tokens, vec![
+            Token::Number(7.0),
+            Token::Mod,
+            Token::Number(3.0),
+            Token::Rem,
+            Token::Number(2.0),
+            Token::DoubleSlash,
+            Token::Number(1.0),
+            Token::Slash,
+            Token::Number(15.0),
+            Token::Percent,
+            Token::EOF,
+        ]);
+    }
+
+    #[test]
+    fn test_radix_literals() {
+        let token = |input: &str| Lexer::new(input).next_token().map(|t| t.token);
+        assert_eq!(token("0xFF").unwrap(), Token::Integer(255));
+        assert_eq!(token("0b1010_0101").unwrap(), Token::Integer(165));
+        assert_eq!(token("0o17").unwrap(), Token::Integer(15));
+        assert_eq!(token("0b0.101").unwrap(), Token::Number(0.625));
+        assert_eq!(token("0x1.8").unwrap(), Token::Number(1.5));
+        assert!(token("0b102").is_err());
+        assert!(token("0b10a1").is_err());
+        assert!(token("0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF").is_err());
+        // Without a digit after the prefix this is still zero followed by an identifier.
+        assert_eq!(token("0bar").unwrap(), Token::Number(0.0));
     }
 }
\ No newline at end of file
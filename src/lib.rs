@@ -1,8 +1,28 @@
+pub mod analysis;
 pub mod ast;
+pub mod braille;
 pub mod parser;
 pub mod lexer;
+pub mod chart;
+pub mod compile;
 pub mod context;
+pub mod currency;
+pub mod datetime;
+pub mod distributions;
 pub mod evaluator;
 pub mod error;
 pub mod functions;
-pub mod repl;
\ No newline at end of file
+pub mod ieee754;
+pub mod implicit;
+pub mod integer;
+pub mod ode;
+pub mod optimize;
+pub mod plot;
+pub mod radix;
+pub mod random;
+pub mod repl;
+pub mod svg;
+pub mod symbolic;
+pub mod table;
+pub mod units;
+pub mod value;
\ No newline at end of file
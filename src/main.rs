@@ -1,15 +1,38 @@
+use std::path::Path;
+
 use matheval::{
+    braille::{self, TerminalOptions},
+    chart::{Series, View},
+    ast::{Expr, Function},
+    compile::CompiledExpr,
     context::AngleMode,
+    currency::RateTable,
     error::{LexError, ParseError},
     evaluator::Evaluator,
+    ieee754::{FloatLayout, Precision},
+    integer::WordSize,
     lexer::Lexer,
     parser::Parser,
+    plot::{self, SampleOptions},
+    radix,
     repl::CalcHelper,
+    svg::{self, SvgOptions},
+    value::Value,
 };
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("plot") {
+        if let Err(e) = plot_command(&args[1..]) {
+            eprintln!("Error: {}", e);
+            eprintln!("{}", PLOT_USAGE);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     println!("=== Calculus Engine REPL ===");
     println!("Type 'exit' or 'quit' to leave the REPL.");
     println!("Type 'help' for available functions\n");
@@ -19,7 +42,7 @@ fn main() {
     let mut evaluator = Evaluator::new();
 
     let mut helper = CalcHelper::new();
-    helper.update_variables(evaluator.get_context().get_variables().keys().cloned().collect());
+    helper.update_variables(completion_names(&evaluator));
     let mut rl = Editor::new().expect("Failed to create REPL editor");
     rl.set_helper(Some(helper));
 
@@ -48,11 +71,22 @@ fn main() {
                     "rad" => set_angle_mode(&mut evaluator, AngleMode::Radians),
                     "grad" => set_angle_mode(&mut evaluator, AngleMode::Gradians),
                     "mode" => show_mode(&evaluator),
+                    name @ ("hex" | "bin" | "oct" | "dec") => set_output_base(&mut evaluator, radix::named_base(name).unwrap()),
+                    cmd if cmd == "base" || cmd.starts_with("base ") => handle_base(cmd, &mut evaluator),
+                    cmd if cmd.starts_with("ieee ") => inspect_float(&cmd[5..], &mut evaluator),
+                    cmd if cmd == "rates" || cmd.starts_with("rates ") => handle_rates(cmd, &mut evaluator),
+                    cmd if cmd == "word" || cmd.starts_with("word ") => handle_word(cmd, &mut evaluator),
+                    cmd if cmd == "seed" || cmd.starts_with("seed ") => handle_seed(cmd, &mut evaluator),
+                    cmd if cmd.starts_with("plot ") => {
+                        let dimensions = rl.dimensions();
+                        handle_plot(&cmd[5..], &mut evaluator, dimensions);
+                    },
+                    cmd if cmd == "export" || cmd.starts_with("export ") => export_table(cmd[6..].trim(), &evaluator),
                     "" => continue,
                     _ => {
                         process_input(trimmed, &mut evaluator);
                         if let Some(h) = rl.helper_mut() {
-                            h.update_variables(evaluator.get_context().get_variables().keys().cloned().collect());
+                            h.update_variables(completion_names(&evaluator));
                         }
                     },
                 }
@@ -75,7 +109,25 @@ fn main() {
     println!("Goodbye");
 }
 
+/// Variables and user-defined functions offered by tab completion.
+fn completion_names(evaluator: &Evaluator) -> Vec<String> {
+    let context = evaluator.get_context();
+    context.get_variables().keys()
+        .chain(context.get_functions().keys())
+        .cloned()
+        .collect()
+}
+
 fn process_input(input: &str, evaluator: &mut Evaluator) {
+    match evaluate_input(input, evaluator) {
+        Some(Value::Table(table)) => println!("{}", table),
+        Some(result) => println!("= {}", evaluator.get_context().format_value(&result)),
+        None => {},
+    }
+}
+
+/// Lexes, parses and evaluates a line, reporting any error on stderr.
+fn evaluate_input(input: &str, evaluator: &mut Evaluator) -> Option<Value> {
     let mut lexer = Lexer::new(input);
     let tokens = match lexer.tokenize() {
         Ok(toks) => toks,
@@ -84,7 +136,7 @@ fn process_input(input: &str, evaluator: &mut Evaluator) {
             if let Some(pos) = get_lexer_error_position(&e) {
                 show_error_context(input, pos);
             }
-            return;
+            return None;
         }
     };
 
@@ -94,13 +146,28 @@ fn process_input(input: &str, evaluator: &mut Evaluator) {
         Err(e) => {
             eprintln!("Parsing error: {}", e);
             show_error_context(input, get_parse_error_position(&e));
-            return;
+            return None;
         }
     };
 
     match evaluator.evaluate(&ast) {
-        Ok(result) => println!("= {}", result),
-        Err(e) => eprintln!("Evaluation error: {}", e),
+        Ok(result) => Some(result),
+        Err(e) => {
+            eprintln!("Evaluation error: {}", e);
+            None
+        },
+    }
+}
+
+/// Shows how the value of an expression is stored as a single and a double.
+fn inspect_float(input: &str, evaluator: &mut Evaluator) {
+    let Some(value) = evaluate_input(input, evaluator) else { return };
+    match value.as_number() {
+        Ok(x) => {
+            println!("{}", FloatLayout::new(x, Precision::Single));
+            println!("{}", FloatLayout::new(x, Precision::Double));
+        },
+        Err(e) => eprintln!("{}", e),
     }
 }
 
@@ -114,7 +181,7 @@ fn get_parse_error_position(error: &ParseError) -> usize {
 
 fn get_lexer_error_position(error: &LexError) -> Option<usize> {
     match error {
-        LexError::InvalidNumber(_) => None,
+        LexError::InvalidNumber(_) | LexError::InvalidDate(_) => None,
         LexError::UnexpectedCharacter(_, pos) => Some(*pos),
     }
 }
@@ -126,6 +193,16 @@ fn show_error_context(input: &str, position: usize) {
 
 fn list_vars(evaluator: &Evaluator) {
     let vars = evaluator.get_context().get_variables();
+    let functions = evaluator.get_context().get_functions();
+
+    if !functions.is_empty() {
+        println!("Defined functions:");
+        let mut functions: Vec<_> = functions.iter().collect();
+        functions.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, function) in functions {
+            println!("  {}({})", name, function.params.join(", "));
+        }
+    }
 
     if vars.is_empty() {
         println!("No variables defined.");
@@ -137,7 +214,7 @@ fn list_vars(evaluator: &Evaluator) {
     vars.sort_by(|a, b| a.0.cmp(b.0));
 
     for (name, value) in vars {
-        println!("  {} = {}", name, value);
+        println!("  {} = {}", name, evaluator.get_context().format_value(value));
     }
 }
 
@@ -160,21 +237,390 @@ fn show_mode(evaluator: &Evaluator) {
     println!("Current angle mode: {}", mode_str);
 }
 
+fn set_output_base(evaluator: &mut Evaluator, base: u32) {
+    match evaluator.get_context_mut().set_output_base(base) {
+        Ok(()) => println!("Output base set to: {}", base),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn handle_base(command: &str, evaluator: &mut Evaluator) {
+    let args: Vec<&str> = command.split_whitespace().skip(1).collect();
+    match args.as_slice() {
+        [] => println!("Output base: {}", evaluator.get_context().get_output_base()),
+        [base] => match base.parse() {
+            Ok(base) => set_output_base(evaluator, base),
+            Err(_) => eprintln!("Usage: base <2-36>"),
+        },
+        _ => eprintln!("Usage: base | base <2-36>"),
+    }
+}
+
+fn handle_rates(command: &str, evaluator: &mut Evaluator) {
+    let args: Vec<&str> = command.split_whitespace().skip(1).collect();
+    let context = evaluator.get_context_mut();
+
+    let result = match args.as_slice() {
+        [] => {
+            match context.get_rates() {
+                Some(table) => show_rates(table),
+                None => println!("No rate table loaded. Use 'rates load <file>'."),
+            }
+            return;
+        },
+        ["load", path] => context.load_rates(Path::new(path)),
+        ["reload"] => context.reload_rates(),
+        _ => {
+            eprintln!("Usage: rates | rates load <file.json|file.csv> | rates reload");
+            return;
+        },
+    };
+
+    match result {
+        Ok(table) => show_rates(table),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn handle_seed(command: &str, evaluator: &mut Evaluator) {
+    let args: Vec<&str> = command.split_whitespace().skip(1).collect();
+    match args.as_slice() {
+        [seed] => match seed.parse::<u64>() {
+            Ok(seed) => {
+                evaluator.get_context_mut().seed(seed);
+                println!("Random seed set to: {}", seed);
+            },
+            Err(_) => eprintln!("Usage: seed <non-negative integer>"),
+        },
+        _ => eprintln!("Usage: seed <non-negative integer>"),
+    }
+}
+
+fn handle_word(command: &str, evaluator: &mut Evaluator) {
+    let args: Vec<&str> = command.split_whitespace().skip(1).collect();
+    let context = evaluator.get_context_mut();
+
+    let (bits, signed) = match args.as_slice() {
+        [] => {
+            println!("Word size: {}", context.get_word_size());
+            return;
+        },
+        [bits] => (bits.parse().ok(), Some(context.get_word_size().signed)),
+        [bits, "signed"] => (bits.parse().ok(), Some(true)),
+        [bits, "unsigned"] => (bits.parse().ok(), Some(false)),
+        _ => (None, None),
+    };
+
+    match (bits, signed) {
+        (Some(bits), Some(signed)) => match WordSize::new(bits, signed) {
+            Ok(word) => {
+                context.set_word_size(word);
+                println!("Word size set to: {}", word);
+            },
+            Err(e) => eprintln!("{}", e),
+        },
+        _ => eprintln!("Usage: word | word <8|16|32|64> [signed|unsigned]"),
+    }
+}
+
+const PLOT_USAGE: &str = "Usage: matheval-cli plot <expr>... [--from A] [--to B] [--var x] [-o out.svg]
+                        [--title TEXT] [--width W] [--height H] [--y-from C] [--y-to D]
+                        [--angle deg|rad|grad (default rad)]
+An <expr> may also be a curve, plot_param(x, y, t, a, b) or plot_polar(r, θ, a, b),
+or a table from table(...) or odesolve(...).";
+
+/// `matheval-cli plot "sin(x)" --from -10 --to 10 -o out.svg`: samples each
+/// expression and writes the chart as SVG, to standard output without `-o`.
+/// Angles are in radians unless `--angle` says otherwise, since the default
+/// range of -10 to 10 would show little of a trigonometric function in
+/// degrees.
+fn plot_command(args: &[String]) -> Result<(), String> {
+    let mut evaluator = Evaluator::new();
+    evaluator.get_context_mut().set_angle_mode(AngleMode::Radians);
+    let mut expressions = Vec::new();
+    let (mut from, mut to, mut y_from, mut y_to) = (None, None, None, None);
+    let mut variable = "x".to_string();
+    let mut output = None;
+    let mut options = SvgOptions::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--from" => from = Some(eval_number(value()?, &mut evaluator)?),
+            "--to" => to = Some(eval_number(value()?, &mut evaluator)?),
+            "--y-from" => y_from = Some(eval_number(value()?, &mut evaluator)?),
+            "--y-to" => y_to = Some(eval_number(value()?, &mut evaluator)?),
+            "--var" => variable = value()?.clone(),
+            "-o" | "--output" => output = Some(value()?.clone()),
+            "--title" => options.title = Some(value()?.clone()),
+            "--width" => options.width = value()?.parse().map_err(|_| "--width needs a whole number")?,
+            "--height" => options.height = value()?.parse().map_err(|_| "--height needs a whole number")?,
+            "--angle" => {
+                let mode = match value()?.as_str() {
+                    "deg" => AngleMode::Degrees,
+                    "rad" => AngleMode::Radians,
+                    "grad" => AngleMode::Gradians,
+                    other => return Err(format!("unknown angle mode '{}'", other)),
+                };
+                evaluator.get_context_mut().set_angle_mode(mode);
+            },
+            option if option.starts_with("--") => return Err(format!("unknown option '{}'", option)),
+            expression => expressions.push(expression.to_string()),
+        }
+    }
+    if expressions.is_empty() {
+        return Err("nothing to plot".to_string());
+    }
+
+    let range = (from.is_some() || to.is_some()).then(|| (from.unwrap_or(-10.0), to.unwrap_or(10.0)));
+    let (series, fitted) = sample_series(expressions, &variable, range, &mut evaluator)?;
+    options.view = Some(View {
+        y: (y_from.unwrap_or(fitted.y.0), y_to.unwrap_or(fitted.y.1)),
+        ..fitted
+    });
+    let document = svg::render(&series, &options);
+    match output {
+        Some(path) => std::fs::write(&path, document).map_err(|e| format!("cannot write {}: {}", path, e)),
+        None => {
+            print!("{}", document);
+            Ok(())
+        },
+    }
+}
+
+/// Samples each expression, a function of `variable`, over `range` (-10 to
+/// 10 by default), labelled with its own text. A call that makes a table,
+/// `odesolve(...)`, `table(...)`, `plot_param(...)` or `plot_polar(...)`, is
+/// evaluated instead and its columns drawn against the first. Returns the
+/// series with a view that fits them, over the data's own x range when there
+/// is nothing but data and no range was given, or an error when a series has
+/// nothing to draw.
+fn sample_series(
+    expressions: Vec<String>,
+    variable: &str,
+    range: Option<(f64, f64)>,
+    evaluator: &mut Evaluator,
+) -> Result<(Vec<Series>, View), String> {
+    let (from, to) = range.unwrap_or((-10.0, 10.0));
+    let mut only_data = true;
+    let mut series = Vec::new();
+    for expression in expressions {
+        let ast = parse_line(&expression)?;
+        if let Expr::FunctionCall { func: Function::OdeSolve | Function::Table | Function::PlotParam | Function::PlotPolar, .. } = ast {
+            let columns = match evaluator.eval_value(&ast).map_err(|e| format!("{}: {}", expression, e))? {
+                Value::Table(table) => table.to_series(),
+                _ => return Err(format!("{}: expected a table", expression)),
+            };
+            let single = columns.len() == 1;
+            series.extend(columns.into_iter().map(|column| Series {
+                label: if single { expression.clone() } else { column.label },
+                curve: column.curve,
+            }));
+        } else {
+            only_data = false;
+            let curve = CompiledExpr::new(&ast, &[variable], evaluator)
+                .and_then(|f| plot::sample_function(&f, evaluator, from, to, &SampleOptions::default()))
+                .map_err(|e| format!("{}: {}", expression, e))?;
+            series.push(Series { label: expression, curve });
+        }
+    }
+    if let Some(empty) = series.iter().find(|s| s.curve.segments.is_empty()) {
+        return Err(format!("{}: nothing to draw, as it has no finite values there", empty.label));
+    }
+    let x = if range.is_none() && only_data { None } else { Some((from, to)) };
+    let view = fit_view(&series, x);
+    Ok((series, view))
+}
+
+/// The view over `x`, or the curves' own range without one, as high as the
+/// curves need.
+fn fit_view(series: &[Series], x: Option<(f64, f64)>) -> View {
+    let fitted = View::fit(series).unwrap_or(View { x: x.unwrap_or((-10.0, 10.0)), y: (-1.0, 1.0) });
+    View { x: x.unwrap_or(fitted.x), ..fitted }
+}
+
+/// `export values.csv` writes the last result, a table from `table(...)`, as
+/// CSV or, for `.md` files, Markdown.
+fn export_table(path: &str, evaluator: &Evaluator) {
+    if path.is_empty() {
+        eprintln!("Usage: export <file.csv|file.md>");
+        return;
+    }
+    let Some(Value::Table(table)) = evaluator.get_context().get("ans") else {
+        eprintln!("Error: nothing to export; the last result is not a table");
+        return;
+    };
+    let markdown = Path::new(path).extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"));
+    let contents = if markdown { table.to_markdown() } else { table.to_csv() };
+    match std::fs::write(path, contents) {
+        Ok(()) => println!("Wrote {} rows to {}", table.rows.len(), path),
+        Err(e) => eprintln!("Error: cannot write {}: {}", path, e),
+    }
+}
+
+/// `plot sin(x), cos(x) from 0 to 360` in the REPL: draws functions of `x`
+/// with Braille characters, sized to the terminal when there is one.
+fn handle_plot(command: &str, evaluator: &mut Evaluator, dimensions: Option<(usize, usize)>) {
+    let result = split_plot_command(command).and_then(|PlotCommand { expressions, range }| {
+        let range = match range {
+            Some((from, to)) => Some((eval_number(from, evaluator)?, eval_number(to, evaluator)?)),
+            None => None,
+        };
+        let (series, view) = sample_series(expressions, "x", range, evaluator)?;
+        let options = match dimensions {
+            Some((columns, rows)) => TerminalOptions { width: columns, height: rows.saturating_sub(2), color: true },
+            None => TerminalOptions { color: false, ..TerminalOptions::default() },
+        };
+        Ok(braille::render(&series, view, &options))
+    });
+    match result {
+        Ok(chart) => print!("{}", chart),
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
+
+/// The parts of a REPL `plot` command, the range still unevaluated.
+struct PlotCommand<'a> {
+    expressions: Vec<String>,
+    range: Option<(&'a str, &'a str)>,
+}
+
+/// Splits `f(x), g(x) from a to b` into the expressions and the range.
+/// Commas inside brackets, as in `max(x, 0)`, don't separate expressions.
+fn split_plot_command(command: &str) -> Result<PlotCommand<'_>, String> {
+    let (functions, range) = match find_word(command, "from").last() {
+        Some(&at) => {
+            let range = &command[at + 4..];
+            let to = *find_word(range, "to").first().ok_or("expected 'from <start> to <end>'")?;
+            (&command[..at], Some((range[..to].trim(), range[to + 2..].trim())))
+        },
+        None => (command, None),
+    };
+
+    let mut expressions = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in functions.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            ',' if depth == 0 => {
+                expressions.push(functions[start..i].trim().to_string());
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    expressions.push(functions[start..].trim().to_string());
+    if expressions.iter().any(String::is_empty) {
+        return Err("expected expressions to plot separated by commas".to_string());
+    }
+    Ok(PlotCommand { expressions, range })
+}
+
+/// Byte offsets where `word` stands on its own, outside any brackets.
+fn find_word(text: &str, word: &str) -> Vec<usize> {
+    let mut found = Vec::new();
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ if depth == 0 && text[i..].starts_with(word) => {
+                let before = text[..i].chars().next_back().is_none_or(char::is_whitespace);
+                let after = text[i + word.len()..].chars().next().is_none_or(char::is_whitespace);
+                if before && after {
+                    found.push(i);
+                }
+            },
+            _ => {},
+        }
+    }
+    found
+}
+
+fn parse_line(input: &str) -> Result<matheval::ast::Expr, String> {
+    let tokens = Lexer::new(input).tokenize().map_err(|e| format!("{}: {}", input, e))?;
+    Parser::new(tokens).parse().map_err(|e| format!("{}: {}", input, e))
+}
+
+/// Evaluates a command-line number, which may be an expression like `2*PI`.
+fn eval_number(input: &str, evaluator: &mut Evaluator) -> Result<f64, String> {
+    let ast = parse_line(input)?;
+    evaluator.eval(&ast).map_err(|e| format!("{}: {}", input, e))
+}
+
+fn show_rates(table: &RateTable) {
+    println!("Base currency: {}", table.base);
+    if let Some(ts) = &table.timestamp {
+        println!("Rates as of: {}", ts);
+    }
+    if let Some(path) = table.source() {
+        println!("Loaded from: {}", path.display());
+    }
+    println!("Currencies: {}", table.currencies().join(", "));
+}
+
 fn print_help() {
     println!("Available functions:");
-    println!("  Trigonometric: sin(x), cos(x), tan(x), asin(x), acos(x), atan(x)");
-    println!("  Logarithmic: ln(x), log(x)");
-    println!("  Power & roots: sqrt(x), exp(x), pow(x,y)");
+    println!("  Trigonometric: sin(x), cos(x), tan(x), asin(x), acos(x), atan(x), atan2(y,x)");
+    println!("                 sec(x), csc(x), cot(x), asec(x), acsc(x), acot(x), hypot(x,y)");
+    println!("  Hyperbolic: sinh(x), cosh(x), tanh(x), asinh(x), acosh(x), atanh(x)");
+    println!("  Logarithmic: ln(x), log(x), log(x,b), log2(x), log1p(x)");
+    println!("  Power & roots: sqrt(x), cbrt(x), root(x,n), exp(x), expm1(x), pow(x,y)");
     println!("  Rounding: floor(x), ceil(x), round(x)");
+    println!("  Special: erf(x), erfc(x), erfinv(x), zeta(s), lambertw(x[, -1]), ellipk(m), ellipe(m),");
+    println!("           ei(x), expint(x), besselj(ν,x), bessely(ν,x), besseli(ν,x), besselk(ν,x)");
+    println!("  Distributions: <name>pdf(x, ...), <name>cdf(x, ...), <name>inv(p, ...) for");
+    println!("    norm(μ=0, σ=1), t(ν), chi2(k), f(d1, d2), bino(n, p), poiss(λ), exp(mean),");
+    println!("    unif(a, b), gam(shape, scale), beta(a, b); e.g. normcdf(1.96), binopdf(3, 10, 0.5)");
+    println!("  Random: rand(), randint(a,b), randn([μ, σ]), choice([a, b, ...]), <name>rnd(...)");
     println!("  Other: abs(x), max(...), min(...)");
     println!("  Factorial: x!");
+    println!("  Calendar: today(), now(), date(y,m,d), year(d), month(d), day(d),");
+    println!("            weekday(d), isoweek(d), busdays(d1,d2), workday(d,n)");
     println!("\nConstants: PI, E");
-    println!("Operators: +, -, *, /, ^");
+    println!("Operators: +, -, *, /, ^, // (floor division), mod (never negative), rem (sign of the dividend)");
+    println!("Percent: 200 + 15% = 230, 200 - 15% = 170, 50 * 10% = 5");
+    println!("Comparisons: <, <=, ==, !=, >=, > (equal within a relative 1e-12), logic: and, or, not, true, false");
+    println!("Functions: f(x) = x^2 + 1, conditionals: if(x > 0, x, -x), {{x^2 if x < 0; x otherwise}}");
+    println!("Tables of values: table(x^2 + 1, x, 0, 10, 0.5) (expression, variable, from, to, step)");
+    println!("Graph analysis: analyze(x^3 - 3*x, x, -3, 3) (roots, extrema, inflection points, y-intercept),");
+    println!("                intersect(x^2, x + 2, x, -5, 5)");
+    println!("Optimization: minimize((x - 1)^2 + (y + 2)^2, [x, y], [0, 0]), maximize(x*exp(-x), x, 5),");
+    println!("              with bounds: minimize(x^2 + y^2, [x, y], [5, 5], [1, 2], [10, 10])");
+    println!("Differential equations: odesolve(y' = -0.5*y, y, t, 0, 10, 1) (equations, unknowns, variable, from, to,");
+    println!("                        initial values, step), odesolve([x' = v, v' = -x], [x, v], t, 0, 10, [1, 0], 0.1)");
+    println!("Taylor series: taylor(sin(x), x, 0, 7) (expression, variable, center, order), p(x) = taylor(exp(x), x, 0, 5)");
+    println!("Curves: plot_param(cos(t), sin(t), t, 0, 360) (x, y, variable, from, to), plot_polar(1 + cos(θ), θ, 0, 360)");
+    println!("        (radius, angle, from, to), as points to plot");
+    println!("Bitwise (integers, wrapped to the word size): a & b, a | b, a xor b, ~a, a << n, a >> n");
+    println!("Integer literals: 0xFF, 0b1010_0101, 0o17 (0b0.101 for fractions)");
+    println!("Bases: 255 in hex, 0b1010 to dec, 0.625 in bin, 35 in base 36");
+    println!("IEEE-754: float_bits(x), double_bits(x), from_bits(n[, 32]), ulp(x), nextafter(x, y)");
+    println!("\nUnits: 3 m/s, 9.81 m/s^2 * 2 kg, 100 km/h to m/s");
+    println!("  SI units with prefixes (km, mg, kWh), derived units (N, J, W, Pa, ...)");
+    println!("  and imperial units (in, ft, mi, lb, gal, psi, ...)");
+    println!("  Temperatures: degC, degF, K (20 degC to degF), differences: ΔdegC, ΔdegF");
+    println!("\nCurrencies: 100 USD to EUR (needs a rate table, see 'rates')");
+    println!("Dates (UTC): 2026-10-17, 2026-10-17T14:30, 14:30, durations like 3d 4h or 90 min");
     println!("\nCommands:");
     println!("  deg   - Set angle mode to degrees (default)");
     println!("  rad   - Set angle mode to radians");
     println!("  grad  - Set angle mode to gradians");
     println!("  mode  - Show current angle mode");
+    println!("  rates - Show the currency rate table ('rates load <file>', 'rates reload')");
+    println!("  word  - Show or set the integer word size ('word 16 unsigned')");
+    println!("  seed  - Seed the random number generator for repeatable results ('seed 42')");
+    println!("  plot  - Draw functions of x in the terminal ('plot sin(x), cos(x) from 0 to 360'),");
+    println!("          or the columns of odesolve(...) and table(...) against their first,");
+    println!("          and curves: 'plot plot_param(cos(t), sin(2*t), t, 0, 360)', 'plot plot_polar(cos(2*θ), θ, 0, 360)'");
+    println!("  export - Write the last table to a CSV or Markdown file ('export values.csv', 'export values.md')");
+    println!("  hex, bin, oct, dec - Show results in base 16, 2, 8 or 10");
+    println!("  base  - Show or set the output base ('base 36')");
+    println!("  ieee  - Show the sign, exponent and mantissa of a number ('ieee 0.1')");
     println!("  vars  - List all defined variables");
     println!("  help  - Show this help");
     println!("  exit  - Exit the REPL");
@@ -0,0 +1,648 @@
+//! Numerical minimization and maximization of a function of one or more
+//! variables, optionally kept within bounds.
+//!
+//! Functions of several variables are minimized with BFGS, a quasi-Newton
+//! method that builds up an estimate of the inverse Hessian from
+//! central-difference gradients. Where the gradient is undefined it gives
+//! way to the Nelder–Mead simplex method, which only compares values and
+//! also handles bounds, by clamping its simplex to them. Functions of one
+//! variable use Brent's method, which mixes parabolic interpolation with
+//! golden-section steps, on the bounds or on a bracket stepped out from the
+//! starting point.
+//!
+//! Points where the function is undefined count as infinitely high, so the
+//! methods steer around them. The argmin is rounded to the decimals the
+//! methods can resolve as long as that doesn't make the value any worse.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    analysis::{self, PointKind},
+    compile::CompiledExpr,
+    error::EvalError,
+    evaluator::Evaluator,
+    table::Table,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Method {
+    /// Brent's method for one variable, Nelder–Mead with bounds and BFGS
+    /// otherwise.
+    Auto,
+    NelderMead,
+    Bfgs,
+    Brent,
+}
+
+impl Method {
+    pub fn name(self) -> &'static str {
+        match self {
+            Method::Auto => "auto",
+            Method::NelderMead => "Nelder-Mead",
+            Method::Bfgs => "BFGS",
+            Method::Brent => "Brent's method",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizeOptions {
+    pub method: Method,
+    /// Iterations allowed before giving up with an error.
+    pub max_iterations: usize,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        OptimizeOptions { method: Method::Auto, max_iterations: 2000 }
+    }
+}
+
+/// Where a function takes its least or greatest value, and that value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Optimum {
+    /// `Minimum` or `Maximum`.
+    pub kind: PointKind,
+    pub point: Vec<f64>,
+    pub value: f64,
+    /// The method that found the point, never `Auto`.
+    pub method: Method,
+    pub iterations: usize,
+}
+
+/// A local minimum of `f` near `start`, within `bounds` on each variable if
+/// they are given.
+pub fn minimize(
+    f: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    start: &[f64],
+    bounds: Option<&[(f64, f64)]>,
+    options: &OptimizeOptions,
+) -> Result<Optimum, EvalError> {
+    optimize(f, evaluator, start, bounds, options, PointKind::Minimum)
+}
+
+/// A local maximum of `f` near `start`, found as the minimum of `-f`.
+pub fn maximize(
+    f: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    start: &[f64],
+    bounds: Option<&[(f64, f64)]>,
+    options: &OptimizeOptions,
+) -> Result<Optimum, EvalError> {
+    optimize(f, evaluator, start, bounds, options, PointKind::Maximum)
+}
+
+/// A one-row table of the argmin, under the variables' names, and the
+/// value, under `minimum` or `maximum`.
+pub fn to_table(variables: &[String], optimum: &Optimum) -> Table {
+    let mut columns = variables.to_vec();
+    columns.push(optimum.kind.label().to_string());
+    let mut row = optimum.point.clone();
+    row.push(optimum.value);
+    Table { columns, labels: Vec::new(), rows: vec![row] }
+}
+
+fn optimize(
+    f: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    start: &[f64],
+    bounds: Option<&[(f64, f64)]>,
+    options: &OptimizeOptions,
+    kind: PointKind,
+) -> Result<Optimum, EvalError> {
+    let start = check(f, start, bounds)?;
+    let n = start.len();
+    let method = match options.method {
+        Method::Auto if n == 1 => Method::Brent,
+        Method::Auto if bounds.is_some() => Method::NelderMead,
+        Method::Auto => Method::Bfgs,
+        Method::Brent if n > 1 => {
+            return Err(EvalError::InvalidArguments("Brent's method minimizes functions of one variable".to_string()));
+        },
+        Method::Bfgs if bounds.is_some() => {
+            return Err(EvalError::InvalidArguments("BFGS does not take bounds; use Nelder-Mead".to_string()));
+        },
+        method => method,
+    };
+
+    let sign = if kind == PointKind::Maximum { -1.0 } else { 1.0 };
+    let mut cost = |p: &[f64]| {
+        let y = sign * f.eval(evaluator, p);
+        if y.is_nan() { f64::INFINITY } else { y }
+    };
+    if cost(&start) == f64::INFINITY {
+        return Err(EvalError::InvalidArguments(format!(
+            "the function is undefined at the starting point {}", format_point(&start)
+        )));
+    }
+
+    let limit = options.max_iterations;
+    let found = match method {
+        Method::Brent => match bounds {
+            Some(&[(low, high)]) => brent_within(&mut cost, low, high, start[0], limit),
+            _ => bracket(&mut cost, start[0]).and_then(|(a, b, c)| brent(&mut cost, a, b, c, limit)),
+        }
+        .map(|found| (found, Method::Brent)),
+        Method::Bfgs => match bfgs(&mut cost, &start, limit) {
+            Err(Stop::Undefined) if options.method == Method::Auto => {
+                nelder_mead(&mut cost, &start, bounds, limit).map(|found| (found, Method::NelderMead))
+            },
+            result => result.map(|found| (found, Method::Bfgs)),
+        },
+        _ => nelder_mead(&mut cost, &start, bounds, limit).map(|found| (found, Method::NelderMead)),
+    };
+    let found = found.map_err(|stop| match stop {
+        Stop::Limit(method) => EvalError::MathError(format!(
+            "{} did not converge within {} iterations", method.name(), limit
+        )),
+        Stop::Unbounded => EvalError::MathError(format!(
+            "no {} found: the function {} without bound",
+            kind.label(),
+            if kind == PointKind::Maximum { "increases" } else { "decreases" }
+        )),
+        Stop::Undefined => EvalError::MathError(
+            "BFGS stopped where the gradient is undefined; try Nelder-Mead".to_string()
+        ),
+    })?;
+    let (Found { point, value, iterations }, method) = found;
+    let (point, value) = tidy(&mut cost, point, value);
+    Ok(Optimum { kind, point, value: sign * value, method, iterations })
+}
+
+/// The starting point clamped to the bounds, after checking that each
+/// variable has a finite start and sensible bounds.
+fn check(f: &CompiledExpr, start: &[f64], bounds: Option<&[(f64, f64)]>) -> Result<Vec<f64>, EvalError> {
+    let n = f.variables().len();
+    if n == 0 || start.len() != n {
+        return Err(EvalError::InvalidArguments(format!(
+            "expected a starting value for each of the {} variable(s), got {}", n, start.len()
+        )));
+    }
+    if start.iter().any(|x| !x.is_finite()) {
+        return Err(EvalError::InvalidArguments(format!("the starting point {} is not finite", format_point(start))));
+    }
+    let Some(bounds) = bounds else {
+        return Ok(start.to_vec());
+    };
+    if bounds.len() != n {
+        return Err(EvalError::InvalidArguments(format!(
+            "expected bounds for each of the {} variable(s), got {}", n, bounds.len()
+        )));
+    }
+    if let Some((low, high)) = bounds.iter().find(|(low, high)| low > high || low.is_nan() || high.is_nan()) {
+        return Err(EvalError::InvalidArguments(format!("the lower bound {} is above the upper bound {}", low, high)));
+    }
+    Ok(clamp(start.to_vec(), Some(bounds)))
+}
+
+fn clamp(mut point: Vec<f64>, bounds: Option<&[(f64, f64)]>) -> Vec<f64> {
+    if let Some(bounds) = bounds {
+        for (x, &(low, high)) in point.iter_mut().zip(bounds) {
+            *x = x.clamp(low, high);
+        }
+    }
+    point
+}
+
+fn format_point(point: &[f64]) -> String {
+    match point {
+        [x] => x.to_string(),
+        _ => format!("[{}]", point.iter().map(f64::to_string).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// Rounds the coordinates to seven significant decimals, or failing that
+/// nine, if the value there is as good but for rounding error. Comparing
+/// values can't place a minimum closer than about the square root of the
+/// machine epsilon, so Brent's method and Nelder–Mead leave digits that
+/// mean nothing.
+fn tidy(cost: &mut dyn FnMut(&[f64]) -> f64, point: Vec<f64>, value: f64) -> (Vec<f64>, f64) {
+    let noise = 4.0 * f64::EPSILON * value.abs();
+    for precision in [1e-7, 1e-9] {
+        let rounded: Vec<f64> = point.iter().map(|&x| analysis::round_to(x, precision * x.abs().max(1.0))).collect();
+        let rounded_value = cost(&rounded);
+        if rounded_value <= value + noise {
+            return (rounded, rounded_value);
+        }
+    }
+    (point, value)
+}
+
+/// A local minimum and how many iterations it took.
+struct Found {
+    point: Vec<f64>,
+    value: f64,
+    iterations: usize,
+}
+
+/// Why a method stopped without a minimum.
+enum Stop {
+    /// The method used up its iterations.
+    Limit(Method),
+    /// The values fell to minus infinity, or the points ran off to it.
+    Unbounded,
+    /// The gradient couldn't be estimated.
+    Undefined,
+}
+
+/// The golden ratio, by which steps grow while bracketing.
+fn golden_ratio() -> f64 {
+    (1.0 + 5f64.sqrt()) / 2.0
+}
+
+/// Steps downhill from `x0` in growing strides until the function rises
+/// again, giving `a`, `b`, `c` in order with `b` lowest.
+fn bracket(cost: &mut dyn FnMut(&[f64]) -> f64, x0: f64) -> Result<(f64, f64, f64), Stop> {
+    let step = 0.1 * x0.abs().max(1.0);
+    let (mut a, mut b) = (x0, x0 + step);
+    let (mut fa, mut fb) = (cost(&[a]), cost(&[b]));
+    if fb > fa {
+        std::mem::swap(&mut a, &mut b);
+        std::mem::swap(&mut fa, &mut fb);
+    }
+    let mut c = b + golden_ratio() * (b - a);
+    let mut fc = cost(&[c]);
+    while fc < fb {
+        if fc == f64::NEG_INFINITY || c.abs() > 1e100 {
+            return Err(Stop::Unbounded);
+        }
+        (a, b) = (b, c);
+        fb = fc;
+        c = b + golden_ratio() * (b - a);
+        fc = cost(&[c]);
+    }
+    Ok((a, b, c))
+}
+
+/// Brent's method between `low` and `high`, trying the ends too since it
+/// only ever approaches them.
+fn brent_within(cost: &mut dyn FnMut(&[f64]) -> f64, low: f64, high: f64, x0: f64, limit: usize) -> Result<Found, Stop> {
+    if low == high {
+        return Ok(Found { point: vec![low], value: cost(&[low]), iterations: 0 });
+    }
+    let inside = if x0 > low && x0 < high { x0 } else { low + (high - low) / (golden_ratio() + 1.0) };
+    let mut found = brent(cost, low, inside, high, limit)?;
+    for end in [low, high] {
+        let value = cost(&[end]);
+        if value < found.value {
+            found = Found { point: vec![end], value, ..found };
+        }
+    }
+    Ok(found)
+}
+
+/// Brent's method on `[a, c]` (either way round), starting from `b`
+/// between them.
+fn brent(cost: &mut dyn FnMut(&[f64]) -> f64, a: f64, b: f64, c: f64, limit: usize) -> Result<Found, Stop> {
+    let shrink = 1.0 - 1.0 / golden_ratio();
+    let tolerance = f64::EPSILON.sqrt();
+    let (mut a, mut c) = (a.min(c), a.max(c));
+    let (mut x, mut w, mut v) = (b, b, b);
+    let fb = cost(&[b]);
+    let (mut fx, mut fw, mut fv) = (fb, fb, fb);
+    // The step before last, and the last.
+    let (mut e, mut d) = (0.0_f64, 0.0_f64);
+
+    for iteration in 0..limit {
+        if fx == f64::NEG_INFINITY {
+            return Err(Stop::Unbounded);
+        }
+        let middle = 0.5 * (a + c);
+        let tol1 = tolerance * x.abs() + 1e-10;
+        let tol2 = 2.0 * tol1;
+        if (x - middle).abs() <= tol2 - 0.5 * (c - a) {
+            return Ok(Found { point: vec![x], value: fx, iterations: iteration });
+        }
+
+        // A parabola through x, w and v, if its vertex is inside the
+        // bracket and the step is less than half the one before last.
+        let mut parabolic = false;
+        if e.abs() > tol1 {
+            let r = (x - w) * (fx - fv);
+            let mut q = (x - v) * (fx - fw);
+            let mut p = (x - v) * q - (x - w) * r;
+            q = 2.0 * (q - r);
+            if q > 0.0 {
+                p = -p;
+            }
+            q = q.abs();
+            let before_last = e;
+            e = d;
+            if p.abs() < (0.5 * q * before_last).abs() && p > q * (a - x) && p < q * (c - x) {
+                d = p / q;
+                let u = x + d;
+                if u - a < tol2 || c - u < tol2 {
+                    d = tol1.copysign(middle - x);
+                }
+                parabolic = true;
+            }
+        }
+        if !parabolic {
+            e = if x >= middle { a - x } else { c - x };
+            d = shrink * e;
+        }
+
+        let u = if d.abs() >= tol1 { x + d } else { x + tol1.copysign(d) };
+        let fu = cost(&[u]);
+        if fu <= fx {
+            if u >= x { a = x } else { c = x }
+            (v, fv) = (w, fw);
+            (w, fw) = (x, fx);
+            (x, fx) = (u, fu);
+        } else {
+            if u < x { a = u } else { c = u }
+            if fu <= fw || w == x {
+                (v, fv) = (w, fw);
+                (w, fw) = (u, fu);
+            } else if fu <= fv || v == x || v == w {
+                (v, fv) = (u, fu);
+            }
+        }
+    }
+    Err(Stop::Limit(Method::Brent))
+}
+
+/// The Nelder–Mead simplex method, with the coefficients adapted to the
+/// dimension (Gao and Han, 2012). On converging it restarts once from a
+/// fresh simplex around the best point, since a simplex can collapse
+/// before reaching a minimum.
+fn nelder_mead(cost: &mut dyn FnMut(&[f64]) -> f64, start: &[f64], bounds: Option<&[(f64, f64)]>, limit: usize) -> Result<Found, Stop> {
+    let n = start.len() as f64;
+    let (reflect, expand, contract, shrink) = (1.0, 1.0 + 2.0 / n, 0.75 - 0.5 / n, 1.0 - 1.0 / n);
+    let mut best = start.to_vec();
+    let mut restarted = false;
+    let mut iteration = 0;
+
+    loop {
+        let mut simplex = initial_simplex(cost, &best, bounds);
+        loop {
+            simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let (lowest, highest) = (simplex[0].1, simplex[simplex.len() - 1].1);
+            if lowest == f64::NEG_INFINITY || simplex[0].0.iter().any(|x| !x.is_finite()) {
+                return Err(Stop::Unbounded);
+            }
+            let size = simplex[1..].iter()
+                .flat_map(|(p, _)| p.iter().zip(&simplex[0].0).map(|(a, b)| (a - b).abs()))
+                .fold(0.0, f64::max);
+            let scale = simplex[0].0.iter().fold(1.0_f64, |m, x| m.max(x.abs()));
+            if highest - lowest <= 1e-12 * lowest.abs().max(1.0) && size <= 1e-10 * scale {
+                break;
+            }
+            if iteration == limit {
+                return Err(Stop::Limit(Method::NelderMead));
+            }
+            iteration += 1;
+
+            let worst = simplex.len() - 1;
+            let centroid: Vec<f64> = (0..start.len())
+                .map(|i| simplex[..worst].iter().map(|(p, _)| p[i]).sum::<f64>() / n)
+                .collect();
+            let mut toward = |t: f64| {
+                let p: Vec<f64> = centroid.iter().zip(&simplex[worst].0).map(|(c, w)| c + t * (c - w)).collect();
+                let p = clamp(p, bounds);
+                let value = cost(&p);
+                (p, value)
+            };
+            let reflected = toward(reflect);
+            if reflected.1 < simplex[0].1 {
+                let expanded = toward(reflect * expand);
+                simplex[worst] = if expanded.1 < reflected.1 { expanded } else { reflected };
+            } else if reflected.1 < simplex[worst - 1].1 {
+                simplex[worst] = reflected;
+            } else {
+                let outside = reflected.1 < simplex[worst].1;
+                let contracted = toward(if outside { reflect * contract } else { -contract });
+                if contracted.1 < if outside { reflected.1 } else { simplex[worst].1 } {
+                    simplex[worst] = contracted;
+                } else {
+                    let first = simplex[0].0.clone();
+                    for (p, value) in &mut simplex[1..] {
+                        for (x, b) in p.iter_mut().zip(&first) {
+                            *x = b + shrink * (*x - b);
+                        }
+                        *value = cost(p);
+                    }
+                }
+            }
+        }
+
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (point, value) = simplex.swap_remove(0);
+        if restarted {
+            return Ok(Found { point, value, iterations: iteration });
+        }
+        best = point;
+        restarted = true;
+    }
+}
+
+/// `start` and a point a short step from it along each axis: 5% of the
+/// coordinate, or 0.00025 from zero. Steps that a bound cuts short go the
+/// other way.
+fn initial_simplex(cost: &mut dyn FnMut(&[f64]) -> f64, start: &[f64], bounds: Option<&[(f64, f64)]>) -> Vec<(Vec<f64>, f64)> {
+    let mut simplex = vec![(start.to_vec(), cost(start))];
+    for i in 0..start.len() {
+        let step = if start[i] == 0.0 { 0.00025 } else { 0.05 * start[i] };
+        let mut p = start.to_vec();
+        p[i] += step;
+        let mut p = clamp(p, bounds);
+        if p[i] == start[i] {
+            p[i] -= step;
+            p = clamp(p, bounds);
+        }
+        let value = cost(&p);
+        simplex.push((p, value));
+    }
+    simplex
+}
+
+/// Fourth-order central differences, with steps of about the fifth root of
+/// the machine epsilon, which balances truncation against rounding error.
+/// A plain central difference would be off by enough to leave BFGS short of
+/// the minimum in a curved valley.
+fn gradient(cost: &mut dyn FnMut(&[f64]) -> f64, point: &[f64]) -> Option<Vec<f64>> {
+    let mut p = point.to_vec();
+    (0..point.len())
+        .map(|i| {
+            // A step that is exact in floating point.
+            let h = (point[i] + f64::EPSILON.powf(0.2) * point[i].abs().max(1.0)) - point[i];
+            let mut at = |offset: f64| {
+                p[i] = point[i] + offset * h;
+                cost(&p)
+            };
+            let slope = (at(-2.0) - 8.0 * at(-1.0) + 8.0 * at(1.0) - at(2.0)) / (12.0 * h);
+            p[i] = point[i];
+            slope.is_finite().then_some(slope)
+        })
+        .collect()
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+/// BFGS with a backtracking line search. When no step along the search
+/// direction lowers the value, the direction is reset to steepest descent;
+/// when that fails too the point is a minimum as far as the gradient can
+/// tell.
+fn bfgs(cost: &mut dyn FnMut(&[f64]) -> f64, start: &[f64], limit: usize) -> Result<Found, Stop> {
+    let n = start.len();
+    let identity = |n: usize| -> Vec<Vec<f64>> {
+        (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect()
+    };
+    let mut x = start.to_vec();
+    let mut fx = cost(&x);
+    let mut g = gradient(cost, &x).ok_or(Stop::Undefined)?;
+    // The estimate of the inverse Hessian, and whether it is still the
+    // identity it starts as.
+    let mut h = identity(n);
+    let mut fresh = true;
+
+    for iteration in 0..limit {
+        if g.iter().all(|&gi| gi.abs() <= 1e-10 * fx.abs().max(1.0)) {
+            return Ok(Found { point: x, value: fx, iterations: iteration });
+        }
+        let mut direction: Vec<f64> = h.iter().map(|row| -dot(row, &g)).collect();
+        let mut slope = dot(&g, &direction);
+        if slope >= 0.0 {
+            (h, fresh) = (identity(n), true);
+            direction = g.iter().map(|gi| -gi).collect();
+            slope = -dot(&g, &g);
+        }
+
+        let mut step = 1.0;
+        let mut accepted = None;
+        for _ in 0..60 {
+            let trial: Vec<f64> = x.iter().zip(&direction).map(|(x, d)| x + step * d).collect();
+            let value = cost(&trial);
+            if value < fx && value <= fx + 1e-4 * step * slope {
+                accepted = Some((trial, value));
+                break;
+            }
+            step *= 0.5;
+        }
+        let Some((next, f_next)) = accepted else {
+            if fresh {
+                return Ok(Found { point: x, value: fx, iterations: iteration });
+            }
+            (h, fresh) = (identity(n), true);
+            continue;
+        };
+        if f_next == f64::NEG_INFINITY || next.iter().any(|x| !x.is_finite()) {
+            return Err(Stop::Unbounded);
+        }
+        let g_next = gradient(cost, &next).ok_or(Stop::Undefined)?;
+
+        let s: Vec<f64> = next.iter().zip(&x).map(|(a, b)| a - b).collect();
+        let y: Vec<f64> = g_next.iter().zip(&g).map(|(a, b)| a - b).collect();
+        let small = s.iter().zip(&next).all(|(s, x)| s.abs() <= 1e-12 * x.abs().max(1.0));
+        (x, fx, g) = (next, f_next, g_next);
+        if small {
+            return Ok(Found { point: x, value: fx, iterations: iteration + 1 });
+        }
+
+        // The BFGS update, skipped where the curvature along the step isn't
+        // positive since it would spoil the estimate.
+        let sy = dot(&s, &y);
+        if sy <= f64::EPSILON * dot(&s, &s).sqrt() * dot(&y, &y).sqrt() {
+            continue;
+        }
+        if fresh {
+            let scale = sy / dot(&y, &y);
+            h.iter_mut().flatten().for_each(|value| *value *= scale);
+            fresh = false;
+        }
+        let rho = 1.0 / sy;
+        let hy: Vec<f64> = h.iter().map(|row| dot(row, &y)).collect();
+        let yhy = dot(&y, &hy);
+        for i in 0..n {
+            for j in 0..n {
+                h[i][j] += rho * ((1.0 + rho * yhy) * s[i] * s[j] - hy[i] * s[j] - s[i] * hy[j]);
+            }
+        }
+    }
+    Err(Stop::Limit(Method::Bfgs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::AngleMode, lexer::Lexer, parser::Parser};
+
+    fn run(input: &str, variables: &[&str], start: &[f64], bounds: Option<&[(f64, f64)]>, method: Method, maximum: bool) -> Result<Optimum, EvalError> {
+        let mut evaluator = Evaluator::new();
+        evaluator.get_context_mut().set_angle_mode(AngleMode::Radians);
+        let expr = Parser::new(Lexer::new(input).tokenize().unwrap()).parse().unwrap();
+        let f = CompiledExpr::new(&expr, variables, &mut evaluator)?;
+        let options = OptimizeOptions { method, ..OptimizeOptions::default() };
+        if maximum {
+            maximize(&f, &mut evaluator, start, bounds, &options)
+        } else {
+            minimize(&f, &mut evaluator, start, bounds, &options)
+        }
+    }
+
+    fn assert_near(found: &[f64], expected: &[f64], tolerance: f64) {
+        assert!(found.iter().zip(expected).all(|(a, b)| (a - b).abs() <= tolerance), "{:?} is not {:?}", found, expected);
+    }
+
+    #[test]
+    fn test_methods() {
+        // Rosenbrock's valley, from the usual start.
+        let rosenbrock = "(1 - x)^2 + 100*(y - x^2)^2";
+        for method in [Method::Auto, Method::Bfgs, Method::NelderMead] {
+            let optimum = run(rosenbrock, &["x", "y"], &[-1.2, 1.0], None, method, false).unwrap();
+            assert_near(&optimum.point, &[1.0, 1.0], 1e-6);
+            assert!(optimum.value < 1e-10, "{:?}", optimum);
+        }
+        assert_eq!(run(rosenbrock, &["x", "y"], &[-1.2, 1.0], None, Method::Auto, false).unwrap().method, Method::Bfgs);
+
+        // The exact argmin of a quadratic comes out round.
+        let quadratic = run("(x - 2)^2 + (y + 3)^2 + 5", &["x", "y"], &[0.0, 0.0], None, Method::Auto, false).unwrap();
+        assert_eq!((quadratic.point, quadratic.value), (vec![2.0, -3.0], 5.0));
+
+        // A kink leaves BFGS's gradient no use at the bottom, but it still
+        // gets close.
+        let kink = run("abs(x - 1) + abs(y - 2)", &["x", "y"], &[0.0, 0.0], None, Method::Auto, false).unwrap();
+        assert_near(&kink.point, &[1.0, 2.0], 1e-6);
+    }
+
+    #[test]
+    fn test_one_variable_and_bounds() {
+        let cosine = run("cos(x)", &["x"], &[1.0], None, Method::Auto, false).unwrap();
+        assert_eq!(cosine.method, Method::Brent);
+        assert_near(&cosine.point, &[std::f64::consts::PI], 1e-7);
+        assert_eq!(cosine.value, -1.0);
+
+        let peak = run("x * exp(-x)", &["x"], &[5.0], None, Method::Auto, true).unwrap();
+        assert_eq!(peak.kind, PointKind::Maximum);
+        assert_near(&peak.point, &[1.0], 1e-7);
+        assert_near(&[peak.value], &[(-1f64).exp()], 1e-15);
+
+        // A minimum on a bound, in one variable and in two.
+        let edge = run("x^2", &["x"], &[3.0], Some(&[(1.0, 5.0)]), Method::Auto, false).unwrap();
+        assert_eq!((edge.point, edge.value), (vec![1.0], 1.0));
+        let corner = run("x + y", &["x", "y"], &[0.5, 0.5], Some(&[(0.0, 1.0), (-1.0, 1.0)]), Method::Auto, false).unwrap();
+        assert_eq!(corner.method, Method::NelderMead);
+        assert_near(&corner.point, &[0.0, -1.0], 1e-9);
+    }
+
+    #[test]
+    fn test_errors() {
+        let message = |result: Result<Optimum, EvalError>| result.unwrap_err().to_string();
+        assert!(message(run("x^3", &["x"], &[0.0], None, Method::Auto, false)).contains("decreases without bound"));
+        assert!(message(run("1/x", &["x"], &[0.0], None, Method::Auto, false)).contains("undefined at the starting point"));
+        assert!(message(run("x^2 + y^2", &["x", "y"], &[1.0], None, Method::Auto, false)).contains("for each of the 2 variable(s)"));
+        assert!(message(run("x^2", &["x"], &[1.0], Some(&[(2.0, 1.0)]), Method::Auto, false)).contains("above the upper bound"));
+        assert!(message(run("x^2 + y^2", &["x", "y"], &[1.0, 1.0], Some(&[(0.0, 1.0), (0.0, 1.0)]), Method::Bfgs, false)).contains("BFGS does not take bounds"));
+
+        let mut evaluator = Evaluator::new();
+        let expr = Parser::new(Lexer::new("(1 - x)^2 + 100*(y - x^2)^2").tokenize().unwrap()).parse().unwrap();
+        let f = CompiledExpr::new(&expr, &["x", "y"], &mut evaluator).unwrap();
+        let options = OptimizeOptions { method: Method::NelderMead, max_iterations: 10 };
+        let error = minimize(&f, &mut evaluator, &[-1.2, 1.0], None, &options).unwrap_err();
+        assert_eq!(error.to_string(), EvalError::MathError("Nelder-Mead did not converge within 10 iterations".to_string()).to_string());
+    }
+}
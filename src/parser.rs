@@ -1,20 +1,48 @@
-/// Parser module for the application.
-/// Uses a grammar to parse input data.
-/// 
-/// The grammar rules are defined as follows:
-/// assignment    → IDENTIFIER EQUALS expression | expression
-/// expression    → term ((PLUS | MINUS) term)*
-/// term          → factor ((STAR | SLASH) factor)*
-/// factor        → power
-/// power         → unary (CARET unary)*
-/// unary         → (PLUS | MINUS)? postfix
-/// postfix       → primary EXCLAMATION?
-/// primary       → NUMBER | IDENTIFIER | function_call | LPAREN expression RPAREN
-/// function_call → IDENTIFIER LPAREN arguments RPAREN
-/// arguments     → expression (COMMA expression)*
-
-
-use crate::{ast::{BinOp, Expr, Function, UnOp}, error::ParseError, lexer::{Token, TokenWithPos}};
+//! Parser module for the application.
+//! Uses a grammar to parse input data.
+//! 
+//! The grammar rules are defined as follows:
+//! equation      → conversion (EQUALS conversion)?
+//! assignment    → IDENTIFIER EQUALS assignment | function_def | conversion
+//! function_def  → IDENTIFIER LPAREN (IDENTIFIER (COMMA IDENTIFIER)*)? RPAREN EQUALS assignment
+//! conversion    → expression (TO (unit | CURRENCY | base) | IN base)*
+//! expression    → logical_or
+//! logical_or    → logical_and (OR logical_and)*
+//! logical_and   → logical_not (AND logical_not)*
+//! logical_not   → NOT logical_not | comparison
+//! comparison    → bitwise_or ((LESS | LESS_EQUAL | EQUAL_EQUAL | NOT_EQUAL | GREATER_EQUAL | GREATER) bitwise_or)?
+//! bitwise_or    → bitwise_xor (PIPE bitwise_xor)*
+//! bitwise_xor   → bitwise_and (XOR bitwise_and)*
+//! bitwise_and   → shift (AMPERSAND shift)*
+//! shift         → sum ((SHIFT_LEFT | SHIFT_RIGHT) sum)*
+//! sum           → term ((PLUS | MINUS) term)*
+//! term          → factor ((STAR | SLASH | DOUBLE_SLASH | MOD | REM) factor)*
+//! factor        → power
+//! power         → unary (CARET unary)*
+//! unary         → (PLUS | MINUS | TILDE)? postfix
+//! postfix       → primary (EXCLAMATION | PERCENT)?
+//! primary       → quantity | NUMBER CURRENCY? | INTEGER | TRUE | FALSE | DATETIME | IDENTIFIER | function_call | piecewise | list | LPAREN conversion RPAREN
+//! function_call → IDENTIFIER LPAREN arguments RPAREN
+//! arguments     → argument (COMMA argument)*
+//! argument      → expression (EQUALS expression)?
+//! list          → LBRACKET (argument (COMMA argument)*)? RBRACKET
+//! piecewise     → LBRACE piece (SEMICOLON piece)* RBRACE
+//! piece         → expression ("if" expression | "otherwise")
+//! quantity      → NUMBER unit (NUMBER unit)*
+//! unit          → unit_power ((STAR | SLASH) unit_power)*
+//! unit_power    → (UNIT | LPAREN unit RPAREN) (CARET MINUS? NUMBER)?
+//! base          → HEX | BIN | OCT | DEC | BASE NUMBER
+//!
+//! `parse` starts from `assignment`; `parse_equation` starts from `equation`,
+//! for input such as implicit curves where `=` states an equation to solve.
+//! Arguments and list items may be equations too, such as the `y' = -y` of
+//! `odesolve(y' = -y, y, t, 0, 5, 1)`.
+//! `in` is also the inch symbol, so it only introduces a conversion when a base follows.
+//! Comparisons do not chain: `a < b < c` is an error, write `a < b and b < c`.
+//! A bare percentage on the right of PLUS or MINUS is a percentage of the left
+//! side (`200 + 15%` is 230); anywhere else `p%` is `p / 100` (`50 * 10%` is 5).
+
+use crate::{ast::{BinOp, ConversionTarget, Expr, Function, UnOp}, currency, error::ParseError, lexer::{Token, TokenWithPos}, radix, units::Unit};
 
 pub struct Parser {
     tokens: Vec<TokenWithPos>,
@@ -28,34 +56,302 @@ impl Parser {
 
     /// Main entry point for parsing.
     pub fn parse(&mut self) -> Result<Expr, ParseError> {
-        self.parse_assignment()
+        let expr = self.parse_assignment()?;
+        self.expect_end()?;
+        Ok(expr)
+    }
+
+    fn expect_end(&self) -> Result<(), ParseError> {
+        if !matches!(self.current_token(), Token::EOF) {
+            return Err(ParseError::UnexpectedToken {
+                expected: "end of input".to_string(),
+                found: format!("{:?}", self.current_token()),
+                position: self.current_token_pos(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Parses an equation such as `x^2 + y^2 = 25`. Input without `=` is
+    /// returned as is and stands for `expr = 0`.
+    pub fn parse_equation(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_conversion()?;
+        if matches!(self.current_token(), Token::Equals) {
+            self.advance();
+            expr = Expr::Equation {
+                left: Box::new(expr),
+                right: Box::new(self.parse_conversion()?),
+            };
+        }
+        self.expect_end()?;
+        Ok(expr)
     }
 
     /// Parse an assignment according to the grammar rules
     fn parse_assignment(&mut self) -> Result<Expr, ParseError> {
-        let expr = self.parse_expression()?;
+        let expr = self.parse_conversion()?;
 
         if matches!(self.current_token(), Token::Equals) {
-            if let Expr::Variable(name) = expr {
-                self.advance();
-                let value = self.parse_assignment()?;
-                return Ok(Expr::Assignment {
-                    name,
-                    value: Box::new(value),
-                });
-            } else {
-                return Err(ParseError::InvalidExpression {
-                    message: "Left side of assignment must be a variable".to_string(),
-                    position: self.current_token_pos(),
-                });
+            let position = self.current_token_pos();
+            // A primed name only means something in an odesolve equation.
+            let primed = |name: &str| (name.contains('\'')).then(|| ParseError::InvalidExpression {
+                message: format!("{} stands for a derivative and can only appear in odesolve equations; choose a name without a prime", name),
+                position,
+            });
+            match expr {
+                Expr::Variable(name) => {
+                    if let Some(error) = primed(&name) {
+                        return Err(error);
+                    }
+                    self.advance();
+                    let value = self.parse_assignment()?;
+                    return Ok(Expr::Assignment {
+                        name,
+                        value: Box::new(value),
+                    });
+                },
+                Expr::FunctionCall { func: Function::User(name), args } => {
+                    if let Some(error) = primed(&name) {
+                        return Err(error);
+                    }
+                    let params = args.into_iter()
+                        .map(|arg| match arg {
+                            Expr::Variable(param) => primed(&param).map_or(Ok(param), Err),
+                            _ => Err(ParseError::InvalidExpression {
+                                message: format!("Parameters of {} must be plain names", name),
+                                position,
+                            }),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    self.advance();
+                    let body = self.parse_assignment()?;
+                    return Ok(Expr::FunctionDef {
+                        name,
+                        params,
+                        body: Box::new(body),
+                    });
+                },
+                Expr::FunctionCall { func, .. } => {
+                    return Err(ParseError::InvalidExpression {
+                        message: format!("Cannot redefine the built-in function {:?}", func),
+                        position,
+                    });
+                },
+                _ => {
+                    return Err(ParseError::InvalidExpression {
+                        message: "Left side of assignment must be a variable or function".to_string(),
+                        position,
+                    });
+                },
             }
         }
 
         Ok(expr)
     }
 
+    /// Parses a unit conversion according to the grammar rules.
+    fn parse_conversion(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_expression()?;
+
+        while matches!(self.current_token(), Token::To) || self.in_base_follows(0) {
+            self.advance();
+            let target = self.parse_conversion_target()?;
+            expr = Expr::Conversion {
+                expr: Box::new(expr),
+                target,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    /// Parses the target after `to` or `in`: a base, a currency or a unit.
+    fn parse_conversion_target(&mut self) -> Result<ConversionTarget, ParseError> {
+        if let Some(base) = self.parse_base()? {
+            return Ok(ConversionTarget::Base(base));
+        }
+        if let Some(code) = self.currency_at(0) {
+            self.advance();
+            return Ok(ConversionTarget::Currency(code));
+        }
+        Ok(ConversionTarget::Unit(self.parse_unit()?))
+    }
+
+    /// Parses `hex`, `bin`, `oct`, `dec` or `base N`, returning `None` if no base follows.
+    fn parse_base(&mut self) -> Result<Option<u32>, ParseError> {
+        if !self.base_at(0) {
+            return Ok(None);
+        }
+        let position = self.current_token_pos();
+        let name = match self.current_token() {
+            Token::Identifier(name) => name.clone(),
+            _ => unreachable!(),
+        };
+        self.advance();
+        if let Some(base) = radix::named_base(&name) {
+            return Ok(Some(base));
+        }
+
+        let base = match self.current_token() {
+            Token::Number(n) if n.fract() == 0.0 => *n,
+            _ => 0.0,
+        };
+        self.advance();
+        radix::check_base(base as u32)
+            .map(Some)
+            .map_err(|e| ParseError::InvalidExpression { message: e.to_string(), position })
+    }
+
     /// Parses an expression according to the grammar rules.
     fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+        self.parse_logical_or()
+    }
+
+    /// Parses a logical or according to the grammar rules.
+    fn parse_logical_or(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_logical_and()?;
+        while matches!(self.current_token(), Token::Or) {
+            self.advance();
+            let right = self.parse_logical_and()?;
+            left = Expr::BinaryOp {
+                left: Box::new(left),
+                op: BinOp::Or,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    /// Parses a logical and according to the grammar rules.
+    fn parse_logical_and(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_logical_not()?;
+        while matches!(self.current_token(), Token::And) {
+            self.advance();
+            let right = self.parse_logical_not()?;
+            left = Expr::BinaryOp {
+                left: Box::new(left),
+                op: BinOp::And,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    /// Parses a logical not according to the grammar rules.
+    fn parse_logical_not(&mut self) -> Result<Expr, ParseError> {
+        if matches!(self.current_token(), Token::Not) {
+            self.advance();
+            let expr = self.parse_logical_not()?;
+            return Ok(Expr::UnaryOp {
+                op: UnOp::Not,
+                expr: Box::new(expr),
+            });
+        }
+        self.parse_comparison()
+    }
+
+    /// Parses a comparison according to the grammar rules.
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.parse_bitwise_or()?;
+        let Some(op) = self.comparison_op() else { return Ok(left) };
+
+        self.advance();
+        let right = self.parse_bitwise_or()?;
+        if self.comparison_op().is_some() {
+            return Err(ParseError::InvalidExpression {
+                message: "Comparisons cannot be chained; combine them with 'and'".to_string(),
+                position: self.current_token_pos(),
+            });
+        }
+        Ok(Expr::BinaryOp {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        })
+    }
+
+    fn comparison_op(&self) -> Option<BinOp> {
+        match self.current_token() {
+            Token::Less => Some(BinOp::Less),
+            Token::LessEqual => Some(BinOp::LessEqual),
+            Token::EqualEqual => Some(BinOp::Equal),
+            Token::NotEqual => Some(BinOp::NotEqual),
+            Token::GreaterEqual => Some(BinOp::GreaterEqual),
+            Token::Greater => Some(BinOp::Greater),
+            _ => None,
+        }
+    }
+
+    /// Parses a bitwise or according to the grammar rules.
+    fn parse_bitwise_or(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_bitwise_xor()?;
+        while matches!(self.current_token(), Token::Pipe) {
+            self.advance();
+            let right = self.parse_bitwise_xor()?;
+            left = Expr::BinaryOp {
+                left: Box::new(left),
+                op: BinOp::BitOr,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    /// Parses a bitwise exclusive or according to the grammar rules.
+    fn parse_bitwise_xor(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_bitwise_and()?;
+        while matches!(self.current_token(), Token::Xor) {
+            self.advance();
+            let right = self.parse_bitwise_and()?;
+            left = Expr::BinaryOp {
+                left: Box::new(left),
+                op: BinOp::BitXor,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    /// Parses a bitwise and according to the grammar rules.
+    fn parse_bitwise_and(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_shift()?;
+        while matches!(self.current_token(), Token::Ampersand) {
+            self.advance();
+            let right = self.parse_shift()?;
+            left = Expr::BinaryOp {
+                left: Box::new(left),
+                op: BinOp::BitAnd,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    /// Parses a bit shift according to the grammar rules.
+    fn parse_shift(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_sum()?;
+
+        while matches!(self.current_token(), Token::ShiftLeft | Token::ShiftRight) {
+            let op = match self.current_token() {
+                Token::ShiftLeft => BinOp::ShiftLeft,
+                Token::ShiftRight => BinOp::ShiftRight,
+                _ => unreachable!(),
+            };
+
+            self.advance();
+            let right = self.parse_sum()?;
+            left = Expr::BinaryOp {
+                left: Box::new(left),
+                op,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(left)
+    }
+
+    /// Parses a sum or difference according to the grammar rules.
+    fn parse_sum(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_term()?;
 
         while matches!(self.current_token(), Token::Plus | Token::Minus) {
@@ -81,10 +377,13 @@ impl Parser {
     fn parse_term(&mut self) -> Result<Expr, ParseError> {
         let mut left = self.parse_factor()?;
 
-        while matches!(self.current_token(), Token::Asterisk | Token::Slash) {
+        while matches!(self.current_token(), Token::Asterisk | Token::Slash | Token::DoubleSlash | Token::Mod | Token::Rem) {
             let op = match self.current_token() {
                 Token::Asterisk => BinOp::Multiply,
                 Token::Slash => BinOp::Divide,
+                Token::DoubleSlash => BinOp::FloorDivide,
+                Token::Mod => BinOp::Modulo,
+                Token::Rem => BinOp::Remainder,
                 _ => unreachable!(),
             };
 
@@ -121,10 +420,11 @@ impl Parser {
 
     /// Parses a unary expression according to the grammar rules.
     fn parse_unary(&mut self) -> Result<Expr, ParseError> {
-        if matches!(self.current_token(), Token::Plus | Token::Minus) {
+        if matches!(self.current_token(), Token::Plus | Token::Minus | Token::Tilde) {
             let op = match self.current_token() {
                 Token::Plus => UnOp::Positive,
                 Token::Minus => UnOp::Negate,
+                Token::Tilde => UnOp::BitNot,
                 _ => unreachable!(),
             };
             self.advance();
@@ -141,25 +441,57 @@ impl Parser {
     /// Parses a postfix expression according to the grammar rules.
     fn parse_postfix(&mut self) -> Result<Expr, ParseError> {
         let primary = self.parse_primary()?;
-        if matches!(self.current_token(), Token::Exclamation) {
-            self.advance();
-            Ok(Expr::PostfixOp {
-                expr: Box::new(primary),
-                op: UnOp::Factorial,
-            })
-        } else {
-            Ok(primary)
-        }
+        let op = match self.current_token() {
+            Token::Exclamation => UnOp::Factorial,
+            Token::Percent => UnOp::Percent,
+            _ => return Ok(primary),
+        };
+        self.advance();
+        Ok(Expr::PostfixOp {
+            expr: Box::new(primary),
+            op,
+        })
     }
 
-    /// Parses a primary expression according to the grammar rules.
+    /// Parses a primary expression according to the grammar rules. Nested
+    /// parentheses, arguments and lists all pass through here, so this is
+    /// where the stack is extended for deeply nested input.
     fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        stacker::maybe_grow(64 * 1024, 1024 * 1024, || self.parse_primary_inner())
+    }
+
+    fn parse_primary_inner(&mut self) -> Result<Expr, ParseError> {
         match self.current_token() {
             Token::Number(n) => {
                 let val = *n;
                 self.advance();
+                if self.unit_follows(0) {
+                    return self.parse_quantity(val);
+                }
+                if let Some(currency) = self.currency_at(0) {
+                    self.advance();
+                    return Ok(Expr::Money {
+                        value: Box::new(Expr::Number(val)),
+                        currency,
+                    });
+                }
                 Ok(Expr::Number(val))
             }
+            Token::Integer(i) => {
+                let i = *i;
+                self.advance();
+                Ok(Expr::Integer(i))
+            }
+            Token::True | Token::False => {
+                let value = matches!(self.current_token(), Token::True);
+                self.advance();
+                Ok(Expr::Bool(value))
+            }
+            Token::DateTime(dt) => {
+                let dt = *dt;
+                self.advance();
+                Ok(Expr::DateTime(dt))
+            }
             Token::Identifier(name) => {
                 if matches!(self.peek_token(), Token::LParen) {
                     self.parse_function_call()
@@ -169,9 +501,11 @@ impl Parser {
                     Ok(Expr::Variable(name))
                 }
             }
+            Token::LBrace => self.parse_piecewise(),
+            Token::LBracket => self.parse_list(),
             Token::LParen => {
                 self.advance();
-                let expr = self.parse_expression()?;
+                let expr = self.parse_conversion()?;
                 self.expect(Token::RParen)?;
                 Ok(expr)
             }
@@ -192,7 +526,7 @@ impl Parser {
             let mut args = Vec::new();
             if !matches!(self.current_token(), Token::RParen) {
                 loop {
-                    let arg = self.parse_expression()?;
+                    let arg = self.parse_argument()?;
                     args.push(arg);
                     if matches!(self.current_token(), Token::RParen) {
                         break;
@@ -202,8 +536,7 @@ impl Parser {
             }
             self.expect(Token::RParen)?;
             Ok(Expr::FunctionCall {
-                func: Function::from_str(&func_name)
-                    .ok_or_else(|| ParseError::InvalidExpression { message: format!("Unknown function: {}", func_name), position: self.current_token_pos() })?,
+                func: Function::from_str(&func_name).unwrap_or(Function::User(func_name)),
                 args,
             })
         } else {
@@ -215,6 +548,201 @@ impl Parser {
         }
     }    
 
+    fn parse_list(&mut self) -> Result<Expr, ParseError> {
+        self.expect(Token::LBracket)?;
+        let mut items = Vec::new();
+        if !matches!(self.current_token(), Token::RBracket) {
+            loop {
+                items.push(self.parse_argument()?);
+                if matches!(self.current_token(), Token::RBracket) {
+                    break;
+                }
+                self.expect(Token::Comma)?;
+            }
+        }
+        self.expect(Token::RBracket)?;
+        Ok(Expr::List(items))
+    }
+
+    /// Parses a function argument or list item, which may be an equation.
+    fn parse_argument(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.parse_expression()?;
+        if !matches!(self.current_token(), Token::Equals) {
+            return Ok(expr);
+        }
+        self.advance();
+        Ok(Expr::Equation {
+            left: Box::new(expr),
+            right: Box::new(self.parse_expression()?),
+        })
+    }
+
+    /// Parses a piecewise expression according to the grammar rules.
+    fn parse_piecewise(&mut self) -> Result<Expr, ParseError> {
+        self.expect(Token::LBrace)?;
+        let mut pieces = Vec::new();
+        let mut otherwise = None;
+
+        loop {
+            let value = self.parse_expression()?;
+            match self.current_token() {
+                Token::Identifier(word) if word == "if" => {
+                    self.advance();
+                    let condition = self.parse_expression()?;
+                    pieces.push((value, condition));
+                },
+                Token::Identifier(word) if word == "otherwise" && otherwise.is_none() => {
+                    self.advance();
+                    otherwise = Some(Box::new(value));
+                },
+                _ => return Err(ParseError::UnexpectedToken {
+                    expected: "'if' or 'otherwise'".to_string(),
+                    found: format!("{:?}", self.current_token()),
+                    position: self.current_token_pos(),
+                }),
+            }
+
+            if !matches!(self.current_token(), Token::Semicolon) {
+                break;
+            }
+            self.advance();
+        }
+
+        self.expect(Token::RBrace)?;
+        Ok(Expr::Piecewise { pieces, otherwise })
+    }
+
+    /// Parses a quantity whose number has already been consumed. Consecutive
+    /// quantities of the same dimension are summed, so `3d 4h` and `5 ft 3 in` work.
+    fn parse_quantity(&mut self, value: f64) -> Result<Expr, ParseError> {
+        let unit = self.parse_unit()?;
+        let dim = unit.dim;
+        let mut expr = Expr::Quantity {
+            value: Box::new(Expr::Number(value)),
+            unit,
+        };
+
+        while let Token::Number(n) = self.current_token() {
+            let same_dimension = match self.token_at(1) {
+                Token::Identifier(name) => Unit::lookup(name).is_some_and(|u| u.dim == dim),
+                _ => false,
+            };
+            if !same_dimension || !self.unit_follows(1) {
+                break;
+            }
+            let n = *n;
+            self.advance();
+            let unit = self.parse_unit()?;
+            expr = Expr::BinaryOp {
+                left: Box::new(expr),
+                op: BinOp::Add,
+                right: Box::new(Expr::Quantity {
+                    value: Box::new(Expr::Number(n)),
+                    unit,
+                }),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    /// Parses a unit expression such as `m/s^2` or `kg*m`.
+    fn parse_unit(&mut self) -> Result<Unit, ParseError> {
+        let mut unit = self.parse_unit_power()?;
+
+        while matches!(self.current_token(), Token::Asterisk | Token::Slash) && self.unit_follows(1) {
+            let is_division = matches!(self.current_token(), Token::Slash);
+            self.advance();
+            let right = self.parse_unit_power()?;
+            unit = if is_division { unit.div(&right) } else { unit.mul(&right) };
+        }
+
+        Ok(unit)
+    }
+
+    /// Parses a unit symbol or parenthesized unit with an optional integer exponent.
+    fn parse_unit_power(&mut self) -> Result<Unit, ParseError> {
+        let position = self.current_token_pos();
+        let unit = match self.current_token() {
+            Token::Identifier(name) => {
+                let unit = Unit::lookup(name).ok_or_else(|| ParseError::InvalidExpression {
+                    message: format!("Unknown unit: {}", name),
+                    position,
+                })?;
+                self.advance();
+                unit
+            }
+            Token::LParen => {
+                self.advance();
+                let unit = self.parse_unit()?;
+                self.expect(Token::RParen)?;
+                Unit { name: format!("({})", unit.name), ..unit }
+            }
+            _ => return Err(ParseError::UnexpectedToken {
+                expected: "Unit".to_string(),
+                found: format!("{:?}", self.current_token()),
+                position,
+            }),
+        };
+
+        if !matches!(self.current_token(), Token::Caret) {
+            return Ok(unit);
+        }
+        self.advance();
+
+        let negative = matches!(self.current_token(), Token::Minus);
+        if negative {
+            self.advance();
+        }
+        match self.current_token() {
+            Token::Number(n) if n.fract() == 0.0 => {
+                let exp = if negative { -(*n as i32) } else { *n as i32 };
+                self.advance();
+                Ok(unit.powi(exp))
+            }
+            _ => Err(ParseError::InvalidExpression {
+                message: "Unit exponents must be integers".to_string(),
+                position: self.current_token_pos(),
+            }),
+        }
+    }
+
+    /// Whether the token `offset` places ahead starts a unit rather than a function call or value.
+    fn unit_follows(&self, offset: usize) -> bool {
+        match self.token_at(offset) {
+            Token::Identifier(_) if self.in_base_follows(offset) => false,
+            Token::Identifier(name) => {
+                !matches!(self.token_at(offset + 1), Token::LParen) && Unit::lookup(name).is_some()
+            }
+            Token::LParen => self.unit_follows(offset + 1),
+            _ => false,
+        }
+    }
+
+    /// Whether the token `offset` places ahead names a base: `hex`, `bin`, `oct`, `dec` or `base N`.
+    fn base_at(&self, offset: usize) -> bool {
+        match self.token_at(offset) {
+            Token::Identifier(name) if name == "base" => matches!(self.token_at(offset + 1), Token::Number(_)),
+            Token::Identifier(name) => radix::named_base(name).is_some(),
+            _ => false,
+        }
+    }
+
+    /// Whether `offset` places ahead is `in` used as a base conversion rather than the inch symbol.
+    fn in_base_follows(&self, offset: usize) -> bool {
+        matches!(self.token_at(offset), Token::Identifier(name) if name == "in") && self.base_at(offset + 1)
+    }
+
+    /// The currency code `offset` places ahead, if that token is one and not also a unit symbol.
+    fn currency_at(&self, offset: usize) -> Option<String> {
+        match self.token_at(offset) {
+            Token::Identifier(code) if currency::is_currency_code(code)
+                && Unit::lookup(code).is_none()
+                && !matches!(self.token_at(offset + 1), Token::LParen) => Some(code.clone()),
+            _ => None,
+        }
+    }
+
     fn current_token(&self) -> &Token {
         self.tokens
             .get(self.position)
@@ -230,8 +758,12 @@ impl Parser {
     }
 
     fn peek_token(&self) -> &Token {
-        &self.tokens
-            .get(self.position + 1)
+        self.token_at(1)
+    }
+
+    fn token_at(&self, offset: usize) -> &Token {
+        self.tokens
+            .get(self.position + offset)
             .map(|twp| &twp.token)
             .unwrap_or(&Token::EOF)
     }
@@ -313,4 +845,209 @@ mod tests {
             panic!("Expected assignment expression");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_quantity_literal() {
+        let expr = parse_expr("9.81 m/s^2 * 2 kg").unwrap();
+        if let Expr::BinaryOp { left, op, right } = expr {
+            assert_eq!(op, BinOp::Multiply);
+            if let Expr::Quantity { unit, .. } = *left {
+                assert_eq!(unit.name, "m/s^2");
+            } else {
+                panic!("Expected quantity on the left");
+            }
+            assert!(matches!(*right, Expr::Quantity { .. }));
+        } else {
+            panic!("Expected binary operation");
+        }
+    }
+
+    #[test]
+    fn test_compound_duration() {
+        let expr = parse_expr("3d 4h").unwrap();
+        if let Expr::BinaryOp { left, op, right } = expr {
+            assert_eq!(op, BinOp::Add);
+            assert!(matches!(*left, Expr::Quantity { .. }));
+            assert!(matches!(*right, Expr::Quantity { .. }));
+        } else {
+            panic!("Expected compound quantity");
+        }
+        assert!(matches!(parse_expr("2 kg 300 g").unwrap(), Expr::BinaryOp { op: BinOp::Add, .. }));
+        // Only quantities of one dimension add up; anything else after a
+        // quantity is a stray token, never silently dropped.
+        assert!(matches!(
+            parse_expr("3 m 4 s"),
+            Err(ParseError::UnexpectedToken { position: 4, .. })
+        ));
+        assert!(parse_expr("3 m 4").is_err());
+    }
+
+    #[test]
+    fn test_conversion() {
+        let expr = parse_expr("100 km/h to m/s").unwrap();
+        if let Expr::Conversion { target: ConversionTarget::Unit(unit), .. } = expr {
+            assert_eq!(unit.name, "m/s");
+        } else {
+            panic!("Expected conversion expression");
+        }
+        assert!(parse_expr("3 m to foo").is_err());
+
+        let expr = parse_expr("100 USD to EUR").unwrap();
+        if let Expr::Conversion { expr, target: ConversionTarget::Currency(code) } = expr {
+            assert_eq!(code, "EUR");
+            assert!(matches!(*expr, Expr::Money { .. }));
+        } else {
+            panic!("Expected currency conversion");
+        }
+    }
+
+    #[test]
+    fn test_bitwise_precedence() {
+        // 1 | (2 xor (3 & (4 << (1 + 1))))
+        let expr = parse_expr("1 | 2 xor 3 & 4 << 1 + 1").unwrap();
+        let Expr::BinaryOp { op, right, .. } = expr else { panic!("Expected binary operation") };
+        assert_eq!(op, BinOp::BitOr);
+        let Expr::BinaryOp { op, right, .. } = *right else { panic!("Expected xor") };
+        assert_eq!(op, BinOp::BitXor);
+        let Expr::BinaryOp { op, right, .. } = *right else { panic!("Expected and") };
+        assert_eq!(op, BinOp::BitAnd);
+        let Expr::BinaryOp { op, right, .. } = *right else { panic!("Expected shift") };
+        assert_eq!(op, BinOp::ShiftLeft);
+        assert!(matches!(*right, Expr::BinaryOp { op: BinOp::Add, .. }));
+        assert!(matches!(parse_expr("~5").unwrap(), Expr::UnaryOp { op: UnOp::BitNot, .. }));
+    }
+
+    #[test]
+    fn test_base_conversion() {
+        assert!(matches!(
+            parse_expr("255 in hex").unwrap(),
+            Expr::Conversion { target: ConversionTarget::Base(16), .. }
+        ));
+        assert!(matches!(
+            parse_expr("0b1010 to base 36").unwrap(),
+            Expr::Conversion { target: ConversionTarget::Base(36), .. }
+        ));
+        // Without a base after it, `in` is still the inch.
+        assert!(matches!(parse_expr("3 in").unwrap(), Expr::Quantity { .. }));
+        assert!(parse_expr("3 in base 40").is_err());
+    }
+
+    #[test]
+    fn test_comparison_and_logic() {
+        // not (x > 1) or (y == 2 and z)
+        let expr = parse_expr("not x > 1 or y == 2 and z").unwrap();
+        let Expr::BinaryOp { left, op, right } = expr else { panic!("Expected binary operation") };
+        assert_eq!(op, BinOp::Or);
+        assert!(matches!(*left, Expr::UnaryOp { op: UnOp::Not, .. }));
+        assert!(matches!(*right, Expr::BinaryOp { op: BinOp::And, .. }));
+
+        assert!(matches!(parse_expr("x == 5").unwrap(), Expr::BinaryOp { op: BinOp::Equal, .. }));
+        assert!(matches!(parse_expr("x = 5 == 5").unwrap(), Expr::Assignment { .. }));
+        assert!(matches!(parse_expr("1 + 2 < 2 * 3").unwrap(), Expr::BinaryOp { op: BinOp::Less, .. }));
+        assert!(parse_expr("1 < 2 < 3").is_err());
+    }
+
+    #[test]
+    fn test_equation() {
+        let parse_equation = |input: &str| Parser::new(Lexer::new(input).tokenize().unwrap()).parse_equation();
+        let expr = parse_equation("sin(x*y) = 0.5").unwrap();
+        if let Expr::Equation { left, right } = expr {
+            assert!(matches!(*left, Expr::FunctionCall { func: Function::Sin, .. }));
+            assert_eq!(*right, Expr::Number(0.5));
+        } else {
+            panic!("Expected equation");
+        }
+        assert!(matches!(parse_equation("x^2 - y").unwrap(), Expr::BinaryOp { .. }));
+        assert!(matches!(parse_equation("x == y").unwrap(), Expr::BinaryOp { op: BinOp::Equal, .. }));
+        assert!(parse_equation("x = y = 2").is_err());
+        // Outside equations `=` is still assignment.
+        assert!(parse_expr("x^2 = 4").is_err());
+
+        // Arguments and list items may be equations.
+        let expr = parse_expr("odesolve([x' = v, v' = -x], [x, v], t, 0, 1, [1, 0])").unwrap();
+        let Expr::FunctionCall { args, .. } = expr else { panic!("Expected a call") };
+        let Expr::List(equations) = &args[0] else { panic!("Expected a list") };
+        assert!(matches!(&equations[1], Expr::Equation { left, .. } if **left == Expr::Variable("v'".to_string())));
+        // Primed names are derivatives, never assigned to or defined.
+        for input in ["f' = 3", "f'(x) = x", "g(x') = x"] {
+            assert!(matches!(parse_expr(input), Err(ParseError::InvalidExpression { message, .. }) if message.contains("odesolve")), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_function_definition() {
+        let expr = parse_expr("f(x, y) = x^2 + y").unwrap();
+        if let Expr::FunctionDef { name, params, .. } = expr {
+            assert_eq!(name, "f");
+            assert_eq!(params, vec!["x".to_string(), "y".to_string()]);
+        } else {
+            panic!("Expected function definition");
+        }
+        assert!(parse_expr("sin(x) = 2").is_err());
+        assert!(parse_expr("f(2) = 2").is_err());
+        assert!(matches!(
+            parse_expr("f(3)").unwrap(),
+            Expr::FunctionCall { func: Function::User(_), .. }
+        ));
+    }
+
+    #[test]
+    fn test_piecewise() {
+        let expr = parse_expr("{x^2 if x < 0; x otherwise}").unwrap();
+        if let Expr::Piecewise { pieces, otherwise } = expr {
+            assert_eq!(pieces.len(), 1);
+            assert!(matches!(pieces[0].1, Expr::BinaryOp { op: BinOp::Less, .. }));
+            assert!(otherwise.is_some());
+        } else {
+            panic!("Expected piecewise expression");
+        }
+        assert!(parse_expr("{1 if x > 0; 2}").is_err());
+        assert!(parse_expr("{1 if x > 0").is_err());
+    }
+
+    #[test]
+    fn test_lists_and_constants() {
+        assert_eq!(parse_expr("[1, 2]").unwrap(), Expr::List(vec![Expr::Number(1.0), Expr::Number(2.0)]));
+        assert_eq!(parse_expr("[]").unwrap(), Expr::List(vec![]));
+        assert!(parse_expr("[1, 2").is_err());
+        assert!(parse_expr("sqrt(2) * [1, 2 + 3]").unwrap().is_constant());
+        assert!(!parse_expr("2 * x").unwrap().is_constant());
+        assert!(!parse_expr("1 + rand()").unwrap().is_constant());
+        assert!(!parse_expr("normrnd(0, 1)").unwrap().is_constant());
+        assert!(!parse_expr("today()").unwrap().is_constant());
+    }
+
+    #[test]
+    fn test_modulo_and_percent() {
+        assert!(matches!(parse_expr("7 mod 3 * 2").unwrap(), Expr::BinaryOp { op: BinOp::Multiply, .. }));
+        assert!(matches!(parse_expr("7 // 2").unwrap(), Expr::BinaryOp { op: BinOp::FloorDivide, .. }));
+        let expr = parse_expr("200 + 15%").unwrap();
+        let Expr::BinaryOp { op, right, .. } = expr else { panic!("Expected binary operation") };
+        assert_eq!(op, BinOp::Add);
+        assert!(matches!(*right, Expr::PostfixOp { op: UnOp::Percent, .. }));
+        // `%` is not a remainder operator, so a trailing operand is rejected.
+        assert!(parse_expr("10 % 3").is_err());
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        // Printed with only the parentheses the grammar needs, and parsed
+        // back to the same tree.
+        for (input, printed) in [
+            ("(x+1)*2 - x/(y*z)", "(x + 1) * 2 - x / (y * z)"),
+            ("a - (b - c) + (d + e)", "a - (b - c) + (d + e)"),
+            ("2^3^2 + (2^3)^2", "2 ^ 3 ^ 2 + (2 ^ 3) ^ 2"),
+            ("-x^2 + -(x^2) + (x+1)!", "-x ^ 2 + -(x ^ 2) + (x + 1)!"),
+            ("sin(x)^2 + normcdf(1, 0, 1)", "sin(x) ^ 2 + normcdf(1, 0, 1)"),
+            ("not (a < b) and c or d", "not a < b and c or d"),
+            ("f(x, y) = {x if x > y; y otherwise}", "f(x, y) = {x if x > y; y otherwise}"),
+            ("[1, 0.5, true] ", "[1, 0.5, true]"),
+            ("(255 + 1) in hex", "255 + 1 in hex"),
+            ("200 + 15%", "200 + 15%"),
+        ] {
+            let expr = parse_expr(input).unwrap();
+            assert_eq!(expr.to_string(), printed);
+            assert_eq!(parse_expr(printed).unwrap(), expr, "{}", printed);
+        }
+    }
+}
@@ -0,0 +1,429 @@
+//! Sampling of functions and curves for plotting.
+//!
+//! A curve is first evaluated on an even grid of its parameter, then every
+//! interval whose midpoint strays from the chord between its ends, or whose
+//! chord is too long, is halved until the polyline follows the curve with
+//! roughly even steps along its length. Points where the curve is undefined
+//! split it into segments, and so do jumps: an interval whose change does
+//! not shrink as it is halved (a step, or a pole such as `tan(90)`) is a
+//! discontinuity rather than a steep stretch, and is never joined.
+
+use serde::Serialize;
+
+use crate::{compile::CompiledExpr, error::EvalError, evaluator::Evaluator, functions, table::Table};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    const GAP: Point = Point { x: f64::NAN, y: f64::NAN };
+
+    fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
+}
+
+/// A sampled curve: polylines to draw, with gaps between them.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct Curve {
+    pub segments: Vec<Vec<Point>>,
+}
+
+impl Curve {
+    /// Splits a run of points into segments at the non-finite ones. A lone
+    /// point between two gaps, such as the value a pole rounds to, can't be
+    /// drawn as a line and is dropped.
+    pub(crate) fn from_points(points: &[Point]) -> Self {
+        let segments = points
+            .split(|p| !p.is_finite())
+            .filter(|segment| segment.len() > 1)
+            .map(<[Point]>::to_vec)
+            .collect();
+        Curve { segments }
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &Point> {
+        self.segments.iter().flatten()
+    }
+
+    /// The points as rows of `x` and `y`, with an undefined row between
+    /// segments so that plotting the table breaks the curve in the same places.
+    pub fn to_table(&self) -> Table {
+        let gap = vec![f64::NAN, f64::NAN];
+        let rows = self.segments.iter().enumerate()
+            .flat_map(|(i, segment)| {
+                let gap = (i > 0).then(|| gap.clone());
+                gap.into_iter().chain(segment.iter().map(|p| vec![p.x, p.y]))
+            })
+            .collect();
+        Table { columns: vec!["x".to_string(), "y".to_string()], labels: Vec::new(), rows }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleOptions {
+    /// Evenly spaced intervals evaluated before any refinement.
+    pub intervals: usize,
+    /// How many times an interval may be halved.
+    pub max_depth: u32,
+    /// How far a midpoint may stray from the chord, as a fraction of the
+    /// curve's size, before its interval is halved.
+    pub tolerance: f64,
+    /// Longest chord, as a fraction of the curve's size, so that fast-moving
+    /// stretches get as many points as slow ones.
+    pub max_step: f64,
+    /// Cap on the number of evaluations, so erratic functions stay fast.
+    pub max_evaluations: usize,
+}
+
+impl Default for SampleOptions {
+    fn default() -> Self {
+        SampleOptions { intervals: 200, max_depth: 10, tolerance: 1e-3, max_step: 0.02, max_evaluations: 20_000 }
+    }
+}
+
+/// Halvings used to tell a jump from a steep but continuous stretch.
+const JUMP_BISECTIONS: u32 = 40;
+
+/// Samples the graph of `f`, a function of one variable, over `[from, to]`.
+pub fn sample_function(
+    f: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    from: f64,
+    to: f64,
+    options: &SampleOptions,
+) -> Result<Curve, EvalError> {
+    check_variable(f)?;
+    sample_curve(from, to, options, |t| Point { x: t, y: f.eval(evaluator, &[t]) })
+}
+
+/// Samples the curve `(x(t), y(t))` for `t` in `[from, to]`.
+pub fn sample_parametric(
+    x: &CompiledExpr,
+    y: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    from: f64,
+    to: f64,
+    options: &SampleOptions,
+) -> Result<Curve, EvalError> {
+    check_variable(x)?;
+    check_variable(y)?;
+    sample_curve(from, to, options, |t| Point { x: x.eval(evaluator, &[t]), y: y.eval(evaluator, &[t]) })
+}
+
+/// Samples the polar curve `r(θ)` for `θ` in `[from, to]`, with angles in
+/// the evaluator's angle mode.
+pub fn sample_polar(
+    r: &CompiledExpr,
+    evaluator: &mut Evaluator,
+    from: f64,
+    to: f64,
+    options: &SampleOptions,
+) -> Result<Curve, EvalError> {
+    check_variable(r)?;
+    let mode = evaluator.get_context().get_angle_mode();
+    sample_curve(from, to, options, |theta| {
+        let radius = r.eval(evaluator, &[theta]);
+        let (sin, cos) = functions::to_radians(theta, mode).sin_cos();
+        Point { x: radius * cos, y: radius * sin }
+    })
+}
+
+fn check_variable(f: &CompiledExpr) -> Result<(), EvalError> {
+    if f.variables().len() == 1 {
+        Ok(())
+    } else {
+        Err(EvalError::InvalidArguments("a curve to plot takes exactly one variable".to_string()))
+    }
+}
+
+fn sample_curve(
+    from: f64,
+    to: f64,
+    options: &SampleOptions,
+    eval: impl FnMut(f64) -> Point,
+) -> Result<Curve, EvalError> {
+    if !from.is_finite() || !to.is_finite() || from >= to {
+        return Err(EvalError::InvalidArguments(format!(
+            "cannot sample over [{}, {}]: expected a finite range with from < to", from, to
+        )));
+    }
+
+    let mut sampler = Sampler { eval, options, scale: (1.0, 1.0), evaluations: 0, points: Vec::new() };
+    let intervals = options.intervals.max(1);
+    let grid: Vec<Sample> = (0..=intervals)
+        .map(|i| sampler.sample(from + (to - from) * i as f64 / intervals as f64))
+        .collect();
+    sampler.scale = (spread(grid.iter().map(|s| s.point.x)), spread(grid.iter().map(|s| s.point.y)));
+
+    sampler.points.push(grid[0].point);
+    for pair in grid.windows(2) {
+        sampler.refine(pair[0], pair[1], 0);
+        sampler.points.push(pair[1].point);
+    }
+    Ok(Curve::from_points(&sampler.points))
+}
+
+/// The spread of the middle 90% of the finite values, so that a few huge
+/// values next to a pole don't make every other detail look flat.
+fn spread(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values: Vec<f64> = values.filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return 1.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let low = values[values.len() / 20];
+    let high = values[values.len() - 1 - values.len() / 20];
+    let spread = high - low;
+    if spread > 0.0 {
+        spread
+    } else {
+        low.abs().max(1.0)
+    }
+}
+
+/// Distance between two points relative to the curve's width and height.
+fn distance(p: Point, q: Point, scale: (f64, f64)) -> f64 {
+    ((p.x - q.x) / scale.0).abs().max(((p.y - q.y) / scale.1).abs())
+}
+
+/// What halving the interval between two samples finds.
+pub(crate) enum Jump<S> {
+    /// The change shrinks with the interval, so it is continuous.
+    None,
+    /// Undefined at the middle sample, between the other two.
+    Undefined([S; 3]),
+    /// A jump, or a pole, between the two samples.
+    Between(S, S),
+}
+
+/// Tells a jump between samples `a` and `b` from a steep but continuous
+/// stretch. Halving towards the larger change shrinks a continuous change
+/// with the interval, while a jump keeps (or, near a pole, grows) its size.
+/// `distance` is infinite or NaN where the function is undefined.
+pub(crate) fn find_jump<S: Copy>(
+    a: S,
+    b: S,
+    midpoint: &mut dyn FnMut(S, S) -> S,
+    distance: &dyn Fn(S, S) -> f64,
+) -> Jump<S> {
+    let (mut left, mut right) = (a, b);
+    let mut first = None;
+    for _ in 0..JUMP_BISECTIONS {
+        let mid = midpoint(left, right);
+        let (to_left, to_right) = (distance(left, mid), distance(mid, right));
+        if !(to_left.is_finite() && to_right.is_finite()) {
+            return Jump::Undefined([left, mid, right]);
+        }
+        let change = to_left.max(to_right);
+        let first = *first.get_or_insert(change);
+        if change == 0.0 || change <= first * 1e-6 {
+            return Jump::None;
+        }
+        if to_left > to_right {
+            right = mid;
+        } else {
+            left = mid;
+        }
+    }
+    if distance(left, right) > first.unwrap_or(0.0) / 2.0 { Jump::Between(left, right) } else { Jump::None }
+}
+
+/// A point together with the parameter it was evaluated at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    t: f64,
+    point: Point,
+}
+
+struct Sampler<'a, F> {
+    eval: F,
+    options: &'a SampleOptions,
+    /// The curve's width and height, which distances are measured against.
+    scale: (f64, f64),
+    evaluations: usize,
+    points: Vec<Point>,
+}
+
+impl<F: FnMut(f64) -> Point> Sampler<'_, F> {
+    fn sample(&mut self, t: f64) -> Sample {
+        self.evaluations += 1;
+        Sample { t, point: (self.eval)(t) }
+    }
+
+    fn distance(&self, p: Point, q: Point) -> f64 {
+        distance(p, q, self.scale)
+    }
+
+    /// Adds the points needed strictly between `a` and `b`.
+    fn refine(&mut self, a: Sample, b: Sample, depth: u32) {
+        if depth >= self.options.max_depth || self.evaluations >= self.options.max_evaluations {
+            self.split_at_jump(a, b);
+            return;
+        }
+
+        let mid = self.sample((a.t + b.t) / 2.0);
+        let finite = [a.point.is_finite(), mid.point.is_finite(), b.point.is_finite()];
+        let needs_points = if finite.iter().all(|&f| f) {
+            let chord_mid = Point { x: (a.point.x + b.point.x) / 2.0, y: (a.point.y + b.point.y) / 2.0 };
+            self.distance(mid.point, chord_mid) > self.options.tolerance
+                || self.distance(a.point, b.point) > self.options.max_step
+        } else {
+            // Narrow down where the curve becomes undefined.
+            finite.iter().any(|&f| f)
+        };
+        if !needs_points {
+            return;
+        }
+
+        self.refine(a, mid, depth + 1);
+        self.points.push(mid.point);
+        self.refine(mid, b, depth + 1);
+    }
+
+    /// Breaks the curve between `a` and `b` if the change between them is a
+    /// jump.
+    fn split_at_jump(&mut self, a: Sample, b: Sample) {
+        let jump = self.distance(a.point, b.point);
+        if !jump.is_finite() || jump <= self.options.tolerance {
+            return;
+        }
+
+        let scale = self.scale;
+        let mut midpoint = |left: Sample, right: Sample| self.sample((left.t + right.t) / 2.0);
+        match find_jump(a, b, &mut midpoint, &|p, q| distance(p.point, q.point, scale)) {
+            Jump::None => {},
+            // Undefined inside: the gap splits the curve by itself.
+            Jump::Undefined(samples) => self.push_between(a, samples, b),
+            Jump::Between(left, right) => {
+                let gap = Sample { t: f64::NAN, point: Point::GAP };
+                self.push_between(a, [left, gap, right], b);
+            },
+        }
+    }
+
+    /// Adds `samples`, skipping the ends `a` and `b`, which the caller adds.
+    fn push_between(&mut self, a: Sample, samples: [Sample; 3], b: Sample) {
+        let inner = samples.into_iter().filter(|&s| s != a && s != b).map(|s| s.point);
+        self.points.extend(inner);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::AngleMode, lexer::Lexer, parser::Parser};
+
+    fn compile(input: &str, variable: &str, evaluator: &mut Evaluator) -> CompiledExpr {
+        let tokens = Lexer::new(input).tokenize().unwrap();
+        let expr = Parser::new(tokens).parse().unwrap();
+        CompiledExpr::new(&expr, &[variable], evaluator).unwrap()
+    }
+
+    fn sample(input: &str, from: f64, to: f64) -> Curve {
+        let mut evaluator = Evaluator::new();
+        evaluator.get_context_mut().set_angle_mode(AngleMode::Radians);
+        let f = compile(input, "x", &mut evaluator);
+        sample_function(&f, &mut evaluator, from, to, &SampleOptions::default()).unwrap()
+    }
+
+    fn sample_param(x: &str, y: &str, from: f64, to: f64) -> Curve {
+        let mut evaluator = Evaluator::new();
+        evaluator.get_context_mut().set_angle_mode(AngleMode::Radians);
+        let (x, y) = (compile(x, "t", &mut evaluator), compile(y, "t", &mut evaluator));
+        sample_parametric(&x, &y, &mut evaluator, from, to, &SampleOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_smooth_function_is_one_segment() {
+        let curve = sample("sin(x)", -10.0, 10.0);
+        assert_eq!(curve.segments.len(), 1);
+        let points = &curve.segments[0];
+        assert_eq!(points[0].x, -10.0);
+        assert_eq!(points[points.len() - 1].x, 10.0);
+        assert!(points.windows(2).all(|w| w[0].x < w[1].x));
+        assert!(points.iter().all(|p| (p.y - p.x.sin()).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_refines_where_curved() {
+        // A straight line needs nothing beyond the initial grid...
+        assert_eq!(sample("2*x + 1", 0.0, 1.0).points().count(), 201);
+        // ...but a sharp peak gets extra points near its tip.
+        let curve = sample("1 / (1 + 10000*x^2)", -1.0, 1.0);
+        let near = curve.points().filter(|p| p.x.abs() < 0.05).count();
+        let far = curve.points().filter(|p| p.x > 0.5 && p.x < 0.55).count();
+        assert!(near > 3 * far, "{} near the peak, {} away from it", near, far);
+    }
+
+    #[test]
+    fn test_undefined_values_split_segments() {
+        let curve = sample("sqrt(x)", -1.0, 1.0);
+        assert_eq!(curve.segments.len(), 1);
+        assert!(curve.segments[0][0].x.abs() < 1e-3);
+
+        let curve = sample("sqrt(1 - x^2)", -2.0, 2.0);
+        assert_eq!(curve.segments.len(), 1);
+        assert!(curve.points().all(|p| p.x >= -1.0 && p.x <= 1.0));
+    }
+
+    #[test]
+    fn test_jumps_split_segments() {
+        let curve = sample("1 / x", -1.0, 1.0);
+        assert_eq!(curve.segments.len(), 2);
+        assert!(curve.segments[0].iter().all(|p| p.x < 0.0 && p.y < 0.0));
+        assert!(curve.segments[1].iter().all(|p| p.x > 0.0 && p.y > 0.0));
+
+        assert_eq!(sample("floor(x)", 0.0, 3.5).segments.len(), 4);
+        // Steep is not the same as discontinuous.
+        assert_eq!(sample("atan(1000*x)", -1.0, 1.0).segments.len(), 1);
+    }
+
+    #[test]
+    fn test_parametric_steps_follow_arc_length() {
+        let circle = sample_param("cos(t)", "sin(t)", 0.0, 2.0 * std::f64::consts::PI);
+        assert_eq!(circle.segments.len(), 1);
+        assert!(circle.points().all(|p| (p.x.hypot(p.y) - 1.0).abs() < 1e-12));
+        let (first, last) = (circle.segments[0][0], circle.segments[0].last().unwrap());
+        assert!((first.x - last.x).abs() < 1e-12 && (first.y - last.y).abs() < 1e-12);
+
+        // The curve crawls near t = 0 and races near the ends, but no step
+        // along it is longer than 2% of its size.
+        let cubic = sample_param("t^3", "t^3", -1.0, 1.0);
+        let size = 0.9 * 2.0;
+        assert!(cubic.segments[0].windows(2).all(|w| (w[1].x - w[0].x).abs() <= 0.02 * size + 1e-12));
+    }
+
+    #[test]
+    fn test_parametric_gaps() {
+        let curve = sample_param("t", "1 / t", -1.0, 1.0);
+        assert_eq!(curve.segments.len(), 2);
+        // A hyperbola branch traced through the point at infinity.
+        let curve = sample_param("1 / t", "t", -1.0, 1.0);
+        assert_eq!(curve.segments.len(), 2);
+    }
+
+    #[test]
+    fn test_polar_uses_angle_mode() {
+        let mut evaluator = Evaluator::new();
+        let r = compile("2", "θ", &mut evaluator);
+        let degrees = sample_polar(&r, &mut evaluator, 0.0, 360.0, &SampleOptions::default()).unwrap();
+        assert_eq!(degrees.segments.len(), 1);
+        assert!(degrees.points().all(|p| (p.x.hypot(p.y) - 2.0).abs() < 1e-12));
+        let end = degrees.segments[0].last().unwrap();
+        assert!((end.x - 2.0).abs() < 1e-12 && end.y.abs() < 1e-12);
+
+        // A cardioid touches the origin at θ = 180°.
+        let cardioid = compile("1 + cos(θ)", "θ", &mut evaluator);
+        let curve = sample_polar(&cardioid, &mut evaluator, 0.0, 360.0, &SampleOptions::default()).unwrap();
+        assert!(curve.points().any(|p| p.x.hypot(p.y) < 1e-6));
+        assert!(curve.points().all(|p| p.x.hypot(p.y) <= 2.0 + 1e-12));
+
+        evaluator.get_context_mut().set_angle_mode(AngleMode::Radians);
+        let half = sample_polar(&r, &mut evaluator, 0.0, std::f64::consts::PI, &SampleOptions::default()).unwrap();
+        assert!(half.points().all(|p| p.y >= -1e-12));
+    }
+}
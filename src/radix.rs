@@ -0,0 +1,125 @@
+//! Formatting numbers in bases 2 to 36.
+//!
+//! Plain numbers keep their sign (`-0xFF`), while integers from programming
+//! mode are shown as their two's-complement bit pattern in the current word
+//! size, so -1 in an 8-bit word is `0xFF`. Bases 2, 8 and 16 use the `0b`,
+//! `0o` and `0x` prefixes the lexer accepts; other bases are labelled.
+
+use crate::{error::EvalError, integer::WordSize};
+
+const DIGITS: &[u8; 36] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub fn check_base(base: u32) -> Result<u32, EvalError> {
+    if (2..=36).contains(&base) {
+        Ok(base)
+    } else {
+        Err(EvalError::MathError(format!("base must be between 2 and 36, got {}", base)))
+    }
+}
+
+/// The base named by `hex`, `bin`, `oct` or `dec`.
+pub fn named_base(name: &str) -> Option<u32> {
+    match name {
+        "hex" => Some(16),
+        "bin" => Some(2),
+        "oct" => Some(8),
+        "dec" => Some(10),
+        _ => None,
+    }
+}
+
+/// Value of a single digit in the given base, accepting either letter case.
+pub fn digit_value(ch: char, base: u32) -> Option<u32> {
+    ch.to_digit(36).filter(|d| *d < base)
+}
+
+fn decorate(sign: &str, digits: &str, base: u32) -> String {
+    match base {
+        2 => format!("{}0b{}", sign, digits),
+        8 => format!("{}0o{}", sign, digits),
+        16 => format!("{}0x{}", sign, digits),
+        10 => format!("{}{}", sign, digits),
+        _ => format!("{}{} (base {})", sign, digits, base),
+    }
+}
+
+fn integer_digits(mut n: u128, base: u32) -> String {
+    if n == 0 {
+        return "0".to_string();
+    }
+    let mut digits = Vec::new();
+    while n > 0 {
+        digits.push(DIGITS[(n % base as u128) as usize]);
+        n /= base as u128;
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// Formats an integer using its two's-complement bit pattern when negative.
+pub fn format_integer(value: i128, base: u32, word: WordSize) -> String {
+    if base == 10 {
+        return value.to_string();
+    }
+    decorate("", &integer_digits(word.to_unsigned(value), base), base)
+}
+
+/// Formats a number with a sign and, for fractions, as many digits as the
+/// `f64` can carry. Fractions that do not terminate within that precision
+/// end in `…`.
+pub fn format_number(value: f64, base: u32) -> String {
+    if base == 10 || !value.is_finite() || value.abs() >= 2f64.powi(127) {
+        return value.to_string();
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    let whole = value.abs().trunc();
+    let mut fraction = value.abs() - whole;
+    let mut digits = integer_digits(whole as u128, base);
+
+    if fraction > 0.0 {
+        let whole_bits = if whole >= 1.0 { whole.log2().floor() as i32 + 1 } else { 0 };
+        let fraction_bits = (f64::MANTISSA_DIGITS as i32 - whole_bits).max(1) as f64;
+        let max_digits = (fraction_bits / (base as f64).log2()).ceil() as usize;
+
+        digits.push('.');
+        for _ in 0..max_digits {
+            fraction *= base as f64;
+            let digit = fraction.trunc();
+            digits.push(DIGITS[digit as usize] as char);
+            fraction -= digit;
+            if fraction == 0.0 {
+                break;
+            }
+        }
+        if fraction > 0.0 {
+            digits.push('…');
+        }
+    }
+
+    decorate(sign, &digits, base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_integer() {
+        let word = WordSize::new(8, true).unwrap();
+        assert_eq!(format_integer(255, 16, WordSize::DEFAULT), "0xFF");
+        assert_eq!(format_integer(-1, 16, word), "0xFF");
+        assert_eq!(format_integer(-1, 10, word), "-1");
+        assert_eq!(format_integer(10, 2, word), "0b1010");
+        assert_eq!(format_integer(35, 36, word), "Z (base 36)");
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(0.625, 2), "0b0.101");
+        assert_eq!(format_number(-255.5, 16), "-0xFF.8");
+        assert_eq!(format_number(1.0 / 3.0, 3), "0.1 (base 3)");
+        assert!(format_number(0.1, 2).ends_with('…'));
+        assert_eq!(format_number(2.5, 10), "2.5");
+    }
+}
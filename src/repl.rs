@@ -1,3 +1,4 @@
+use crate::distributions::Distribution;
 use rustyline::{Helper, completion::{Completer, Pair}, highlight::Highlighter, hint::Hinter, validate::Validator};
 
 pub struct CalcHelper {
@@ -16,18 +17,79 @@ impl CalcHelper {
                 "asin".to_string(),
                 "acos".to_string(),
                 "atan".to_string(),
+                "atan2".to_string(),
+                "sec".to_string(),
+                "csc".to_string(),
+                "cot".to_string(),
+                "asec".to_string(),
+                "acsc".to_string(),
+                "acot".to_string(),
+                "sinh".to_string(),
+                "cosh".to_string(),
+                "tanh".to_string(),
+                "asinh".to_string(),
+                "acosh".to_string(),
+                "atanh".to_string(),
+                "hypot".to_string(),
                 "sqrt".to_string(),
                 "abs".to_string(),
                 "ln".to_string(),
                 "log".to_string(),
+                "log2".to_string(),
+                "log1p".to_string(),
                 "exp".to_string(),
+                "expm1".to_string(),
+                "cbrt".to_string(),
+                "root".to_string(),
+                "erf".to_string(),
+                "erfc".to_string(),
+                "erfinv".to_string(),
+                "besselj".to_string(),
+                "bessely".to_string(),
+                "besseli".to_string(),
+                "besselk".to_string(),
+                "zeta".to_string(),
+                "lambertw".to_string(),
+                "ellipk".to_string(),
+                "ellipe".to_string(),
+                "ei".to_string(),
+                "expint".to_string(),
                 "floor".to_string(),
                 "ceil".to_string(),
                 "round".to_string(),
                 "pow".to_string(),
                 "max".to_string(),
                 "min".to_string(),
-            ],
+                "today".to_string(),
+                "now".to_string(),
+                "date".to_string(),
+                "year".to_string(),
+                "month".to_string(),
+                "day".to_string(),
+                "weekday".to_string(),
+                "isoweek".to_string(),
+                "busdays".to_string(),
+                "workday".to_string(),
+                "float_bits".to_string(),
+                "double_bits".to_string(),
+                "from_bits".to_string(),
+                "ulp".to_string(),
+                "nextafter".to_string(),
+                "if".to_string(),
+                "table".to_string(),
+                "analyze".to_string(),
+                "intersect".to_string(),
+                "minimize".to_string(),
+                "maximize".to_string(),
+                "odesolve".to_string(),
+                "taylor".to_string(),
+                "plot_param".to_string(),
+                "plot_polar".to_string(),
+                "rand".to_string(),
+                "randint".to_string(),
+                "randn".to_string(),
+                "choice".to_string(),
+            ].into_iter().chain(Distribution::names()).collect(),
             commands: vec![
                 "help".to_string(),
                 "exit".to_string(),
@@ -37,6 +99,17 @@ impl CalcHelper {
                 "rad".to_string(),
                 "grad".to_string(),
                 "mode".to_string(),
+                "rates".to_string(),
+                "word".to_string(),
+                "seed".to_string(),
+                "plot".to_string(),
+                "export".to_string(),
+                "hex".to_string(),
+                "bin".to_string(),
+                "oct".to_string(),
+                "dec".to_string(),
+                "base".to_string(),
+                "ieee".to_string(),
             ],
             variables: vec![],
         }
@@ -53,6 +126,12 @@ impl CalcHelper {
     }
 }
 
+impl Default for CalcHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Completer for CalcHelper {
     type Candidate = Pair;
 
@@ -0,0 +1,235 @@
+//! SVG rendering of sampled curves, for charts in reports and other
+//! headless output.
+//!
+//! The chart has a light grid at round tick values, the coordinate axes
+//! where they are in view (a frame otherwise), one coloured polyline per
+//! series and a legend in the top-right corner.
+
+use std::fmt::Write;
+
+use crate::chart::{self, Series, View};
+
+/// Series colours, in order; after the last they repeat.
+pub const PALETTE: [&str; 8] = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    pub width: u32,
+    pub height: u32,
+    pub title: Option<String>,
+    /// The part of the plane to show; fitted to the series when `None`.
+    pub view: Option<View>,
+    /// Draw a unit the same length on both axes, for circles and other shapes.
+    pub equal_scale: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions { width: 800, height: 500, title: None, view: None, equal_scale: false }
+    }
+}
+
+const MARGIN_LEFT: f64 = 64.0;
+const MARGIN_RIGHT: f64 = 24.0;
+const MARGIN_TOP: f64 = 24.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+const TITLE_HEIGHT: f64 = 28.0;
+
+/// Draws `series` as an SVG document.
+pub fn render(series: &[Series], options: &SvgOptions) -> String {
+    let (width, height) = (options.width as f64, options.height as f64);
+    let top = MARGIN_TOP + if options.title.is_some() { TITLE_HEIGHT } else { 0.0 };
+    let area = Area {
+        left: MARGIN_LEFT,
+        top,
+        width: (width - MARGIN_LEFT - MARGIN_RIGHT).max(1.0),
+        height: (height - top - MARGIN_BOTTOM).max(1.0),
+    };
+    let mut view = options.view
+        .or_else(|| View::fit(series))
+        .unwrap_or(View { x: (-1.0, 1.0), y: (-1.0, 1.0) });
+    if options.equal_scale {
+        view = view.equal_scale(area.width, area.height);
+    }
+    let frame = Frame { area, view };
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="sans-serif" font-size="12">"#,
+        options.width, options.height, options.width, options.height);
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    if let Some(title) = &options.title {
+        let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" font-size="16">{}</text>"#,
+            width / 2.0, MARGIN_TOP + 6.0, escape(title));
+    }
+    frame.draw_grid(&mut svg);
+    frame.draw_axes(&mut svg);
+    for (index, s) in series.iter().enumerate() {
+        frame.draw_series(&mut svg, s, PALETTE[index % PALETTE.len()]);
+    }
+    frame.draw_legend(&mut svg, series);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The plotting area, in SVG units.
+#[derive(Debug, Clone, Copy)]
+struct Area {
+    left: f64,
+    top: f64,
+    width: f64,
+    height: f64,
+}
+
+struct Frame {
+    area: Area,
+    view: View,
+}
+
+impl Frame {
+    fn to_svg_x(&self, x: f64) -> f64 {
+        self.area.left + (x - self.view.x.0) / (self.view.x.1 - self.view.x.0) * self.area.width
+    }
+
+    fn to_svg_y(&self, y: f64) -> f64 {
+        self.area.top + (self.view.y.1 - y) / (self.view.y.1 - self.view.y.0) * self.area.height
+    }
+
+    fn ticks(&self) -> (Vec<f64>, f64, Vec<f64>, f64) {
+        let x_count = (self.area.width / 80.0).round().max(2.0) as usize;
+        let y_count = (self.area.height / 50.0).round().max(2.0) as usize;
+        let (x, y) = (self.view.x, self.view.y);
+        (
+            chart::nice_ticks(x.0, x.1, x_count), chart::tick_step(x.0, x.1, x_count),
+            chart::nice_ticks(y.0, y.1, y_count), chart::tick_step(y.0, y.1, y_count),
+        )
+    }
+
+    /// Grid lines and labels at the ticks, labels along the bottom and left.
+    fn draw_grid(&self, svg: &mut String) {
+        let Area { left, top, width, height } = self.area;
+        let (bottom, right) = (top + height, left + width);
+        let (x_ticks, x_step, y_ticks, y_step) = self.ticks();
+
+        svg.push_str("<g stroke=\"#e0e0e0\" stroke-width=\"1\">\n");
+        for &x in &x_ticks {
+            let sx = self.to_svg_x(x);
+            let _ = writeln!(svg, r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}"/>"#, sx, top, sx, bottom);
+        }
+        for &y in &y_ticks {
+            let sy = self.to_svg_y(y);
+            let _ = writeln!(svg, r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}"/>"#, left, sy, right, sy);
+        }
+        svg.push_str("</g>\n<g fill=\"#444\">\n");
+        for &x in &x_ticks {
+            let _ = writeln!(svg, r#"<text x="{:.2}" y="{:.2}" text-anchor="middle">{}</text>"#,
+                self.to_svg_x(x), bottom + 18.0, chart::format_tick(x, x_step));
+        }
+        for &y in &y_ticks {
+            let _ = writeln!(svg, r#"<text x="{:.2}" y="{:.2}" text-anchor="end" dominant-baseline="middle">{}</text>"#,
+                left - 8.0, self.to_svg_y(y), chart::format_tick(y, y_step));
+        }
+        svg.push_str("</g>\n");
+    }
+
+    /// The axes through the origin where they are in view, and a frame
+    /// around the plotting area.
+    fn draw_axes(&self, svg: &mut String) {
+        let Area { left, top, width, height } = self.area;
+        let _ = writeln!(svg, r##"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="none" stroke="#888"/>"##,
+            left, top, width, height);
+        svg.push_str("<g stroke=\"#333\" stroke-width=\"1.2\">\n");
+        if self.view.y.0 < 0.0 && self.view.y.1 > 0.0 {
+            let sy = self.to_svg_y(0.0);
+            let _ = writeln!(svg, r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}"/>"#, left, sy, left + width, sy);
+        }
+        if self.view.x.0 < 0.0 && self.view.x.1 > 0.0 {
+            let sx = self.to_svg_x(0.0);
+            let _ = writeln!(svg, r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}"/>"#, sx, top, sx, top + height);
+        }
+        svg.push_str("</g>\n");
+    }
+
+    fn draw_series(&self, svg: &mut String, series: &Series, color: &str) {
+        let _ = writeln!(svg, r#"<g fill="none" stroke="{}" stroke-width="2" stroke-linejoin="round">"#, color);
+        for segment in &series.curve.segments {
+            for part in self.view.clip(segment) {
+                let points: Vec<String> = part.iter()
+                    .map(|p| format!("{:.2},{:.2}", self.to_svg_x(p.x), self.to_svg_y(p.y)))
+                    .collect();
+                let _ = writeln!(svg, r#"<polyline points="{}"/>"#, points.join(" "));
+            }
+        }
+        svg.push_str("</g>\n");
+    }
+
+    fn draw_legend(&self, svg: &mut String, series: &[Series]) {
+        let labelled: Vec<(usize, &Series)> = series.iter().enumerate().filter(|(_, s)| !s.label.is_empty()).collect();
+        if labelled.is_empty() {
+            return;
+        }
+        let longest = labelled.iter().map(|(_, s)| s.label.chars().count()).max().unwrap_or(0);
+        let (row, box_width) = (18.0, 40.0 + 7.0 * longest as f64);
+        let x = self.area.left + self.area.width - box_width - 10.0;
+        let y = self.area.top + 10.0;
+        let _ = writeln!(svg, r##"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="white" fill-opacity="0.85" stroke="#bbb"/>"##,
+            x, y, box_width, row * labelled.len() as f64 + 8.0);
+        for (line, (index, s)) in labelled.iter().enumerate() {
+            let sy = y + 4.0 + row * (line as f64 + 0.5);
+            let color = PALETTE[index % PALETTE.len()];
+            let _ = writeln!(svg, r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-width="2"/>"#,
+                x + 8.0, sy, x + 28.0, sy, color);
+            let _ = writeln!(svg, r#"<text x="{:.2}" y="{:.2}" dominant-baseline="middle">{}</text>"#,
+                x + 34.0, sy, escape(&s.label));
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plot::{Curve, Point};
+
+    fn line(label: &str, points: &[(f64, f64)]) -> Series {
+        let points = points.iter().map(|&(x, y)| Point { x, y }).collect();
+        Series { label: label.to_string(), curve: Curve { segments: vec![points] } }
+    }
+
+    #[test]
+    fn test_render_structure() {
+        let series = [line("x < 1 & y", &[(-2.0, -1.0), (2.0, 1.0)]), line("flat", &[(-2.0, 0.5), (2.0, 0.5)])];
+        let svg = render(&series, &SvgOptions { title: Some("Two lines".to_string()), ..SvgOptions::default() });
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\" height=\"500\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(svg.contains(PALETTE[0]) && svg.contains(PALETTE[1]));
+        assert!(svg.contains(">Two lines</text>"));
+        // Labels are escaped.
+        assert!(svg.contains(">x &lt; 1 &amp; y</text>"));
+        // Tick labels at round numbers, with the decimals the 0.5 step needs.
+        assert!(svg.contains(">-2.0</text>") && !svg.contains(">-2</text>"));
+        assert!(svg.contains(">0.5</text>"));
+    }
+
+    #[test]
+    fn test_render_clips_to_view() {
+        let series = [line("", &[(0.0, 0.0), (1.0, 100.0), (2.0, 0.0)])];
+        let view = View { x: (0.0, 2.0), y: (0.0, 1.0) };
+        let svg = render(&series, &SvgOptions { view: Some(view), ..SvgOptions::default() });
+        // The spike leaves the view and comes back: two pieces, no legend.
+        assert_eq!(svg.matches("<polyline").count(), 2);
+        assert!(!svg.contains("fill-opacity"));
+        assert!(svg.lines().filter(|l| l.starts_with("<polyline")).all(|l| {
+            l.split('"').nth(1).unwrap().split([' ', ',']).all(|n| {
+                let n: f64 = n.parse().unwrap();
+                (0.0..=800.0).contains(&n)
+            })
+        }));
+    }
+}
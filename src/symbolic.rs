@@ -0,0 +1,664 @@
+//! Symbolic manipulation of expressions: derivatives, algebraic
+//! simplification and Taylor polynomials, as in `taylor(sin(x), x, 0, 7)`.
+//!
+//! Derivatives follow the rules for the arithmetic operators and the
+//! elementary functions, with functions defined in the session inlined.
+//! Trigonometric functions read their arguments in the context's angle mode
+//! (and the inverse ones answer in it), so in degrees `sin(x)` differentiates
+//! to `0.017453292519943295 * cos(x)`. Other variables are constants.
+//!
+//! The simplifier brings expressions to a sum of products of powers and
+//! collects like terms there, which keeps repeated derivatives from growing
+//! out of hand. It knows nothing about the functions themselves, so
+//! `sin(x) ^ 2 + cos(x) ^ 2` stays as it is.
+
+use crate::{
+    ast::{BinOp, Expr, Function, UnOp},
+    context::Context,
+    error::EvalError,
+    evaluator::Evaluator,
+    functions,
+    value::Value,
+};
+
+/// The highest order `taylor` expands to. `n!` is still exact in an `i128`
+/// well past this, but the derivatives themselves grow quickly.
+pub const MAX_ORDER: usize = 20;
+
+/// Derivatives larger than this many nodes are given up on, since each
+/// further one tends to be larger still.
+const MAX_SIZE: usize = 200_000;
+
+/// Products of two sums are multiplied out only while that makes at most
+/// this many terms.
+const MAX_EXPANSION: usize = 64;
+
+/// How deeply functions defined in the session may be inlined, which stops
+/// a recursive definition from inlining forever.
+const MAX_INLINING: usize = 32;
+
+/// The derivative of `expr` with respect to `variable`, simplified.
+pub fn differentiate(expr: &Expr, variable: &str, context: &Context) -> Result<Expr, EvalError> {
+    let derivative = Differentiator { variable, context, inlining: 0 }.derivative(expr)?;
+    Ok(simplify(&derivative))
+}
+
+/// The `order`-th Taylor polynomial of `expr` in `variable` about `center`:
+/// the sum of `f⁽ᵏ⁾(a) (x - a)ᵏ / k!` for `k` up to `order`. Coefficients
+/// that come out whole stay exact fractions, as in `x - x ^ 3 / 6`.
+pub fn taylor(expr: &Expr, variable: &str, center: f64, order: usize, evaluator: &mut Evaluator) -> Result<Expr, EvalError> {
+    if order > MAX_ORDER {
+        return Err(EvalError::InvalidArguments(format!("the order must be at most {}, got {}", MAX_ORDER, order)));
+    }
+    let offset = if center == 0.0 {
+        Expr::Variable(variable.to_string())
+    } else {
+        simplify(&binary(Expr::Variable(variable.to_string()), BinOp::Subtract, Expr::Number(center)))
+    };
+
+    let mut derivative = simplify(expr);
+    let mut polynomial: Option<Expr> = None;
+    let mut factorial: i128 = 1;
+    for k in 0..=order {
+        if k > 0 {
+            derivative = differentiate(&derivative, variable, evaluator.get_context())?;
+            if size(&derivative) > MAX_SIZE {
+                return Err(EvalError::MathError(format!(
+                    "the derivatives of {} grow too large past order {}", expr, k - 1
+                )));
+            }
+            factorial *= k as i128;
+        }
+        // Division by zero, domain errors and infinite values mean there is no
+        // expansion; anything else, like an undefined variable, is passed on.
+        let undefined = |reason: String| EvalError::MathError(format!(
+            "{} has no Taylor expansion at {} = {}: {} undefined there ({})",
+            expr, variable, center,
+            if k == 0 { "it is".to_string() } else { format!("derivative {} is", k) },
+            reason
+        ));
+        let value = match evaluator.eval_with(&[(variable, Value::Number(center))], &derivative)
+            .and_then(|value| value.into_plain().as_number())
+        {
+            Ok(value) if value.is_finite() => value,
+            Ok(value) => return Err(undefined(format!("the value is {}", value))),
+            Err(EvalError::DivisionByZero) => return Err(undefined("division by zero".to_string())),
+            Err(EvalError::MathError(message)) => return Err(undefined(message)),
+            Err(error) => return Err(error),
+        };
+        if value == 0.0 {
+            continue;
+        }
+
+        let power = match k {
+            0 => None,
+            1 => Some(offset.clone()),
+            _ => Some(binary(offset.clone(), BinOp::Power, Expr::Number(k as f64))),
+        };
+        let term = term(value.abs(), factorial, power);
+        polynomial = Some(match polynomial {
+            None if value < 0.0 => negate(term),
+            None => term,
+            Some(sum) => binary(sum, if value < 0.0 { BinOp::Subtract } else { BinOp::Add }, term),
+        });
+    }
+    Ok(polynomial.unwrap_or(Expr::Number(0.0)))
+}
+
+/// `magnitude / factorial` times `power`, as an exact fraction when the
+/// derivative is a whole number.
+fn term(magnitude: f64, factorial: i128, power: Option<Expr>) -> Expr {
+    let whole = magnitude.round();
+    if (magnitude - whole).abs() <= 1e-9 * whole && whole < 2f64.powi(53) {
+        let divisor = gcd(whole as i128, factorial);
+        let (numerator, denominator) = (whole as i128 / divisor, factorial / divisor);
+        let scaled = match power {
+            None => Expr::Number(numerator as f64),
+            Some(power) if numerator == 1 => power,
+            Some(power) => binary(Expr::Number(numerator as f64), BinOp::Multiply, power),
+        };
+        if denominator == 1 { scaled } else { binary(scaled, BinOp::Divide, Expr::Number(denominator as f64)) }
+    } else {
+        // The noise in the last digits would only clutter the polynomial.
+        let coefficient: f64 = format!("{:.14e}", magnitude / factorial as f64).parse().unwrap_or(magnitude);
+        match power {
+            None => Expr::Number(coefficient),
+            Some(power) if coefficient == 1.0 => power,
+            Some(power) => binary(Expr::Number(coefficient), BinOp::Multiply, power),
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// The number of nodes in `expr`.
+fn size(expr: &Expr) -> usize {
+    1 + match expr {
+        Expr::BinaryOp { left, right, .. } | Expr::Equation { left, right } => size(left) + size(right),
+        Expr::UnaryOp { expr, .. } | Expr::PostfixOp { expr, .. } => size(expr),
+        Expr::FunctionCall { args, .. } | Expr::List(args) => args.iter().map(size).sum(),
+        Expr::Piecewise { pieces, otherwise } => {
+            pieces.iter().map(|(value, condition)| size(value) + size(condition)).sum::<usize>()
+                + otherwise.as_deref().map_or(0, size)
+        },
+        _ => 0,
+    }
+}
+
+struct Differentiator<'a> {
+    variable: &'a str,
+    context: &'a Context,
+    inlining: usize,
+}
+
+impl Differentiator<'_> {
+    fn derivative(&mut self, expr: &Expr) -> Result<Expr, EvalError> {
+        if !self.depends(expr) {
+            return Ok(Expr::Number(0.0));
+        }
+        match expr {
+            Expr::Variable(_) => Ok(Expr::Number(1.0)),
+            Expr::BinaryOp { left, op, right } => self.binary_op(left, op, right),
+            Expr::UnaryOp { op: UnOp::Negate, expr } => Ok(negate(self.derivative(expr)?)),
+            Expr::UnaryOp { op: UnOp::Positive, expr } => self.derivative(expr),
+            Expr::PostfixOp { op: UnOp::Percent, expr } => {
+                Ok(binary(self.derivative(expr)?, BinOp::Divide, Expr::Number(100.0)))
+            },
+            Expr::FunctionCall { func, args } => self.function(func, args),
+            Expr::Piecewise { pieces, otherwise } => Ok(Expr::Piecewise {
+                pieces: pieces.iter()
+                    .map(|(value, condition)| Ok((self.derivative(value)?, condition.clone())))
+                    .collect::<Result<_, EvalError>>()?,
+                otherwise: match otherwise {
+                    Some(value) => Some(Box::new(self.derivative(value)?)),
+                    None => None,
+                },
+            }),
+            other => Err(cannot_differentiate(other)),
+        }
+    }
+
+    /// Whether `expr` depends on the variable, through the functions it
+    /// calls as well.
+    fn depends(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::Variable(name) => name == self.variable,
+            Expr::BinaryOp { left, right, .. } | Expr::Equation { left, right } => self.depends(left) || self.depends(right),
+            Expr::UnaryOp { expr, .. } | Expr::PostfixOp { expr, .. } | Expr::Conversion { expr, .. } => self.depends(expr),
+            Expr::Quantity { value, .. } | Expr::Money { value, .. } => self.depends(value),
+            // A session function may read the variable as a global, so
+            // only its arguments are looked at if it cannot be inlined.
+            Expr::FunctionCall { args, .. } | Expr::List(args) => args.iter().any(|arg| self.depends(arg)),
+            Expr::Piecewise { pieces, otherwise } => {
+                pieces.iter().any(|(value, condition)| self.depends(value) || self.depends(condition))
+                    || otherwise.as_deref().is_some_and(|value| self.depends(value))
+            },
+            _ => false,
+        }
+    }
+
+    fn binary_op(&mut self, left: &Expr, op: &BinOp, right: &Expr) -> Result<Expr, EvalError> {
+        let (u, v) = (left.clone(), right.clone());
+        match op {
+            BinOp::Add | BinOp::Subtract => Ok(binary(self.derivative(left)?, op.clone(), self.derivative(right)?)),
+            BinOp::Multiply => {
+                let (du, dv) = (self.derivative(left)?, self.derivative(right)?);
+                Ok(binary(binary(du, BinOp::Multiply, v), BinOp::Add, binary(u, BinOp::Multiply, dv)))
+            },
+            BinOp::Divide => {
+                let (du, dv) = (self.derivative(left)?, self.derivative(right)?);
+                let numerator = binary(binary(du, BinOp::Multiply, v.clone()), BinOp::Subtract, binary(u, BinOp::Multiply, dv));
+                Ok(binary(numerator, BinOp::Divide, binary(v, BinOp::Power, Expr::Number(2.0))))
+            },
+            BinOp::Power if !self.depends(right) => {
+                // n u^(n - 1) u'
+                let exponent = binary(v.clone(), BinOp::Subtract, Expr::Number(1.0));
+                let scaled = binary(v, BinOp::Multiply, binary(u, BinOp::Power, exponent));
+                Ok(binary(scaled, BinOp::Multiply, self.derivative(left)?))
+            },
+            BinOp::Power => {
+                // u^v (v' ln(u) + v u' / u), which is u^v ln(u) v' for a constant u.
+                let dv = self.derivative(right)?;
+                let mut rate = binary(dv, BinOp::Multiply, call(Function::Ln, u.clone()));
+                if self.depends(left) {
+                    let du = self.derivative(left)?;
+                    rate = binary(rate, BinOp::Add, binary(binary(v.clone(), BinOp::Multiply, du), BinOp::Divide, u.clone()));
+                }
+                Ok(binary(binary(u, BinOp::Power, v), BinOp::Multiply, rate))
+            },
+            op => Err(EvalError::MathError(format!("cannot differentiate the operator {}", op.symbol()))),
+        }
+    }
+
+    fn function(&mut self, func: &Function, args: &[Expr]) -> Result<Expr, EvalError> {
+        if let Function::User(name) = func {
+            return self.user_function(name, args);
+        }
+        let k = functions::to_radians(1.0, self.context.get_angle_mode());
+        // Radians per unit of angle, left out in radians.
+        let angle = |expr: Expr| if k == 1.0 { expr } else { binary(Expr::Number(k), BinOp::Multiply, expr) };
+        let per_angle = |expr: Expr| if k == 1.0 { expr } else { binary(expr, BinOp::Divide, Expr::Number(k)) };
+
+        let [u] = args else {
+            return self.two_argument_function(func, args);
+        };
+        let u = u.clone();
+        let square = |expr: Expr| binary(expr, BinOp::Power, Expr::Number(2.0));
+        let reciprocal = |expr: Expr| binary(Expr::Number(1.0), BinOp::Divide, expr);
+        let sqrt = |expr: Expr| call(Function::Sqrt, expr);
+        let one_minus_square = binary(Expr::Number(1.0), BinOp::Subtract, square(u.clone()));
+        let one_plus_square = binary(Expr::Number(1.0), BinOp::Add, square(u.clone()));
+
+        // The derivative of the outer function, at u.
+        let outer = match func {
+            Function::Sin => angle(call(Function::Cos, u)),
+            Function::Cos => negate(angle(call(Function::Sin, u))),
+            Function::Tan => angle(square(call(Function::Sec, u))),
+            Function::Sec => angle(binary(call(Function::Sec, u.clone()), BinOp::Multiply, call(Function::Tan, u))),
+            Function::Csc => negate(angle(binary(call(Function::Csc, u.clone()), BinOp::Multiply, call(Function::Cot, u)))),
+            Function::Cot => negate(angle(square(call(Function::Csc, u)))),
+            Function::Asin => per_angle(reciprocal(sqrt(one_minus_square))),
+            Function::Acos => negate(per_angle(reciprocal(sqrt(one_minus_square)))),
+            Function::Atan => per_angle(reciprocal(one_plus_square)),
+            Function::Acot => negate(per_angle(reciprocal(one_plus_square))),
+            Function::Asec | Function::Acsc => {
+                let root = sqrt(binary(square(u.clone()), BinOp::Subtract, Expr::Number(1.0)));
+                let rate = per_angle(reciprocal(binary(call(Function::Abs, u), BinOp::Multiply, root)));
+                if let Function::Asec = func { rate } else { negate(rate) }
+            },
+            Function::Sinh => call(Function::Cosh, u),
+            Function::Cosh => call(Function::Sinh, u),
+            Function::Tanh => reciprocal(square(call(Function::Cosh, u))),
+            Function::Asinh => reciprocal(sqrt(binary(square(u), BinOp::Add, Expr::Number(1.0)))),
+            Function::Acosh => reciprocal(sqrt(binary(square(u), BinOp::Subtract, Expr::Number(1.0)))),
+            Function::Atanh => reciprocal(one_minus_square),
+            Function::Ln => reciprocal(u),
+            Function::Log => reciprocal(binary(u, BinOp::Multiply, Expr::Number(std::f64::consts::LN_10))),
+            Function::Log2 => reciprocal(binary(u, BinOp::Multiply, Expr::Number(std::f64::consts::LN_2))),
+            Function::Log1p => reciprocal(binary(Expr::Number(1.0), BinOp::Add, u)),
+            Function::Exp | Function::Expm1 => call(Function::Exp, u),
+            Function::Sqrt => reciprocal(binary(Expr::Number(2.0), BinOp::Multiply, sqrt(u))),
+            Function::Cbrt => reciprocal(binary(Expr::Number(3.0), BinOp::Multiply, square(call(Function::Cbrt, u)))),
+            Function::Abs => binary(u.clone(), BinOp::Divide, call(Function::Abs, u)),
+            Function::Erf | Function::Erfc => {
+                let rate = binary(
+                    Expr::Number(std::f64::consts::FRAC_2_SQRT_PI),
+                    BinOp::Multiply,
+                    call(Function::Exp, negate(square(u))),
+                );
+                if let Function::Erf = func { rate } else { negate(rate) }
+            },
+            func => return Err(EvalError::MathError(format!("cannot differentiate {}", func.name()))),
+        };
+        Ok(binary(outer, BinOp::Multiply, self.derivative(&args[0])?))
+    }
+
+    fn two_argument_function(&mut self, func: &Function, args: &[Expr]) -> Result<Expr, EvalError> {
+        let [a, b] = args else {
+            return Err(EvalError::MathError(format!("cannot differentiate {} with {} argument(s)", func.name(), args.len())));
+        };
+        let (a, b) = (a.clone(), b.clone());
+        let rewritten = match func {
+            Function::Pow => binary(a, BinOp::Power, b),
+            Function::Root => binary(a, BinOp::Power, binary(Expr::Number(1.0), BinOp::Divide, b)),
+            Function::Log => binary(call(Function::Ln, a), BinOp::Divide, call(Function::Ln, b)),
+            Function::Hypot => call(Function::Sqrt, binary(
+                binary(a, BinOp::Power, Expr::Number(2.0)),
+                BinOp::Add,
+                binary(b, BinOp::Power, Expr::Number(2.0)),
+            )),
+            Function::Atan2 => {
+                // (x y' - y x') / (x² + y²) for atan2(y, x), in the angle mode.
+                let (dy, dx) = (self.derivative(&a)?, self.derivative(&b)?);
+                let numerator = binary(binary(b.clone(), BinOp::Multiply, dy), BinOp::Subtract, binary(a.clone(), BinOp::Multiply, dx));
+                let denominator = binary(
+                    binary(a, BinOp::Power, Expr::Number(2.0)),
+                    BinOp::Add,
+                    binary(b, BinOp::Power, Expr::Number(2.0)),
+                );
+                let rate = binary(numerator, BinOp::Divide, denominator);
+                let k = functions::to_radians(1.0, self.context.get_angle_mode());
+                return Ok(if k == 1.0 { rate } else { binary(rate, BinOp::Divide, Expr::Number(k)) });
+            },
+            func => return Err(EvalError::MathError(format!("cannot differentiate {}", func.name()))),
+        };
+        self.derivative(&rewritten)
+    }
+
+    /// Differentiates the body of a function defined in the session, with
+    /// the arguments put in place of its parameters.
+    fn user_function(&mut self, name: &str, args: &[Expr]) -> Result<Expr, EvalError> {
+        let function = self.context.get_function(name).ok_or_else(|| EvalError::UndefinedFunction(name.to_string()))?;
+        if function.params.len() != args.len() {
+            return Err(EvalError::InvalidArguments(format!(
+                "{} expects {} argument(s), got {}", name, function.params.len(), args.len()
+            )));
+        }
+        if self.inlining == MAX_INLINING {
+            return Err(EvalError::MathError(format!("cannot differentiate {}: it calls itself too deeply", name)));
+        }
+        let body = substitute(&function.body, &function.params, args);
+        self.inlining += 1;
+        let derivative = self.derivative(&body);
+        self.inlining -= 1;
+        derivative
+    }
+}
+
+fn cannot_differentiate(expr: &Expr) -> EvalError {
+    EvalError::MathError(format!("cannot differentiate {}", expr))
+}
+
+/// `expr` with each of `params` replaced by the matching argument.
+fn substitute(expr: &Expr, params: &[String], args: &[Expr]) -> Expr {
+    let sub = |e: &Expr| Box::new(substitute(e, params, args));
+    match expr {
+        Expr::Variable(name) => match params.iter().position(|param| param == name) {
+            Some(i) => args[i].clone(),
+            None => expr.clone(),
+        },
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp { left: sub(left), op: op.clone(), right: sub(right) },
+        Expr::UnaryOp { op, expr } => Expr::UnaryOp { op: op.clone(), expr: sub(expr) },
+        Expr::PostfixOp { expr, op } => Expr::PostfixOp { expr: sub(expr), op: op.clone() },
+        Expr::FunctionCall { func, args: inner } => Expr::FunctionCall {
+            func: func.clone(),
+            args: inner.iter().map(|arg| *sub(arg)).collect(),
+        },
+        Expr::List(items) => Expr::List(items.iter().map(|item| *sub(item)).collect()),
+        Expr::Piecewise { pieces, otherwise } => Expr::Piecewise {
+            pieces: pieces.iter().map(|(value, condition)| (*sub(value), *sub(condition))).collect(),
+            otherwise: otherwise.as_deref().map(sub),
+        },
+        Expr::Conversion { expr, target } => Expr::Conversion { expr: sub(expr), target: target.clone() },
+        Expr::Quantity { value, unit } => Expr::Quantity { value: sub(value), unit: unit.clone() },
+        Expr::Money { value, currency } => Expr::Money { value: sub(value), currency: currency.clone() },
+        other => other.clone(),
+    }
+}
+
+/// Brings `expr` to a normal form: a sum of terms, each a number times a
+/// product of powers, with like terms and like factors collected and
+/// products of short sums multiplied out, so `x * y + 2 * y * x` is
+/// `3 * x * y`. Terms and factors keep the order they first appear in.
+/// Anything else, like a function call, is simplified inside and then
+/// counts as a single factor.
+pub fn simplify(expr: &Expr) -> Expr {
+    from_sum(to_sum(expr))
+}
+
+/// `base ^ exponent` within a term, keyed by the text of `base` so that
+/// like factors can be found.
+#[derive(Clone)]
+struct Factor {
+    key: String,
+    base: Expr,
+    exponent: f64,
+}
+
+#[derive(Clone)]
+struct Term {
+    coefficient: f64,
+    factors: Vec<Factor>,
+}
+
+impl Term {
+    /// Whether the terms differ only in their coefficients.
+    fn like(&self, other: &Term) -> bool {
+        self.factors.len() == other.factors.len()
+            && self.factors.iter().all(|a| other.factors.iter().any(|b| a.key == b.key && a.exponent == b.exponent))
+    }
+
+    fn times(&self, other: &Term) -> Term {
+        let mut factors = self.factors.clone();
+        for factor in &other.factors {
+            match factors.iter().position(|f| f.key == factor.key) {
+                Some(i) => factors[i].exponent += factor.exponent,
+                None => factors.push(factor.clone()),
+            }
+        }
+        factors.retain(|f| f.exponent != 0.0);
+        Term { coefficient: self.coefficient * other.coefficient, factors }
+    }
+}
+
+/// A sum of terms, none of them zero.
+type Sum = Vec<Term>;
+
+fn to_sum(expr: &Expr) -> Sum {
+    match expr {
+        Expr::Number(n) => constant(*n),
+        Expr::Integer(i) => constant(*i as f64),
+        Expr::BinaryOp { left, op: BinOp::Add, right } => add(to_sum(left), to_sum(right)),
+        Expr::BinaryOp { left, op: BinOp::Subtract, right } => add(to_sum(left), scale(to_sum(right), -1.0)),
+        Expr::BinaryOp { left, op: BinOp::Multiply, right } => multiply(&to_sum(left), &to_sum(right)),
+        Expr::BinaryOp { left, op: BinOp::Divide, right } => multiply(&to_sum(left), &power(to_sum(right), -1.0)),
+        Expr::BinaryOp { left, op: BinOp::Power, right } => {
+            let (base, exponent) = (to_sum(left), to_sum(right));
+            match exponent.as_slice() {
+                [] => constant(1.0),
+                [Term { coefficient, factors }] if factors.is_empty() => power(base, *coefficient),
+                _ if matches!(base.as_slice(), [Term { coefficient: 1.0, factors }] if factors.is_empty()) => constant(1.0),
+                _ => factor(binary(from_sum(base), BinOp::Power, from_sum(exponent)), 1.0),
+            }
+        },
+        Expr::UnaryOp { op: UnOp::Negate, expr } => scale(to_sum(expr), -1.0),
+        Expr::UnaryOp { op: UnOp::Positive, expr } => to_sum(expr),
+        Expr::BinaryOp { left, op, right } => factor(binary(simplify(left), op.clone(), simplify(right)), 1.0),
+        Expr::UnaryOp { op, expr } => factor(Expr::UnaryOp { op: op.clone(), expr: Box::new(simplify(expr)) }, 1.0),
+        Expr::PostfixOp { expr, op } => factor(Expr::PostfixOp { expr: Box::new(simplify(expr)), op: op.clone() }, 1.0),
+        Expr::FunctionCall { func, args } => {
+            factor(Expr::FunctionCall { func: func.clone(), args: args.iter().map(simplify).collect() }, 1.0)
+        },
+        Expr::Piecewise { pieces, otherwise } => factor(Expr::Piecewise {
+            pieces: pieces.iter().map(|(value, condition)| (simplify(value), simplify(condition))).collect(),
+            otherwise: otherwise.as_deref().map(|value| Box::new(simplify(value))),
+        }, 1.0),
+        other => factor(other.clone(), 1.0),
+    }
+}
+
+fn constant(value: f64) -> Sum {
+    if value == 0.0 { Vec::new() } else { vec![Term { coefficient: value, factors: Vec::new() }] }
+}
+
+fn factor(base: Expr, exponent: f64) -> Sum {
+    vec![Term { coefficient: 1.0, factors: vec![Factor { key: base.to_string(), base, exponent }] }]
+}
+
+fn add(mut sum: Sum, other: Sum) -> Sum {
+    for term in other {
+        match sum.iter().position(|t| t.like(&term)) {
+            Some(i) => sum[i].coefficient += term.coefficient,
+            None => sum.push(term),
+        }
+    }
+    sum.retain(|t| t.coefficient != 0.0);
+    sum
+}
+
+fn scale(mut sum: Sum, factor: f64) -> Sum {
+    sum.iter_mut().for_each(|term| term.coefficient *= factor);
+    sum.retain(|t| t.coefficient != 0.0);
+    sum
+}
+
+/// The product of two sums, multiplied out unless both are long.
+fn multiply(a: &Sum, b: &Sum) -> Sum {
+    if a.len() > 1 && b.len() > 1 && a.len() * b.len() > MAX_EXPANSION {
+        return factor(binary(from_sum(a.clone()), BinOp::Multiply, from_sum(b.clone())), 1.0);
+    }
+    a.iter().flat_map(|x| b.iter().map(move |y| x.times(y))).fold(Vec::new(), |sum, term| add(sum, vec![term]))
+}
+
+/// `base ^ exponent`. Powers of products are only split up for whole
+/// exponents, since `(x ^ 2) ^ 0.5` is `abs(x)` rather than `x`. Powers of
+/// sums are left whole, so that they meet their like factors again after
+/// being differentiated.
+fn power(base: Sum, exponent: f64) -> Sum {
+    let whole = exponent.fract() == 0.0;
+    match base.as_slice() {
+        _ if exponent == 0.0 => constant(1.0),
+        [] if exponent > 0.0 => Vec::new(),
+        [] => factor(Expr::Number(0.0), exponent),
+        [term] if whole || (term.factors.is_empty() && term.coefficient > 0.0) => vec![Term {
+            coefficient: term.coefficient.powf(exponent),
+            factors: term.factors.iter().map(|f| Factor { exponent: f.exponent * exponent, ..f.clone() }).collect(),
+        }],
+        [Term { coefficient, factors }] if *coefficient == 1.0 && factors.len() == 1 && factors[0].exponent == 1.0 => {
+            vec![Term { coefficient: 1.0, factors: vec![Factor { exponent, ..factors[0].clone() }] }]
+        },
+        _ => factor(from_sum(base), exponent),
+    }
+}
+
+fn from_sum(sum: Sum) -> Expr {
+    let mut terms = sum.iter();
+    let Some(first) = terms.next() else {
+        return Expr::Number(0.0);
+    };
+    terms.fold(from_term(first, true), |expr, term| {
+        let op = if term.coefficient < 0.0 { BinOp::Subtract } else { BinOp::Add };
+        binary(expr, op, from_term(term, false))
+    })
+}
+
+/// `term` as a quotient of products. Only a term that leads its sum keeps
+/// its sign; the others leave it to the `+` or `-` before them.
+fn from_term(term: &Term, signed: bool) -> Expr {
+    let negative = signed && term.coefficient < 0.0;
+    let magnitude = term.coefficient.abs();
+    let powers = |positive: bool| -> Vec<Expr> {
+        term.factors.iter()
+            .filter(|f| (f.exponent > 0.0) == positive)
+            .map(|f| match f.exponent.abs() {
+                1.0 => f.base.clone(),
+                exponent => binary(f.base.clone(), BinOp::Power, Expr::Number(exponent)),
+            })
+            .collect()
+    };
+    let (mut numerator, denominator) = (powers(true), powers(false));
+    if magnitude != 1.0 || numerator.is_empty() {
+        numerator.insert(0, Expr::Number(if negative { -magnitude } else { magnitude }));
+    } else if negative {
+        numerator[0] = negate(numerator[0].clone());
+    }
+    let product = |factors: Vec<Expr>| factors.into_iter().reduce(|a, b| binary(a, BinOp::Multiply, b));
+    let numerator = product(numerator).unwrap_or(Expr::Number(1.0));
+    match product(denominator) {
+        Some(denominator) => binary(numerator, BinOp::Divide, denominator),
+        None => numerator,
+    }
+}
+
+/// `-expr`, cancelling a double negation and folding numbers.
+fn negate(expr: Expr) -> Expr {
+    match expr {
+        Expr::Number(n) => Expr::Number(-n),
+        Expr::UnaryOp { op: UnOp::Negate, expr } => *expr,
+        expr => Expr::UnaryOp { op: UnOp::Negate, expr: Box::new(expr) },
+    }
+}
+
+fn binary(left: Expr, op: BinOp, right: Expr) -> Expr {
+    Expr::BinaryOp { left: Box::new(left), op, right: Box::new(right) }
+}
+
+fn call(func: Function, arg: Expr) -> Expr {
+    Expr::FunctionCall { func, args: vec![arg] }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::AngleMode, lexer::Lexer, parser::Parser};
+
+    fn parse(input: &str) -> Expr {
+        Parser::new(Lexer::new(input).tokenize().unwrap()).parse().unwrap()
+    }
+
+    fn radians() -> Evaluator {
+        let mut evaluator = Evaluator::new();
+        evaluator.get_context_mut().set_angle_mode(AngleMode::Radians);
+        evaluator
+    }
+
+    fn derivative(input: &str, evaluator: &Evaluator) -> String {
+        differentiate(&parse(input), "x", evaluator.get_context()).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_differentiate() {
+        let mut evaluator = radians();
+        assert_eq!(derivative("x^3 + 2*x - 7", &evaluator), "3 * x ^ 2 + 2");
+        assert_eq!(derivative("sin(x)*cos(x)", &evaluator), "cos(x) ^ 2 - sin(x) ^ 2");
+        // -x^2 is (-x)^2 in this grammar.
+        assert_eq!(derivative("exp(-x^2)", &evaluator), "2 * exp(x ^ 2) * x");
+        assert_eq!(derivative("exp(-(x^2))", &evaluator), "-2 * exp(-(x ^ 2)) * x");
+        assert_eq!(derivative("ln(x)/x", &evaluator), "1 / x ^ 2 - ln(x) / x ^ 2");
+        assert_eq!(derivative("2^x", &evaluator), "2 ^ x * ln(2)");
+        assert_eq!(derivative("a*x", &evaluator), "a");
+
+        // Inlined session functions, and degrees.
+        evaluator.evaluate(&parse("f(t) = t^2 + 1")).unwrap();
+        assert_eq!(derivative("f(3*x)", &evaluator), "18 * x");
+        evaluator.get_context_mut().set_angle_mode(AngleMode::Degrees);
+        assert_eq!(derivative("sin(x)", &evaluator), "0.017453292519943295 * cos(x)");
+
+        evaluator.evaluate(&parse("g(t) = g(t) + 1")).unwrap();
+        let error = |input: &str| differentiate(&parse(input), "x", evaluator.get_context()).unwrap_err().to_string();
+        assert!(error("floor(x)").contains("cannot differentiate floor"));
+        assert!(error("x mod 2").contains("cannot differentiate the operator mod"));
+        assert!(error("g(x)").contains("calls itself"));
+    }
+
+    #[test]
+    fn test_simplify() {
+        for (input, simplified) in [
+            ("0 + x*1 - 0", "x"),
+            ("x + -y", "x - y"),
+            ("x - -2", "x + 2"),
+            ("(-x) * (-y)", "x * y"),
+            ("2 * (3 * x) * 4", "24 * x"),
+            ("x^1 + x^0 + 1^x", "x + 2"),
+            ("-(-x) / 1", "x"),
+            ("x*y + 2*y*x", "3 * x * y"),
+            ("(x + 1) * (x + 1) - 2*x", "x ^ 2 + 1"),
+            ("(x + 1)^2 * (x + 1)^-3", "1 / (x + 1)"),
+            ("x^2 / x * y / y^3", "x / y ^ 2"),
+            ("(x^2)^0.5", "(x ^ 2) ^ 0.5"),
+        ] {
+            assert_eq!(simplify(&parse(input)).to_string(), simplified, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_taylor() {
+        let mut evaluator = radians();
+        let mut expand = |input: &str, center: f64, order: usize| {
+            taylor(&parse(input), "x", center, order, &mut evaluator).map(|p| p.to_string())
+        };
+        assert_eq!(expand("sin(x)", 0.0, 7).unwrap(), "x - x ^ 3 / 6 + x ^ 5 / 120 - x ^ 7 / 5040");
+        assert_eq!(expand("exp(x)", 0.0, 3).unwrap(), "1 + x + x ^ 2 / 2 + x ^ 3 / 6");
+        assert_eq!(expand("cos(x)", 0.0, 4).unwrap(), "1 - x ^ 2 / 2 + x ^ 4 / 24");
+        assert_eq!(expand("1/(1 - x)", 0.0, 3).unwrap(), "1 + x + x ^ 2 + x ^ 3");
+        assert_eq!(expand("x^3", 2.0, 5).unwrap(), "8 + 12 * (x - 2) + 6 * (x - 2) ^ 2 + (x - 2) ^ 3");
+        assert_eq!(expand("ln(x)", 1.0, 3).unwrap(), "x - 1 - (x - 1) ^ 2 / 2 + (x - 1) ^ 3 / 3");
+        assert_eq!(expand("sqrt(x)", 4.0, 2).unwrap(), "2 + 0.25 * (x - 4) - 0.015625 * (x - 4) ^ 2");
+        assert_eq!(expand("-(x^2) + x^2", 0.0, 2).unwrap(), "0");
+        assert_eq!(expand("1 - cos(x)", 0.0, 2).unwrap(), "x ^ 2 / 2");
+        assert_eq!(expand("-sin(x)", 0.0, 3).unwrap(), "-x + x ^ 3 / 6");
+        assert_eq!(expand("-exp(x)", 0.0, 0).unwrap(), "-1");
+        // Derivatives that would grow without like terms collected.
+        assert_eq!(expand("1/(1 + x^2)", 0.0, 12).unwrap(), "1 - x ^ 2 + x ^ 4 - x ^ 6 + x ^ 8 - x ^ 10 + x ^ 12");
+        assert!(expand("1/(1 + x^2)", 0.0, MAX_ORDER).unwrap().ends_with("- x ^ 18 + x ^ 20"));
+        assert_eq!(expand("tan(x)", 0.0, 9).unwrap(), "x + x ^ 3 / 3 + 2 * x ^ 5 / 15 + 17 * x ^ 7 / 315 + 62 * x ^ 9 / 2835");
+
+        assert!(expand("ln(x)", 0.0, 2).unwrap_err().to_string().contains("undefined there"));
+        assert!(expand("sqrt(x)", 0.0, 2).unwrap_err().to_string().contains("derivative 1"));
+        assert!(expand("x", 0.0, MAX_ORDER + 1).is_err());
+        // Errors that aren't about the point itself are passed on as they are.
+        assert_eq!(expand("x * q", 0.0, 1).unwrap_err(), EvalError::UndefinedVariable("q".to_string()));
+    }
+}
@@ -0,0 +1,236 @@
+//! Tables of values: a function evaluated at evenly spaced points, as in
+//! `table(x^2 + 1, x, 0, 10, 0.5)`.
+//!
+//! Tables are shown with aligned columns and can be written out as CSV or
+//! Markdown. Cells where the function is undefined hold NaN; they print as
+//! `undefined`, are left empty in CSV and become `null` in JSON. Any table
+//! whose first column is the variable, such as a solution from `odesolve`,
+//! can also be plotted, each other column against the first.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    chart::Series,
+    compile::CompiledExpr,
+    error::EvalError,
+    evaluator::Evaluator,
+    plot::{Curve, Point},
+};
+
+/// Most rows a table may have, so a small step can't stall the session.
+pub const MAX_ROWS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Table {
+    pub columns: Vec<String>,
+    /// A label for each row, shown in a first column of its own; empty when
+    /// the rows aren't labelled.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    pub rows: Vec<Vec<f64>>,
+}
+
+/// Evaluates `f`, a function of one variable, from `from` to `to` in steps
+/// of `step`. The variable's column is headed by its name and the values'
+/// by `y`.
+pub fn tabulate(f: &CompiledExpr, evaluator: &mut Evaluator, from: f64, to: f64, step: f64) -> Result<Table, EvalError> {
+    let [variable] = f.variables() else {
+        return Err(EvalError::InvalidArguments("a table takes exactly one variable".to_string()));
+    };
+    let rows = sample_points(from, to, step)?
+        .into_iter()
+        .map(|x| vec![x, f.eval(evaluator, &[x])])
+        .collect();
+    Ok(Table { columns: vec![variable.clone(), "y".to_string()], labels: Vec::new(), rows })
+}
+
+/// `from`, `from + step` and so on as far as `to`, one for each row of a
+/// table.
+pub(crate) fn sample_points(from: f64, to: f64, step: f64) -> Result<Vec<f64>, EvalError> {
+    let count = ((to - from) / step + 1e-9).floor();
+    if !from.is_finite() || !to.is_finite() || !step.is_finite() || step == 0.0 || count < 0.0 {
+        return Err(EvalError::InvalidArguments(format!(
+            "cannot tabulate from {} to {} in steps of {}: expected finite bounds and a step towards the end", from, to, step
+        )));
+    }
+    if count >= MAX_ROWS as f64 {
+        return Err(EvalError::InvalidArguments(format!(
+            "a table from {} to {} in steps of {} has more than {} rows", from, to, step, MAX_ROWS
+        )));
+    }
+
+    Ok((0..=count as usize).map(|i| snap(from + i as f64 * step)).collect())
+}
+
+/// Rounds away the error that builds up in `from + i * step`, so that a
+/// step of 0.1 gives 0.3 rather than 0.30000000000000004.
+fn snap(x: f64) -> f64 {
+    format!("{:.12e}", x).parse().unwrap_or(x)
+}
+
+impl Table {
+    /// Comma-separated values with a header line; undefined cells are empty.
+    pub fn to_csv(&self) -> String {
+        let mut header: Vec<String> = self.columns.iter().map(|c| csv_field(c)).collect();
+        if !self.labels.is_empty() {
+            header.insert(0, String::new());
+        }
+        let mut csv = header.join(",");
+        csv.push('\n');
+        for (i, row) in self.rows.iter().enumerate() {
+            let mut cells: Vec<String> = row.iter()
+                .map(|&cell| if cell.is_nan() { String::new() } else { cell.to_string() })
+                .collect();
+            if let Some(label) = self.labels.get(i) {
+                cells.insert(0, csv_field(label));
+            }
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// A Markdown table with right-aligned columns of numbers.
+    pub fn to_markdown(&self) -> String {
+        let line = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        let labelled = !self.labels.is_empty();
+        let mut header: Vec<String> = self.columns.iter().map(|c| c.replace('|', "\\|")).collect();
+        let mut rule: Vec<String> = self.columns.iter().map(|_| "---:".to_string()).collect();
+        if labelled {
+            header.insert(0, String::new());
+            rule.insert(0, "---".to_string());
+        }
+        let mut markdown = line(header);
+        markdown.push_str(&line(rule));
+        for (i, row) in self.rows.iter().enumerate() {
+            let mut cells: Vec<String> = row.iter().map(|&cell| format_cell(cell)).collect();
+            if labelled {
+                cells.insert(0, self.labels.get(i).map_or(String::new(), |l| l.replace('|', "\\|")));
+            }
+            markdown.push_str(&line(cells));
+        }
+        markdown
+    }
+
+    /// Each column after the first against the first, as curves for a
+    /// chart, broken where a value is undefined.
+    pub fn to_series(&self) -> Vec<Series> {
+        self.columns.iter().enumerate().skip(1)
+            .map(|(i, column)| {
+                let points: Vec<Point> = self.rows.iter().map(|row| Point { x: row[0], y: row[i] }).collect();
+                Series { label: column.clone(), curve: Curve::from_points(&points) }
+            })
+            .collect()
+    }
+}
+
+fn format_cell(cell: f64) -> String {
+    if cell.is_nan() {
+        "undefined".to_string()
+    } else {
+        cell.to_string()
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Right-aligned columns of numbers under a ruled header, after the
+/// left-aligned row labels if there are any.
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let labelled = !self.labels.is_empty();
+        let mut header = self.columns.clone();
+        let mut cells: Vec<Vec<String>> = self.rows.iter()
+            .map(|row| row.iter().map(|&cell| format_cell(cell)).collect())
+            .collect();
+        if labelled {
+            header.insert(0, String::new());
+            for (i, row) in cells.iter_mut().enumerate() {
+                row.insert(0, self.labels.get(i).cloned().unwrap_or_default());
+            }
+        }
+        let widths: Vec<usize> = header.iter().enumerate()
+            .map(|(i, column)| {
+                cells.iter().map(|row| row[i].chars().count()).chain([column.chars().count()]).max().unwrap_or(0)
+            })
+            .collect();
+        let line = |row: &[String]| -> String {
+            let padded: Vec<String> = row.iter().zip(&widths).enumerate()
+                .map(|(i, (c, &w))| if labelled && i == 0 { format!("{:<w$}", c) } else { format!("{:>w$}", c) })
+                .collect();
+            padded.join(" │ ")
+        };
+
+        write!(f, "{}", line(&header))?;
+        let rule: Vec<String> = widths.iter().map(|&w| "─".repeat(w)).collect();
+        write!(f, "\n{}", rule.join("─┼─"))?;
+        for row in &cells {
+            write!(f, "\n{}", line(row))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn table(input: &str, from: f64, to: f64, step: f64) -> Result<Table, EvalError> {
+        let mut evaluator = Evaluator::new();
+        let expr = Parser::new(Lexer::new(input).tokenize().unwrap()).parse().unwrap();
+        let f = CompiledExpr::new(&expr, &["x"], &mut evaluator)?;
+        tabulate(&f, &mut evaluator, from, to, step)
+    }
+
+    #[test]
+    fn test_tabulate() {
+        let t = table("x^2 + 1", 0.0, 1.0, 0.1).unwrap();
+        assert_eq!(t.columns, vec!["x", "y"]);
+        assert_eq!(t.rows.len(), 11);
+        assert_eq!(t.rows[3][0], 0.3);
+        assert_eq!(t.rows[10], vec![1.0, 2.0]);
+
+        // Counting down, and an end the steps don't land on.
+        let down = table("x", 2.0, -1.5, -1.0).unwrap();
+        assert_eq!(down.rows.iter().map(|row| row[0]).collect::<Vec<_>>(), vec![2.0, 1.0, 0.0, -1.0]);
+
+        assert!(table("x", 0.0, 1.0, 0.0).is_err());
+        assert!(table("x", 0.0, 1.0, -0.5).is_err());
+        assert!(table("x", 0.0, 1.0, 1e-6).is_err());
+    }
+
+    #[test]
+    fn test_formats() {
+        let t = table("1 / x", -1.0, 1.0, 1.0).unwrap();
+        assert_eq!(t.to_string(), " x │         y\n───┼──────────\n-1 │        -1\n 0 │ undefined\n 1 │         1");
+        assert_eq!(t.to_csv(), "x,y\n-1,-1\n0,\n1,1\n");
+        assert_eq!(t.to_markdown(), "| x | y |\n| ---: | ---: |\n| -1 | -1 |\n| 0 | undefined |\n| 1 | 1 |\n");
+        assert_eq!(serde_json::to_string(&t).unwrap(), r#"{"columns":["x","y"],"rows":[[-1.0,-1.0],[0.0,null],[1.0,1.0]]}"#);
+
+        let labelled = Table { labels: vec!["low".to_string(), "middle".to_string(), "high".to_string()], ..t };
+        assert_eq!(labelled.to_string(), "       │  x │         y\n───────┼────┼──────────\nlow    │ -1 │        -1\nmiddle │  0 │ undefined\nhigh   │  1 │         1");
+        assert_eq!(labelled.to_csv(), ",x,y\nlow,-1,-1\nmiddle,0,\nhigh,1,1\n");
+        assert!(labelled.to_markdown().starts_with("|  | x | y |\n| --- | ---: | ---: |\n| low | -1 | -1 |\n"));
+    }
+
+    #[test]
+    fn test_to_series() {
+        let series = table("1 / x", -2.0, 2.0, 1.0).unwrap().to_series();
+        assert_eq!(series.len(), 1);
+        assert_eq!(series[0].label, "y");
+        // The undefined value at 0 splits the curve in two.
+        assert_eq!(series[0].curve.segments, vec![
+            vec![Point { x: -2.0, y: -0.5 }, Point { x: -1.0, y: -1.0 }],
+            vec![Point { x: 1.0, y: 1.0 }, Point { x: 2.0, y: 0.5 }],
+        ]);
+    }
+}
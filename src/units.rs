@@ -0,0 +1,681 @@
+//! Physical units and dimensional analysis.
+//!
+//! Quantities are stored in SI base units together with their dimension, so
+//! arithmetic only has to combine dimensions and check them when adding.
+//!
+//! Celsius and Fahrenheit are affine rather than multiplicative: `degC` and
+//! `degF` denote points on a temperature scale, while `ΔdegC` and `ΔdegF`
+//! denote temperature differences, and the two are tracked separately.
+
+use std::{fmt, ops::{Div, Mul}};
+
+use crate::error::EvalError;
+
+/// Symbols of the SI base units, in the order used by `Dimension`.
+const BASE_SYMBOLS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+/// Order in which base units are written when formatting (mass first, as in `kg*m^2/s^2`).
+const DISPLAY_ORDER: [usize; 7] = [1, 0, 2, 3, 4, 5, 6];
+
+/// Exponents of the seven SI base dimensions: length, mass, time, current,
+/// temperature, amount of substance and luminous intensity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dimension([i8; 7]);
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension([0, 0, 0, 0, 0, 0, 0]);
+    pub const LENGTH: Dimension = Dimension([1, 0, 0, 0, 0, 0, 0]);
+    pub const MASS: Dimension = Dimension([0, 1, 0, 0, 0, 0, 0]);
+    pub const TIME: Dimension = Dimension([0, 0, 1, 0, 0, 0, 0]);
+    pub const CURRENT: Dimension = Dimension([0, 0, 0, 1, 0, 0, 0]);
+    pub const TEMPERATURE: Dimension = Dimension([0, 0, 0, 0, 1, 0, 0]);
+    pub const AMOUNT: Dimension = Dimension([0, 0, 0, 0, 0, 1, 0]);
+    pub const LUMINOSITY: Dimension = Dimension([0, 0, 0, 0, 0, 0, 1]);
+
+    const fn new(exponents: [i8; 7]) -> Self {
+        Dimension(exponents)
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        *self == Dimension::NONE
+    }
+
+    /// Raises the dimension to a power, failing if any exponent would stop being an integer.
+    pub fn pow(self, exp: f64) -> Option<Dimension> {
+        let mut exps = self.0;
+        for e in exps.iter_mut() {
+            let scaled = *e as f64 * exp;
+            if (scaled - scaled.round()).abs() > 1e-9 {
+                return None;
+            }
+            *e = scaled.round() as i8;
+        }
+        Some(Dimension(exps))
+    }
+
+    /// Number of base-unit factors needed to write this dimension out.
+    fn complexity(&self) -> u32 {
+        self.0.iter().map(|e| e.unsigned_abs() as u32).sum()
+    }
+
+    /// Base-unit factors with positive and negative exponents, e.g. `(["kg", "m^2"], ["s^2"])`.
+    fn factors(&self) -> (Vec<String>, Vec<String>) {
+        let factor = |i: usize, exp: i8| {
+            if exp == 1 {
+                BASE_SYMBOLS[i].to_string()
+            } else {
+                format!("{}^{}", BASE_SYMBOLS[i], exp)
+            }
+        };
+
+        let numerator = DISPLAY_ORDER.iter()
+            .filter(|&&i| self.0[i] > 0)
+            .map(|&i| factor(i, self.0[i]))
+            .collect();
+        let denominator = DISPLAY_ORDER.iter()
+            .filter(|&&i| self.0[i] < 0)
+            .map(|&i| factor(i, -self.0[i]))
+            .collect();
+        (numerator, denominator)
+    }
+}
+
+impl Mul for Dimension {
+    type Output = Dimension;
+
+    // Multiplying quantities adds the exponents of their dimensions.
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, other: Dimension) -> Dimension {
+        let mut exps = self.0;
+        for (e, o) in exps.iter_mut().zip(other.0) {
+            *e += o;
+        }
+        Dimension(exps)
+    }
+}
+
+impl Div for Dimension {
+    type Output = Dimension;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, other: Dimension) -> Dimension {
+        let mut exps = self.0;
+        for (e, o) in exps.iter_mut().zip(other.0) {
+            *e -= o;
+        }
+        Dimension(exps)
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (numerator, denominator) = self.factors();
+
+        if numerator.is_empty() {
+            let negative: Vec<String> = DISPLAY_ORDER.iter()
+                .filter(|&&i| self.0[i] < 0)
+                .map(|&i| format!("{}^{}", BASE_SYMBOLS[i], self.0[i]))
+                .collect();
+            return write!(f, "{}", negative.join("*"));
+        }
+
+        write!(f, "{}", join_factors(&numerator.join("*"), &denominator))
+    }
+}
+
+fn join_factors(numerator: &str, denominator: &[String]) -> String {
+    match denominator.len() {
+        0 => numerator.to_string(),
+        1 => format!("{}/{}", numerator, denominator[0]),
+        _ => format!("{}/({})", numerator, denominator.join("*")),
+    }
+}
+
+/// A resolved unit: `si = value * factor + offset`, plus its dimension.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    pub name: String,
+    pub factor: f64,
+    pub offset: f64,
+    pub dim: Dimension,
+    pub temperature: Option<Temperature>,
+}
+
+/// Whether a temperature denotes a point on a scale or a difference between two points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Temperature {
+    Absolute,
+    Difference,
+}
+
+impl Unit {
+    /// Looks up a unit symbol, accepting SI prefixes on units that allow them (`km`, `mA`, `kWh`).
+    pub fn lookup(symbol: &str) -> Option<Unit> {
+        if let Some(def) = UNITS.iter().find(|u| u.symbol == symbol) {
+            return Some(def.to_unit(symbol, 1.0));
+        }
+
+        PREFIXES.iter()
+            .filter_map(|(prefix, scale)| {
+                let rest = symbol.strip_prefix(prefix)?;
+                UNITS.iter()
+                    .find(|u| u.prefixable && u.symbol == rest)
+                    .map(|def| def.to_unit(symbol, *scale))
+            })
+            .next()
+    }
+
+    /// Compound units are linear, so a temperature inside one (`J/degC`) counts as a difference.
+    pub fn mul(&self, other: &Unit) -> Unit {
+        Unit::linear(format!("{}*{}", self.name, other.name), self.factor * other.factor, self.dim * other.dim)
+    }
+
+    pub fn div(&self, other: &Unit) -> Unit {
+        Unit::linear(format!("{}/{}", self.name, other.name), self.factor / other.factor, self.dim / other.dim)
+    }
+
+    pub fn powi(&self, exp: i32) -> Unit {
+        Unit::linear(format!("{}^{}", self.name, exp), self.factor.powi(exp), self.dim.pow(exp as f64).unwrap_or(self.dim))
+    }
+
+    /// The difference unit matching an absolute temperature unit (`degC` → `ΔdegC`).
+    pub fn difference(&self) -> Unit {
+        match self.temperature {
+            Some(Temperature::Absolute) => Unit {
+                name: format!("Δ{}", self.name),
+                factor: self.factor,
+                offset: 0.0,
+                dim: self.dim,
+                temperature: Some(Temperature::Difference),
+            },
+            _ => self.clone(),
+        }
+    }
+
+    fn linear(name: String, factor: f64, dim: Dimension) -> Unit {
+        Unit { name, factor, offset: 0.0, dim, temperature: None }
+    }
+}
+
+struct UnitDef {
+    symbol: &'static str,
+    factor: f64,
+    offset: f64,
+    dim: Dimension,
+    prefixable: bool,
+    temperature: Option<Temperature>,
+}
+
+impl UnitDef {
+    fn to_unit(&self, name: &str, scale: f64) -> Unit {
+        Unit {
+            name: name.to_string(),
+            factor: self.factor * scale,
+            offset: self.offset,
+            dim: self.dim,
+            temperature: self.temperature,
+        }
+    }
+}
+
+const fn si(symbol: &'static str, factor: f64, dim: Dimension) -> UnitDef {
+    UnitDef { symbol, factor, offset: 0.0, dim, prefixable: true, temperature: None }
+}
+
+const fn other(symbol: &'static str, factor: f64, dim: Dimension) -> UnitDef {
+    UnitDef { symbol, factor, offset: 0.0, dim, prefixable: false, temperature: None }
+}
+
+const fn temperature(symbol: &'static str, factor: f64, offset: f64) -> UnitDef {
+    UnitDef { symbol, factor, offset, dim: Dimension::TEMPERATURE, prefixable: false, temperature: Some(Temperature::Absolute) }
+}
+
+const fn temperature_difference(symbol: &'static str, factor: f64) -> UnitDef {
+    UnitDef { symbol, factor, offset: 0.0, dim: Dimension::TEMPERATURE, prefixable: false, temperature: Some(Temperature::Difference) }
+}
+
+const VELOCITY: Dimension = Dimension::new([1, 0, -1, 0, 0, 0, 0]);
+const VOLUME: Dimension = Dimension::new([3, 0, 0, 0, 0, 0, 0]);
+const FREQUENCY: Dimension = Dimension::new([0, 0, -1, 0, 0, 0, 0]);
+const FORCE: Dimension = Dimension::new([1, 1, -2, 0, 0, 0, 0]);
+const ENERGY: Dimension = Dimension::new([2, 1, -2, 0, 0, 0, 0]);
+const POWER: Dimension = Dimension::new([2, 1, -3, 0, 0, 0, 0]);
+const PRESSURE: Dimension = Dimension::new([-1, 1, -2, 0, 0, 0, 0]);
+const CHARGE: Dimension = Dimension::new([0, 0, 1, 1, 0, 0, 0]);
+const VOLTAGE: Dimension = Dimension::new([2, 1, -3, -1, 0, 0, 0]);
+const RESISTANCE: Dimension = Dimension::new([2, 1, -3, -2, 0, 0, 0]);
+const CAPACITANCE: Dimension = Dimension::new([-2, -1, 4, 2, 0, 0, 0]);
+const MAGNETIC_FLUX: Dimension = Dimension::new([2, 1, -2, -1, 0, 0, 0]);
+const FLUX_DENSITY: Dimension = Dimension::new([0, 1, -2, -1, 0, 0, 0]);
+const INDUCTANCE: Dimension = Dimension::new([2, 1, -2, -2, 0, 0, 0]);
+
+static UNITS: &[UnitDef] = &[
+    // SI base units (the kilogram is prefixed from the gram)
+    si("m", 1.0, Dimension::LENGTH),
+    si("g", 1e-3, Dimension::MASS),
+    si("s", 1.0, Dimension::TIME),
+    si("A", 1.0, Dimension::CURRENT),
+    si("K", 1.0, Dimension::TEMPERATURE),
+    si("mol", 1.0, Dimension::AMOUNT),
+    si("cd", 1.0, Dimension::LUMINOSITY),
+    // SI derived units
+    si("Hz", 1.0, FREQUENCY),
+    si("N", 1.0, FORCE),
+    si("J", 1.0, ENERGY),
+    si("W", 1.0, POWER),
+    si("Pa", 1.0, PRESSURE),
+    si("C", 1.0, CHARGE),
+    si("V", 1.0, VOLTAGE),
+    si("ohm", 1.0, RESISTANCE),
+    si("Ω", 1.0, RESISTANCE),
+    si("F", 1.0, CAPACITANCE),
+    si("Wb", 1.0, MAGNETIC_FLUX),
+    si("T", 1.0, FLUX_DENSITY),
+    si("H", 1.0, INDUCTANCE),
+    // Accepted non-SI units
+    si("L", 1e-3, VOLUME),
+    si("l", 1e-3, VOLUME),
+    si("Wh", 3600.0, ENERGY),
+    si("eV", 1.602176634e-19, ENERGY),
+    si("cal", 4.184, ENERGY),
+    si("bar", 1e5, PRESSURE),
+    other("min", 60.0, Dimension::TIME),
+    other("h", 3600.0, Dimension::TIME),
+    other("d", 86400.0, Dimension::TIME),
+    other("week", 604800.0, Dimension::TIME),
+    other("yr", 31557600.0, Dimension::TIME),
+    other("au", 1.495978707e11, Dimension::LENGTH),
+    other("ly", 9.4607304725808e15, Dimension::LENGTH),
+    other("atm", 101325.0, PRESSURE),
+    other("mmHg", 133.322387415, PRESSURE),
+    // Temperature scales
+    temperature("degC", 1.0, 273.15),
+    temperature("degF", 5.0 / 9.0, 459.67 * 5.0 / 9.0),
+    temperature_difference("ΔdegC", 1.0),
+    temperature_difference("ΔdegF", 5.0 / 9.0),
+    temperature_difference("delta_degC", 1.0),
+    temperature_difference("delta_degF", 5.0 / 9.0),
+    other("degR", 5.0 / 9.0, Dimension::TEMPERATURE),
+    // Imperial and US customary units
+    other("in", 0.0254, Dimension::LENGTH),
+    other("ft", 0.3048, Dimension::LENGTH),
+    other("yd", 0.9144, Dimension::LENGTH),
+    other("mi", 1609.344, Dimension::LENGTH),
+    other("nmi", 1852.0, Dimension::LENGTH),
+    other("oz", 0.028349523125, Dimension::MASS),
+    other("lb", 0.45359237, Dimension::MASS),
+    other("gal", 3.785411784e-3, VOLUME),
+    other("mph", 0.44704, VELOCITY),
+    other("kn", 1852.0 / 3600.0, VELOCITY),
+    other("lbf", 4.4482216152605, FORCE),
+    other("psi", 6894.757293168361, PRESSURE),
+    other("BTU", 1055.05585262, ENERGY),
+    other("hp", 745.6998715822702, POWER),
+];
+
+/// SI prefixes; `da` comes first so it wins over `d`.
+static PREFIXES: &[(&str, f64)] = &[
+    ("da", 1e1),
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("μ", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+    ("z", 1e-21),
+    ("y", 1e-24),
+];
+
+/// Derived units that results are simplified to when their dimension matches.
+static DERIVED: &[(&str, Dimension)] = &[
+    ("N", FORCE),
+    ("J", ENERGY),
+    ("W", POWER),
+    ("Pa", PRESSURE),
+    ("C", CHARGE),
+    ("V", VOLTAGE),
+    ("Ω", RESISTANCE),
+    ("F", CAPACITANCE),
+    ("Wb", MAGNETIC_FLUX),
+    ("T", FLUX_DENSITY),
+    ("H", INDUCTANCE),
+];
+
+/// Chooses the shortest way of writing a dimension: an exact derived unit,
+/// a derived unit combined with base units when that saves at least two
+/// factors (`J/K`), or plain SI base units.
+pub fn simplified_name(dim: Dimension) -> String {
+    if let Some((name, _)) = DERIVED.iter().find(|(_, d)| *d == dim) {
+        return name.to_string();
+    }
+
+    let best = DERIVED.iter()
+        .map(|(name, d)| (name, dim / *d))
+        .min_by_key(|(_, rest)| rest.complexity());
+
+    if let Some((name, rest)) = best && rest.complexity() + 2 < dim.complexity() {
+        let (numerator, denominator) = rest.factors();
+        let numerator: Vec<String> = std::iter::once(name.to_string()).chain(numerator).collect();
+        return join_factors(&numerator.join("*"), &denominator);
+    }
+
+    dim.to_string()
+}
+
+/// A dimensioned value, kept in SI base units. `unit` remembers the unit the
+/// user wrote or converted to, and is only used for display. `temperature`
+/// is set for quantities that come from `degC`/`degF` or their differences;
+/// plain kelvins leave it unset and are compatible with both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub dim: Dimension,
+    pub unit: Option<Unit>,
+    pub temperature: Option<Temperature>,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: &Unit) -> Self {
+        Quantity {
+            value: value * unit.factor + unit.offset,
+            dim: unit.dim,
+            unit: Some(unit.clone()),
+            temperature: unit.temperature,
+        }
+    }
+
+    pub fn dimensionless(value: f64) -> Self {
+        Quantity { value, dim: Dimension::NONE, unit: None, temperature: None }
+    }
+
+    /// Magnitude of the quantity expressed in `unit`.
+    pub fn value_in(&self, unit: &Unit) -> f64 {
+        (self.value - unit.offset) / unit.factor
+    }
+
+    /// Re-expresses the quantity in `unit`, which must have the same dimension.
+    /// Absolute temperatures only convert to absolute scales, differences only to differences.
+    pub fn convert_to(&self, unit: &Unit) -> Result<Quantity, EvalError> {
+        if self.dim != unit.dim {
+            return Err(EvalError::UnitError(format!(
+                "cannot convert {} to {}", self.unit_name(), unit.name
+            )));
+        }
+
+        let temperature = match (self.temperature, unit.temperature) {
+            (Some(Temperature::Difference), Some(Temperature::Absolute)) => {
+                return Err(EvalError::UnitError(format!(
+                    "cannot convert a temperature difference to {}; use {}", unit.name, unit.difference().name
+                )));
+            },
+            (Some(Temperature::Absolute), Some(Temperature::Difference)) => {
+                return Err(EvalError::UnitError(format!(
+                    "cannot convert an absolute temperature to {}", unit.name
+                )));
+            },
+            (current, None) => current,
+            (_, target) => target,
+        };
+        // Allow for the rounding in the scale's offset, so -459.67 degF is 0 K.
+        if temperature == Some(Temperature::Absolute) && self.value < -1e-9 {
+            return Err(EvalError::UnitError(format!(
+                "{} is below absolute zero", self
+            )));
+        }
+
+        Ok(Quantity {
+            value: self.value,
+            dim: self.dim,
+            unit: Some(unit.clone()),
+            temperature,
+        })
+    }
+
+    /// Adding a difference to an absolute temperature moves it along the scale;
+    /// adding two absolute temperatures is meaningless.
+    pub fn checked_add(self, other: Quantity) -> Result<Quantity, EvalError> {
+        self.check_same_dimension(&other, "add")?;
+        let temperature = match (self.temperature, other.temperature) {
+            (Some(Temperature::Absolute), Some(Temperature::Absolute)) => {
+                return Err(EvalError::UnitError(format!(
+                    "cannot add two absolute temperatures ({} and {}); use a difference such as {}",
+                    self, other, self.unit_name_difference()
+                )));
+            },
+            (Some(Temperature::Absolute), _) | (_, Some(Temperature::Absolute)) => Some(Temperature::Absolute),
+            (a, b) => a.or(b),
+        };
+        let unit = match (&self.temperature, &other.temperature) {
+            (_, Some(Temperature::Absolute)) => other.unit.or(self.unit),
+            _ => self.unit.or(other.unit),
+        };
+        Ok(Quantity {
+            value: self.value + other.value,
+            dim: self.dim,
+            unit,
+            temperature,
+        })
+    }
+
+    /// Subtracting two absolute temperatures gives a difference; subtracting a
+    /// difference from an absolute temperature gives another absolute temperature.
+    pub fn checked_sub(self, other: Quantity) -> Result<Quantity, EvalError> {
+        self.check_same_dimension(&other, "subtract")?;
+        let (temperature, unit) = match (self.temperature, other.temperature) {
+            (Some(Temperature::Absolute), Some(Temperature::Absolute)) | (None, Some(Temperature::Absolute)) => {
+                (Some(Temperature::Difference), self.unit.or(other.unit).map(|u| u.difference()))
+            },
+            (Some(Temperature::Difference), Some(Temperature::Absolute)) => {
+                return Err(EvalError::UnitError(format!(
+                    "cannot subtract an absolute temperature ({}) from a difference ({})", other, self
+                )));
+            },
+            (a, b) => (a.or(b), self.unit.or(other.unit)),
+        };
+        Ok(Quantity {
+            value: self.value - other.value,
+            dim: self.dim,
+            unit,
+            temperature,
+        })
+    }
+
+    pub fn checked_mul(self, other: Quantity) -> Result<Quantity, EvalError> {
+        self.check_not_absolute("multiply")?;
+        other.check_not_absolute("multiply")?;
+        let unit = Quantity::scaled_unit(&self, &other);
+        let temperature = unit.as_ref().and(self.temperature.or(other.temperature));
+        Ok(Quantity {
+            value: self.value * other.value,
+            dim: self.dim * other.dim,
+            unit,
+            temperature,
+        })
+    }
+
+    pub fn checked_div(self, other: Quantity) -> Result<Quantity, EvalError> {
+        self.check_not_absolute("divide")?;
+        other.check_not_absolute("divide")?;
+        if other.value == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        let (unit, temperature) = if other.dim.is_dimensionless() {
+            (self.unit, self.temperature)
+        } else {
+            (None, None)
+        };
+        Ok(Quantity {
+            value: self.value / other.value,
+            dim: self.dim / other.dim,
+            unit,
+            temperature,
+        })
+    }
+
+    pub fn checked_pow(self, exp: f64) -> Result<Quantity, EvalError> {
+        self.check_not_absolute("raise")?;
+        let dim = self.dim.pow(exp).ok_or_else(|| EvalError::UnitError(format!(
+            "cannot raise {} to the power {}", self.unit_name(), exp
+        )))?;
+        Ok(Quantity {
+            value: self.value.powf(exp),
+            dim,
+            unit: None,
+            temperature: None,
+        })
+    }
+
+    /// Negates the magnitude as written, so `-(20 degC)` is -20 degC rather than -293.15 K.
+    pub fn negate(self) -> Quantity {
+        match (&self.unit, self.temperature) {
+            (Some(unit), Some(Temperature::Absolute)) => Quantity::new(-self.value_in(unit), unit),
+            _ => Quantity { value: -self.value, ..self },
+        }
+    }
+
+    /// Absolute value of the magnitude as written.
+    pub fn abs(self) -> Quantity {
+        let magnitude = match &self.unit {
+            Some(unit) => self.value_in(unit),
+            None => self.value,
+        };
+        if magnitude < 0.0 { self.negate() } else { self }
+    }
+
+    /// Name of the unit the quantity is displayed in.
+    pub fn unit_name(&self) -> String {
+        match &self.unit {
+            Some(unit) => unit.name.clone(),
+            None => simplified_name(self.dim),
+        }
+    }
+
+    fn unit_name_difference(&self) -> String {
+        match &self.unit {
+            Some(unit) => unit.difference().name,
+            None => self.unit_name(),
+        }
+    }
+
+    /// Describes the units for error messages.
+    fn describe(&self) -> String {
+        if self.dim.is_dimensionless() {
+            "a dimensionless number".to_string()
+        } else {
+            self.unit_name()
+        }
+    }
+
+    /// Scaling by a plain number keeps the display unit (`2 * 3 km` is `6 km`).
+    fn scaled_unit(a: &Quantity, b: &Quantity) -> Option<Unit> {
+        match (a.dim.is_dimensionless(), b.dim.is_dimensionless()) {
+            (true, false) => b.unit.clone(),
+            (false, true) => a.unit.clone(),
+            _ => None,
+        }
+    }
+
+    fn check_same_dimension(&self, other: &Quantity, verb: &str) -> Result<(), EvalError> {
+        if self.dim != other.dim {
+            return Err(EvalError::UnitError(format!(
+                "cannot {} {} and {}", verb, self.describe(), other.describe()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Products of `degC`/`degF` values depend on the arbitrary zero of the scale.
+    fn check_not_absolute(&self, verb: &str) -> Result<(), EvalError> {
+        if self.temperature == Some(Temperature::Absolute) {
+            return Err(EvalError::UnitError(format!(
+                "cannot {} the absolute temperature {}; convert it to K or use {}",
+                verb, self, self.unit_name_difference()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (value, digits) = match &self.unit {
+            Some(unit) => {
+                let value = self.value_in(unit);
+                // Taking away an offset leaves noise the size of the larger
+                // term, so `280 K to degC` keeps fewer digits of 6.85.
+                let terms = self.value.abs().max(unit.offset.abs()) / unit.factor.abs();
+                let lost = (terms.log10() - value.abs().log10()).floor();
+                (value, if lost > 0.0 { 15usize.saturating_sub(lost as usize).max(1) } else { 15 })
+            },
+            None => (self.value, 15),
+        };
+        write!(f, "{} {}", round_significant(value, digits), self.unit_name())
+    }
+}
+
+/// Rounds away the last digits of noise that unit factors and offsets
+/// introduce, so `20 degC to degF` shows 68 rather than 67.99999999999999.
+fn round_significant(value: f64, digits: usize) -> f64 {
+    if !value.is_finite() || value == 0.0 {
+        return value;
+    }
+    format!("{:.*e}", digits - 1, value).parse().unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_with_prefixes() {
+        let km = Unit::lookup("km").unwrap();
+        assert_eq!(km.factor, 1000.0);
+        assert_eq!(km.dim, Dimension::LENGTH);
+        assert_eq!(Unit::lookup("kg").unwrap().factor, 1.0);
+        assert_eq!(Unit::lookup("min").unwrap().factor, 60.0);
+        assert_eq!(Unit::lookup("mi").unwrap().factor, 1609.344);
+        assert!(Unit::lookup("kft").is_none());
+        assert!(Unit::lookup("x").is_none());
+    }
+
+    #[test]
+    fn test_simplified_names() {
+        assert_eq!(simplified_name(FORCE), "N");
+        assert_eq!(simplified_name(ENERGY), "J");
+        assert_eq!(simplified_name(VELOCITY), "m/s");
+        assert_eq!(simplified_name(Dimension::new([1, 0, -2, 0, 0, 0, 0])), "m/s^2");
+        assert_eq!(simplified_name(ENERGY / Dimension::TEMPERATURE), "J/K");
+        assert_eq!(simplified_name(FREQUENCY), "s^-1");
+    }
+
+    #[test]
+    fn test_add_requires_same_dimension() {
+        let m = Quantity::new(1.0, &Unit::lookup("m").unwrap());
+        let s = Quantity::new(1.0, &Unit::lookup("s").unwrap());
+        assert!(matches!(m.checked_add(s), Err(EvalError::UnitError(_))));
+    }
+
+    #[test]
+    fn test_temperature_scales() {
+        let deg_c = Unit::lookup("degC").unwrap();
+        let deg_f = Unit::lookup("degF").unwrap();
+        let boiling = Quantity::new(100.0, &deg_c);
+        assert!((boiling.value - 373.15).abs() < 1e-9);
+        assert!((boiling.value_in(&deg_f) - 212.0).abs() < 1e-9);
+        assert_eq!(deg_c.difference().name, "ΔdegC");
+        assert_eq!(deg_c.difference().offset, 0.0);
+    }
+}
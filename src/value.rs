@@ -0,0 +1,205 @@
+//! Values produced by the evaluator.
+
+use std::fmt;
+
+use crate::{ast::Expr, currency::Money, datetime::{self, DateTime}, error::EvalError, integer::WordSize, radix, table::Table, units::{Dimension, Quantity}};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    /// A fixed-width integer from programming mode, already wrapped to the word size.
+    Integer(i128),
+    /// Result of a comparison or logical operator. Booleans are not numbers:
+    /// `true + 1` is a type error rather than 2.
+    Bool(bool),
+    Quantity(Quantity),
+    Money(Money),
+    DateTime(DateTime),
+    /// The result of defining `f(x) = ...`, shown as its signature.
+    Function {
+        name: String,
+        params: Vec<String>,
+    },
+    List(Vec<Value>),
+    /// A table of values from `table(...)`, shown with aligned columns.
+    Table(Table),
+    /// A symbolic result, such as the polynomial from `taylor(...)`. Where
+    /// its variables are bound, as in a call to `p(x) = taylor(...)`, it is
+    /// evaluated there instead.
+    Expression(Expr),
+    /// A number shown in another base, from `255 in hex`; it computes like the number itself.
+    InBase {
+        value: Box<Value>,
+        base: u32,
+        word: WordSize,
+    },
+}
+
+impl Value {
+    /// Returns the plain number, failing for values that carry units.
+    pub fn as_number(&self) -> Result<f64, EvalError> {
+        match self {
+            Value::Number(n) => Ok(*n),
+            Value::Integer(i) => Ok(*i as f64),
+            Value::Bool(b) => Err(EvalError::TypeError(format!(
+                "expected a number, found {}", b
+            ))),
+            Value::Quantity(q) => Err(EvalError::UnitError(format!(
+                "expected a dimensionless number, found {}", q
+            ))),
+            Value::Money(m) => Err(EvalError::CurrencyError(format!(
+                "expected a plain number, found {}", m
+            ))),
+            Value::DateTime(dt) => Err(EvalError::TypeError(format!(
+                "expected a number, found the date {}", dt
+            ))),
+            Value::Function { .. } => Err(EvalError::TypeError(format!(
+                "expected a number, found the function {}", self
+            ))),
+            Value::List(_) => Err(EvalError::TypeError(format!(
+                "expected a number, found the list {}", self
+            ))),
+            Value::Table(_) => Err(EvalError::TypeError(
+                "expected a number, found a table".to_string()
+            )),
+            Value::Expression(expr) => Err(EvalError::TypeError(format!(
+                "expected a number, found the expression {}", expr
+            ))),
+            Value::InBase { value, .. } => value.as_number(),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, EvalError> {
+        match self {
+            Value::Bool(b) => Ok(*b),
+            Value::InBase { value, .. } => value.as_bool(),
+            other => Err(EvalError::TypeError(format!("expected true or false, found {}", other))),
+        }
+    }
+
+    pub fn as_datetime(&self) -> Result<DateTime, EvalError> {
+        match self {
+            Value::DateTime(dt) => Ok(*dt),
+            other => Err(EvalError::TypeError(format!("expected a date, found {}", other))),
+        }
+    }
+
+    /// ISO 8601 rendering for dates, timestamps and durations, if the value is one.
+    pub fn to_iso8601(&self) -> Option<String> {
+        match self {
+            Value::DateTime(dt) => Some(dt.to_string()),
+            Value::Quantity(q) if q.dim == Dimension::TIME => Some(datetime::iso_duration(q.value)),
+            _ => None,
+        }
+    }
+
+    pub fn into_quantity(self) -> Result<Quantity, EvalError> {
+        match self {
+            Value::Number(n) => Ok(Quantity::dimensionless(n)),
+            Value::Integer(i) => Ok(Quantity::dimensionless(i as f64)),
+            Value::Bool(b) => Err(EvalError::TypeError(format!(
+                "expected a number, found {}", b
+            ))),
+            Value::Quantity(q) => Ok(q),
+            Value::Money(m) => Err(EvalError::CurrencyError(format!(
+                "cannot combine {} with physical units", m
+            ))),
+            Value::DateTime(dt) => Err(EvalError::TypeError(format!(
+                "cannot combine the date {} with physical units", dt
+            ))),
+            Value::Function { .. } => Err(EvalError::TypeError(format!(
+                "cannot combine the function {} with physical units", self
+            ))),
+            Value::List(_) => Err(EvalError::TypeError(format!(
+                "cannot combine the list {} with physical units", self
+            ))),
+            Value::Table(_) => Err(EvalError::TypeError(
+                "tables cannot be used in arithmetic".to_string()
+            )),
+            Value::Expression(expr) => Err(EvalError::TypeError(format!(
+                "the expression {} cannot be used in arithmetic; define a function with it, as in p(x) = taylor(...)", expr
+            ))),
+            Value::InBase { value, .. } => value.into_quantity(),
+        }
+    }
+
+    /// Wraps a number or integer so it displays in the given base.
+    pub fn in_base(self, base: u32, word: WordSize) -> Result<Value, EvalError> {
+        match self.into_plain() {
+            Value::List(items) => items.into_iter()
+                .map(|item| item.in_base(base, word))
+                .collect::<Result<_, _>>()
+                .map(Value::List),
+            value @ (Value::Number(_) | Value::Integer(_)) => Ok(Value::InBase {
+                value: Box::new(value),
+                base: radix::check_base(base)?,
+                word,
+            }),
+            other => Err(EvalError::TypeError(format!(
+                "only numbers can be shown in another base, found {}", other
+            ))),
+        }
+    }
+
+    /// Strips the display base, leaving the value to compute with.
+    pub fn into_plain(self) -> Value {
+        match self {
+            Value::InBase { value, .. } => *value,
+            other => other,
+        }
+    }
+
+    /// Formats the value in the given output base; values other than numbers,
+    /// and numbers that already carry a base, are unaffected.
+    pub fn to_string_in(&self, base: u32, word: WordSize) -> String {
+        match self {
+            Value::Number(_) | Value::Integer(_) if base != 10 => self.clone()
+                .in_base(base, word)
+                .map_or_else(|_| self.to_string(), |v| v.to_string()),
+            Value::List(items) => format!(
+                "[{}]", items.iter().map(|item| item.to_string_in(base, word)).collect::<Vec<_>>().join(", ")
+            ),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Wraps a quantity, collapsing it to a plain number once its units cancel out.
+    pub fn from_quantity(q: Quantity) -> Value {
+        if q.dim.is_dimensionless() {
+            Value::Number(q.value)
+        } else {
+            Value::Quantity(q)
+        }
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Quantity(q) => write!(f, "{}", q),
+            Value::Money(m) => write!(f, "{}", m),
+            Value::DateTime(dt) => write!(f, "{}", dt),
+            Value::Function { name, params } => write!(f, "{}({})", name, params.join(", ")),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", items.join(", "))
+            },
+            Value::Table(table) => write!(f, "{}", table),
+            Value::Expression(expr) => write!(f, "{}", expr),
+            Value::InBase { value, base, word } => match **value {
+                Value::Integer(i) => write!(f, "{}", radix::format_integer(i, *base, *word)),
+                Value::Number(n) => write!(f, "{}", radix::format_number(n, *base)),
+                ref other => write!(f, "{}", other),
+            },
+        }
+    }
+}
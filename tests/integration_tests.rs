@@ -1,4 +1,4 @@
-use matheval::{lexer::Lexer, parser::Parser, evaluator::Evaluator};
+use matheval::{lexer::Lexer, parser::Parser, evaluator::Evaluator, context::AngleMode, plot::Curve};
 
 fn eval_expr(input: &str) -> Result<f64, String> {
     let mut lexer = Lexer::new(input);
@@ -247,4 +247,1178 @@ fn test_angle_mode_switching() {
     let expr_rad = "sin(PI / 6)";
     let result_rad = evaluator.eval(&Parser::new(Lexer::new(expr_rad).tokenize().unwrap()).parse().unwrap()).unwrap();
     assert!((result_rad - 0.5).abs() < 1e-10);
-}
\ No newline at end of file
+}
+
+// === TESTS FOR UNITS ===
+
+fn eval_display(input: &str) -> Result<String, String> {
+    let tokens = Lexer::new(input).tokenize().map_err(|e| format!("{}", e))?;
+    let ast = Parser::new(tokens).parse().map_err(|e| format!("{}", e))?;
+    let mut evaluator = Evaluator::new();
+    evaluator.evaluate(&ast).map(|v| v.to_string()).map_err(|e| format!("{}", e))
+}
+
+#[test]
+fn test_unit_literals() {
+    assert_eq!(eval_display("3 m/s").unwrap(), "3 m/s");
+    assert_eq!(eval_display("3 km + 500 m").unwrap(), "3.5 km");
+    assert_eq!(eval_display("2 * 3 kg").unwrap(), "6 kg");
+}
+
+#[test]
+fn test_derived_unit_simplification() {
+    assert_eq!(eval_display("9.81 m/s^2 * 2 kg").unwrap(), "19.62 N");
+    assert_eq!(eval_display("10 N * 2 m").unwrap(), "20 J");
+    assert_eq!(eval_display("100 J / 4 s").unwrap(), "25 W");
+    assert_eq!(eval_display("10 N / 2 m^2").unwrap(), "5 Pa");
+}
+
+#[test]
+fn test_dimension_mismatch() {
+    assert!(eval_display("1 m + 1 s").unwrap_err().contains("cannot add m and s"));
+    assert!(eval_display("1 m - 2").is_err());
+    assert!(eval_display("sin(3 m)").is_err());
+}
+
+#[test]
+fn test_unit_conversion() {
+    assert_eq!(eval_display("100 km/h to m/s").unwrap(), "27.7777777777778 m/s");
+    assert_eq!(eval_display("1 mi to ft").unwrap(), "5280 ft");
+    assert!(eval_display("1 m to s").is_err());
+}
+
+#[test]
+fn test_units_cancel_to_numbers() {
+    assert_eq!(eval_expr("3 km / 1 m").unwrap(), 3000.0);
+    assert_eq!(eval_expr("sqrt(9 m^2) / 1 m").unwrap(), 3.0);
+}
+
+// === TESTS FOR TEMPERATURES ===
+
+#[test]
+fn test_absolute_temperature_conversion() {
+    assert_eq!(eval_display("20 degC to degF").unwrap(), "68 degF");
+    assert_eq!(eval_display("-40 degC to degF").unwrap(), "-40 degF");
+    assert_eq!(eval_display("300 K to degC").unwrap(), "26.85 degC");
+    assert_eq!(eval_display("212 degF to K").unwrap(), "373.15 K");
+    // Taking away the offset leaves noise the size of 280, not of the result.
+    assert_eq!(eval_display("280 K to degC").unwrap(), "6.85 degC");
+    assert_eq!(eval_display("273.15000001 K to degC").unwrap(), "0.00000001 degC");
+    assert_eq!(eval_display("100 degF to degC").unwrap(), "37.7777777777778 degC");
+}
+
+#[test]
+fn test_temperature_differences() {
+    assert_eq!(eval_display("30 degC - 20 degC").unwrap(), "10 ΔdegC");
+    assert_eq!(eval_display("20 degC + 5 ΔdegC").unwrap(), "25 degC");
+    assert_eq!(eval_display("10 ΔdegC to ΔdegF").unwrap(), "18 ΔdegF");
+    assert_eq!(eval_display("4.18 J/(g*ΔdegC) * 10 g * 5 ΔdegC").unwrap(), "209 J");
+}
+
+#[test]
+fn test_absolute_temperature_errors() {
+    assert!(eval_display("20 degC + 20 degC").unwrap_err().contains("two absolute temperatures"));
+    assert!(eval_display("2 * 20 degC").is_err());
+    assert!(eval_display("10 ΔdegC to degF").is_err());
+    assert!(eval_display("20 degC to ΔdegF").is_err());
+
+    let error = eval_display("-300 degC to K").unwrap_err();
+    assert!(error.contains("-300 degC is below absolute zero"), "{}", error);
+    assert!(eval_display("-500 degF to degC").is_err());
+    assert!(eval_display("-1 K to degF").is_err());
+    assert_eq!(eval_display("-273.15 degC to K").unwrap(), "0 K");
+    assert_eq!(eval_display("-459.67 degF to K").unwrap(), "0 K");
+    // Differences may be as negative as they like.
+    assert_eq!(eval_display("-300 ΔdegC to ΔdegF").unwrap(), "-540 ΔdegF");
+}
+
+// === TESTS FOR CURRENCIES ===
+
+fn eval_with_rates(input: &str) -> Result<String, String> {
+    let table = matheval::currency::RateTable::from_json(
+        r#"{"base": "EUR", "timestamp": "2026-10-17", "rates": {"USD": 1.25, "GBP": 0.8}}"#
+    ).unwrap();
+    let mut evaluator = Evaluator::new();
+    evaluator.get_context_mut().set_rates(table);
+    let tokens = Lexer::new(input).tokenize().map_err(|e| format!("{}", e))?;
+    let ast = Parser::new(tokens).parse().map_err(|e| format!("{}", e))?;
+    evaluator.evaluate(&ast).map(|v| v.to_string()).map_err(|e| format!("{}", e))
+}
+
+#[test]
+fn test_currency_conversion() {
+    assert_eq!(eval_with_rates("100 USD to EUR").unwrap(), "80.00 EUR (rates as of 2026-10-17)");
+    assert_eq!(eval_with_rates("100 USD to GBP").unwrap(), "64.00 GBP (rates as of 2026-10-17)");
+    assert_eq!(eval_with_rates("10 EUR * 3").unwrap(), "30.00 EUR");
+    assert_eq!(eval_with_rates("10 EUR + 25 USD").unwrap(), "30.00 EUR (rates as of 2026-10-17)");
+}
+
+#[test]
+fn test_currency_errors() {
+    assert!(eval_with_rates("100 USD to JPY").unwrap_err().contains("no exchange rate for JPY"));
+    assert!(eval_with_rates("100 USD + 3").is_err());
+    assert!(eval_with_rates("100 USD * 2 m").is_err());
+    assert!(eval_display("100 USD to EUR").unwrap_err().contains("no exchange rate table"));
+}
+
+// === TESTS FOR DATES AND DURATIONS ===
+
+#[test]
+fn test_date_arithmetic() {
+    assert_eq!(eval_display("2026-12-25 - 2026-10-17").unwrap(), "69 d");
+    assert_eq!(eval_display("2026-10-17 + 3d 4h").unwrap(), "2026-10-20T04:00:00Z");
+    assert_eq!(eval_display("2026-10-17 + 2 week").unwrap(), "2026-10-31");
+    assert_eq!(eval_display("2026-10-17T08:00 + 90 min").unwrap(), "2026-10-17T09:30:00Z");
+    assert_eq!(eval_display("2026-10-17 + 14:30").unwrap(), "2026-10-17T14:30:00Z");
+    assert!(eval_display("2026-10-17 + 2026-10-18").is_err());
+    assert!(eval_display("2026-10-17 + 3 m").is_err());
+}
+
+#[test]
+fn test_now_and_today() {
+    let mut evaluator = Evaluator::new();
+    let expr = Parser::new(Lexer::new("now() + 90 min - now()").tokenize().unwrap()).parse().unwrap();
+    if let matheval::value::Value::Quantity(q) = evaluator.eval_value(&expr).unwrap() {
+        assert!((q.value - 5400.0).abs() < 1.0);
+    } else {
+        panic!("Expected a duration");
+    }
+    let today = Parser::new(Lexer::new("today()").tokenize().unwrap()).parse().unwrap();
+    assert!(evaluator.eval_value(&today).unwrap().to_string().len() == 10);
+}
+
+#[test]
+fn test_calendar_functions() {
+    assert_eq!(eval_expr("weekday(2026-10-17)").unwrap(), 6.0);
+    assert_eq!(eval_expr("isoweek(2026-10-17)").unwrap(), 42.0);
+    assert_eq!(eval_expr("busdays(2026-10-12, 2026-10-26)").unwrap(), 10.0);
+    assert_eq!(eval_display("workday(2026-10-16, 1)").unwrap(), "2026-10-19");
+    // Whole weeks are taken at once rather than walked day by day.
+    assert_eq!(eval_display("busdays(2026-10-16, workday(2026-10-16, 10^12))").unwrap(), "1000000000000");
+    assert!(eval_display("workday(2026-10-16, 10^300)").unwrap_err().contains("at most"));
+    assert_eq!(eval_display("date(2026, 10, 17)").unwrap(), "2026-10-17");
+    assert!(eval_display("date(2026, 2, 30)").is_err());
+    assert!(eval_display("weekday(5)").is_err());
+}
+
+#[test]
+fn test_iso8601_rendering() {
+    let tokens = Lexer::new("3d 4h").tokenize().unwrap();
+    let value = Evaluator::new().evaluate(&Parser::new(tokens).parse().unwrap()).unwrap();
+    assert_eq!(value.to_iso8601().unwrap(), "P3DT4H");
+}
+
+// === TESTS FOR PROGRAMMING MODE ===
+
+fn eval_with_word(input: &str, bits: u32, signed: bool) -> Result<String, String> {
+    let mut evaluator = Evaluator::new();
+    let word = matheval::integer::WordSize::new(bits, signed).unwrap();
+    evaluator.get_context_mut().set_word_size(word);
+    let tokens = Lexer::new(input).tokenize().map_err(|e| format!("{}", e))?;
+    let ast = Parser::new(tokens).parse().map_err(|e| format!("{}", e))?;
+    evaluator.evaluate(&ast).map(|v| v.to_string()).map_err(|e| format!("{}", e))
+}
+
+#[test]
+fn test_bitwise_operators() {
+    assert_eq!(eval_expr("12 & 10").unwrap(), 8.0);
+    assert_eq!(eval_expr("12 | 10").unwrap(), 14.0);
+    assert_eq!(eval_expr("12 xor 10").unwrap(), 6.0);
+    assert_eq!(eval_expr("~0").unwrap(), -1.0);
+    assert_eq!(eval_expr("1 << 4").unwrap(), 16.0);
+    assert_eq!(eval_expr("256 >> 4").unwrap(), 16.0);
+    // Shifts bind looser than arithmetic, and & binds tighter than |.
+    assert_eq!(eval_expr("1 << 2 + 1").unwrap(), 8.0);
+    assert_eq!(eval_expr("1 | 6 & 3").unwrap(), 3.0);
+    assert!(eval_expr("1.5 & 1").is_err());
+    assert!(eval_expr("1 << -1").is_err());
+}
+
+#[test]
+fn test_word_size_wrapping() {
+    assert_eq!(eval_with_word("~0", 8, false).unwrap(), "255");
+    assert_eq!(eval_with_word("(127 | 0) + 1", 8, true).unwrap(), "-128");
+    assert_eq!(eval_with_word("(255 | 0) + 1", 8, false).unwrap(), "0");
+    assert_eq!(eval_with_word("(16 | 0) * 16", 8, false).unwrap(), "0");
+    assert_eq!(eval_with_word("1 << 15", 16, true).unwrap(), "-32768");
+    assert_eq!(eval_with_word("-1 >> 1", 16, true).unwrap(), "-1");
+    assert_eq!(eval_with_word("65535 >> 15", 16, false).unwrap(), "1");
+    assert_eq!(eval_with_word("(1 << 63) - 1", 64, true).unwrap(), "9223372036854775807");
+    // Mixing integers with fractional numbers falls back to floating point.
+    assert_eq!(eval_with_word("(3 | 0) / 2", 32, true).unwrap(), "1.5");
+}
+
+// === TESTS FOR OUTPUT BASES ===
+
+#[test]
+fn test_radix_literals() {
+    assert_eq!(eval_expr("0xFF").unwrap(), 255.0);
+    assert_eq!(eval_expr("0b1010 + 0o17").unwrap(), 25.0);
+    assert_eq!(eval_expr("0xF0 & 0x3C").unwrap(), 48.0);
+    assert_eq!(eval_expr("0b0.1 * 3").unwrap(), 1.5);
+    assert_eq!(eval_with_word("0xFF", 8, true).unwrap(), "-1");
+}
+
+#[test]
+fn test_base_conversion() {
+    assert_eq!(eval_display("255 in hex").unwrap(), "0xFF");
+    assert_eq!(eval_display("255 to oct").unwrap(), "0o377");
+    assert_eq!(eval_display("0b1010 in dec").unwrap(), "10");
+    assert_eq!(eval_display("0.625 in bin").unwrap(), "0b0.101");
+    assert_eq!(eval_display("35 in base 36").unwrap(), "Z (base 36)");
+    assert_eq!(eval_expr("(255 in hex) + 1").unwrap(), 256.0);
+    assert!(eval_display("3 m in hex").is_err());
+    // `in` without a base is still the inch.
+    assert_eq!(eval_display("12 in to ft").unwrap(), "1 ft");
+}
+
+#[test]
+fn test_negative_numbers_in_bases() {
+    // Plain numbers keep their sign; integers show their two's-complement bits.
+    assert_eq!(eval_display("-255 in hex").unwrap(), "-0xFF");
+    assert_eq!(eval_with_word("-1 | 0 in hex", 16, true).unwrap(), "0xFFFF");
+    assert_eq!(eval_with_word("~5 in bin", 8, true).unwrap(), "0b11111010");
+    assert_eq!(eval_with_word("~5 in dec", 8, true).unwrap(), "-6");
+}
+
+#[test]
+fn test_output_base_setting() {
+    let mut evaluator = Evaluator::new();
+    evaluator.get_context_mut().set_output_base(16).unwrap();
+    let value = evaluator.evaluate(&Parser::new(Lexer::new("4096 + 0.5").tokenize().unwrap()).parse().unwrap()).unwrap();
+    assert_eq!(evaluator.get_context().format_value(&value), "0x1000.8");
+    assert!(evaluator.get_context_mut().set_output_base(37).is_err());
+}
+
+// === TESTS FOR IEEE-754 INSPECTION ===
+
+#[test]
+fn test_float_bits() {
+    assert_eq!(eval_display("double_bits(1) in hex").unwrap(), "0x3FF0000000000000");
+    assert_eq!(eval_display("float_bits(-2.5) in hex").unwrap(), "0xC0200000");
+    assert_eq!(eval_display("double_bits(-0) in hex").unwrap(), "0x8000000000000000");
+    assert_eq!(eval_expr("from_bits(0x3FF0000000000000)").unwrap(), 1.0);
+    assert_eq!(eval_expr("from_bits(0x3F800000, 32)").unwrap(), 1.0);
+    assert_eq!(eval_expr("from_bits(double_bits(PI))").unwrap(), std::f64::consts::PI);
+    assert!(eval_expr("from_bits(0x1FFFFFFFF, 32)").is_err());
+
+    // The patterns are integers of the word size like any other.
+    assert_eq!(eval_display("double_bits(-1) == double_bits(-1) + 0").unwrap(), "true");
+    assert_eq!(eval_display("double_bits(1) + 1 in hex").unwrap(), "0x3FF0000000000001");
+    assert_eq!(eval_expr("from_bits(double_bits(-1.5) + 0)").unwrap(), -1.5);
+
+    // A word narrower than the pattern cannot hold it.
+    let mut evaluator = Evaluator::new();
+    evaluator.get_context_mut().set_word_size(matheval::integer::WordSize::new(32, true).unwrap());
+    let mut eval = |input: &str| {
+        let ast = Parser::new(Lexer::new(input).tokenize().unwrap()).parse().unwrap();
+        evaluator.evaluate(&ast).map(|v| v.to_string()).map_err(|e| e.to_string())
+    };
+    assert_eq!(eval("float_bits(-2.5) + 0 == float_bits(-2.5)").unwrap(), "true");
+    assert_eq!(eval("from_bits(float_bits(-2.5), 32)").unwrap(), "-2.5");
+    assert!(eval("double_bits(1)").unwrap_err().contains("at least 64 bits"));
+    for width in ["4294967296", "32.5", "0"] {
+        let error = eval_display(&format!("from_bits(1, {})", width)).unwrap_err();
+        assert!(error.contains("must be 32 or 64"), "{}", error);
+    }
+}
+
+#[test]
+fn test_ulp_and_nextafter() {
+    assert_eq!(eval_expr("ulp(1)").unwrap(), f64::EPSILON);
+    assert_eq!(eval_expr("ulp(-1024)").unwrap(), 1024.0 * f64::EPSILON);
+    assert_eq!(eval_expr("nextafter(1, 2) - 1").unwrap(), f64::EPSILON);
+    assert!(eval_expr("nextafter(0, -1)").unwrap() < 0.0);
+    assert!(eval_expr("ulp(from_bits(0x7FF0000000000000))").is_err());
+}
+
+// === TESTS FOR COMPARISONS AND LOGIC ===
+
+#[test]
+fn test_comparisons() {
+    assert_eq!(eval_display("1 < 2").unwrap(), "true");
+    assert_eq!(eval_display("2 <= 2").unwrap(), "true");
+    assert_eq!(eval_display("3 > 4").unwrap(), "false");
+    assert_eq!(eval_display("1 + 2 != 3").unwrap(), "false");
+    assert_eq!(eval_display("0.1 + 0.2 == 0.3").unwrap(), "true");
+    assert_eq!(eval_display("1 m > 3 ft").unwrap(), "true");
+    assert_eq!(eval_display("2026-12-25 > 2026-10-17").unwrap(), "true");
+    assert_eq!(eval_display("9:30 < 14:00").unwrap(), "true");
+    assert_eq!(eval_display("(not false) == true").unwrap(), "true");
+    // Infinity is not within any tolerance of a finite number.
+    assert_eq!(eval_display("10^400 == 5").unwrap(), "false");
+    assert_eq!(eval_display("10^400 > 10^300").unwrap(), "true");
+    assert_eq!(eval_display("10^400 == 10^401").unwrap(), "true");
+    assert!(eval_display("1 m < 1 s").is_err());
+    assert!(eval_display("true < false").is_err());
+    assert!(eval_display("1 < 2 < 3").is_err());
+}
+
+#[test]
+fn test_logical_operators() {
+    assert_eq!(eval_display("1 < 2 and 2 < 3").unwrap(), "true");
+    assert_eq!(eval_display("1 > 2 or 2 > 3").unwrap(), "false");
+    assert_eq!(eval_display("not 1 > 2").unwrap(), "true");
+    // Booleans are not numbers.
+    assert!(eval_display("true + 1").is_err());
+    assert!(eval_display("1 and true").is_err());
+}
+
+#[test]
+fn test_short_circuit() {
+    // The right side would fail with an undefined variable or division by zero.
+    assert_eq!(eval_display("false and undefined_var > 0").unwrap(), "false");
+    assert_eq!(eval_display("true or 1/0 > 0").unwrap(), "true");
+    assert!(eval_display("true and 1/0 > 0").is_err());
+}
+
+#[test]
+fn test_assignment_vs_equality() {
+    let mut evaluator = Evaluator::new();
+    let mut run = |input: &str| {
+        let ast = Parser::new(Lexer::new(input).tokenize().unwrap()).parse().unwrap();
+        evaluator.evaluate(&ast).unwrap().to_string()
+    };
+    assert_eq!(run("x = 5"), "5");
+    assert_eq!(run("x == 5"), "true");
+    assert_eq!(run("ok = x == 6"), "false");
+}
+
+// === TESTS FOR FUNCTIONS AND CONDITIONALS ===
+
+fn eval_session(lines: &[&str]) -> Result<String, String> {
+    let mut evaluator = Evaluator::new();
+    let mut last = String::new();
+    for line in lines {
+        let tokens = Lexer::new(line).tokenize().map_err(|e| format!("{}", e))?;
+        let ast = Parser::new(tokens).parse().map_err(|e| format!("{}", e))?;
+        last = evaluator.evaluate(&ast).map(|v| v.to_string()).map_err(|e| format!("{}", e))?;
+    }
+    Ok(last)
+}
+
+#[test]
+fn test_if_is_lazy() {
+    assert_eq!(eval_expr("if(3 > 0, 3, -3)").unwrap(), 3.0);
+    assert_eq!(eval_expr("if(-3 > 0, -3, 3)").unwrap(), 3.0);
+    // The untaken branch would divide by zero or hit a domain error.
+    assert_eq!(eval_expr("if(true, 1, 1/0)").unwrap(), 1.0);
+    assert_eq!(eval_expr("if(false, sqrt(-1), 2)").unwrap(), 2.0);
+    assert!(eval_expr("if(1, 2, 3)").is_err());
+    assert!(eval_expr("if(true, 2)").is_err());
+}
+
+#[test]
+fn test_user_functions() {
+    assert_eq!(eval_session(&["f(x) = x^2 + 1"]).unwrap(), "f(x)");
+    assert_eq!(eval_session(&["f(x) = x^2 + 1", "f(3)"]).unwrap(), "10");
+    assert_eq!(eval_session(&["hyp(a, b) = sqrt(a^2 + b^2)", "hyp(3, 4)"]).unwrap(), "5");
+    // Parameters shadow variables only during the call.
+    assert_eq!(eval_session(&["x = 7", "f(x) = 2 * x", "f(1) + x"]).unwrap(), "9");
+    assert_eq!(eval_session(&["fact(n) = if(n <= 1, 1, n * fact(n - 1))", "fact(10)"]).unwrap(), "3628800");
+    assert!(eval_session(&["g(2)"]).is_err());
+    assert!(eval_session(&["f(x) = x", "f(1, 2)"]).is_err());
+    assert!(eval_session(&["loop(n) = loop(n + 1)", "loop(0)"]).is_err());
+    let error = eval_session(&["deep(n) = if(n > 100000, 0, 1 + {2 * (3 + deep(n + 1)) if true})", "deep(0)"]).unwrap_err();
+    assert!(error.contains("deep calls itself too deeply"), "{}", error);
+}
+
+#[test]
+fn test_deep_nesting() {
+    // Only calls count towards the depth limit, not the size of an expression.
+    let sum = vec!["1"; 10000].join(" + ");
+    assert_eq!(eval_expr(&sum).unwrap(), 10000.0);
+    let nested = format!("{}1{}", "(".repeat(2000), ")".repeat(2000));
+    assert_eq!(eval_expr(&nested).unwrap(), 1.0);
+    assert_eq!(eval_session(&["f(n) = if(n <= 0, 0, 1 + f(n - 1))", "f(1000)"]).unwrap(), "1000");
+}
+
+#[test]
+fn test_piecewise() {
+    let f = "f(x) = {x^2 if x < 0; x otherwise}";
+    assert_eq!(eval_session(&[f, "f(-3)"]).unwrap(), "9");
+    assert_eq!(eval_session(&[f, "f(4)"]).unwrap(), "4");
+    let sign = "sign(x) = {-1 if x < 0; 0 if x == 0; 1 if x > 0}";
+    assert_eq!(eval_session(&[sign, "sign(-2) + sign(0) + sign(5)"]).unwrap(), "0");
+    // Only the chosen piece is evaluated.
+    assert_eq!(eval_session(&["g(x) = {1/x if x != 0; 0 otherwise}", "g(0)"]).unwrap(), "0");
+    assert!(eval_session(&["h(x) = {1 if x > 0}", "h(-1)"]).is_err());
+}
+
+// === TESTS FOR MODULO, INTEGER DIVISION AND PERCENT ===
+
+#[test]
+fn test_modulo_and_remainder() {
+    assert_eq!(eval_expr("7 mod 3").unwrap(), 1.0);
+    assert_eq!(eval_expr("-7 mod 3").unwrap(), 2.0);
+    assert_eq!(eval_expr("-7 mod -3").unwrap(), 2.0);
+    assert_eq!(eval_expr("-7 rem 3").unwrap(), -1.0);
+    assert_eq!(eval_expr("7 rem -3").unwrap(), 1.0);
+    assert_eq!(eval_expr("5.5 mod 2").unwrap(), 1.5);
+    assert_eq!(eval_expr("2 + 7 mod 3 * 2").unwrap(), 4.0);
+    assert!(matches!(eval_expr("5 mod 0"), Err(e) if e.contains("Division by zero")));
+    assert_eq!(eval_display("370 cm mod 1 m").unwrap(), "70 cm");
+    assert!(eval_display("3 m mod 2 s").is_err());
+    assert_eq!(eval_with_word("0xFF mod 0x10", 64, true).unwrap(), "15");
+    assert_eq!(eval_with_word("-7 mod 3 in hex", 8, true).unwrap(), "0x2");
+}
+
+#[test]
+fn test_floor_division() {
+    assert_eq!(eval_expr("7 // 2").unwrap(), 3.0);
+    assert_eq!(eval_expr("-7 // 2").unwrap(), -4.0);
+    assert_eq!(eval_expr("7.5 // 2").unwrap(), 3.0);
+    assert_eq!(eval_expr("(7 // 2) * 2 + 7 mod 2").unwrap(), 7.0);
+    assert!(eval_expr("1 // 0").is_err());
+    assert_eq!(eval_with_word("-0x7 // 0x2", 64, true).unwrap(), "-4");
+    assert_eq!(eval_display("1 km // 300 m").unwrap(), "3");
+}
+
+#[test]
+fn test_percent() {
+    // On the right of + and -, a percentage is taken of the left side.
+    assert_eq!(eval_expr("200 + 15%").unwrap(), 230.0);
+    assert!((eval_expr("200 - 15%").unwrap() - 170.0).abs() < 1e-10);
+    assert!((eval_expr("200 + 15% + 10%").unwrap() - 253.0).abs() < 1e-10);
+    // Everywhere else it is a fraction.
+    assert!((eval_expr("50 * 10%").unwrap() - 5.0).abs() < 1e-10);
+    assert!((eval_expr("10% * 50").unwrap() - 5.0).abs() < 1e-10);
+    assert!((eval_expr("20 / 50%").unwrap() - 40.0).abs() < 1e-10);
+    assert!((eval_expr("100 ^ 50%").unwrap() - 10.0).abs() < 1e-10);
+    assert!((eval_expr("7 mod 50%").unwrap() - 0.0).abs() < 1e-10);
+    assert!((eval_expr("3 // 40%").unwrap() - 7.0).abs() < 1e-10);
+    assert!((eval_expr("15%").unwrap() - 0.15).abs() < 1e-10);
+    // Only a percentage on its own is relative; a larger term is just a number.
+    assert!((eval_expr("200 + 15% * 2").unwrap() - 200.3).abs() < 1e-10);
+    assert_eq!(eval_display("50% < 1").unwrap(), "true");
+    assert_eq!(eval_display("80 kg - 10%").unwrap(), "72 kg");
+    assert_eq!(eval_with_rates("100 USD + 8%").unwrap(), "108.00 USD");
+    assert!(eval_expr("10 % 3").is_err());
+}
+
+// === TESTS FOR RECIPROCAL, INVERSE AND HYPERBOLIC TRIGONOMETRY ===
+
+fn eval_in_mode(input: &str, mode: matheval::context::AngleMode) -> Result<f64, String> {
+    let mut evaluator = Evaluator::new();
+    evaluator.get_context_mut().set_angle_mode(mode);
+    let tokens = Lexer::new(input).tokenize().map_err(|e| e.to_string())?;
+    let expr = Parser::new(tokens).parse().map_err(|e| e.to_string())?;
+    evaluator.eval(&expr).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_reciprocal_trig() {
+    use matheval::context::AngleMode::*;
+    assert!((eval_in_mode("sec(60)", Degrees).unwrap() - 2.0).abs() < 1e-10);
+    assert!((eval_in_mode("csc(30)", Degrees).unwrap() - 2.0).abs() < 1e-10);
+    assert!((eval_in_mode("cot(45)", Degrees).unwrap() - 1.0).abs() < 1e-10);
+    assert!((eval_in_mode("sec(PI / 3)", Radians).unwrap() - 2.0).abs() < 1e-10);
+    assert!((eval_in_mode("cot(50)", Gradians).unwrap() - 1.0).abs() < 1e-10);
+    assert_eq!(eval_in_mode("cot(90)", Degrees).unwrap(), 0.0);
+    // Poles are domain errors rather than huge numbers.
+    assert!(eval_in_mode("sec(90)", Degrees).is_err());
+    assert!(eval_in_mode("sec(-270)", Degrees).is_err());
+    assert!(eval_in_mode("sec(PI / 2)", Radians).is_err());
+    assert!(eval_in_mode("csc(180)", Degrees).is_err());
+    assert!(eval_in_mode("cot(0)", Degrees).is_err());
+    assert!(eval_in_mode("csc(200)", Gradians).is_err());
+}
+
+#[test]
+fn test_inverse_trig() {
+    use matheval::context::AngleMode::*;
+    assert!((eval_in_mode("asec(2)", Degrees).unwrap() - 60.0).abs() < 1e-10);
+    assert!((eval_in_mode("acsc(2)", Degrees).unwrap() - 30.0).abs() < 1e-10);
+    assert!((eval_in_mode("acot(1)", Degrees).unwrap() - 45.0).abs() < 1e-10);
+    assert!((eval_in_mode("acot(0)", Radians).unwrap() - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+    assert!((eval_in_mode("acot(-1)", Gradians).unwrap() + 50.0).abs() < 1e-10);
+    assert!(eval_in_mode("asec(0.5)", Degrees).is_err());
+    assert!(eval_in_mode("acsc(-0.5)", Degrees).is_err());
+}
+
+#[test]
+fn test_atan2_and_hypot() {
+    use matheval::context::AngleMode::*;
+    assert!((eval_in_mode("atan2(1, -1)", Degrees).unwrap() - 135.0).abs() < 1e-10);
+    assert!((eval_in_mode("atan2(-1, -1)", Degrees).unwrap() + 135.0).abs() < 1e-10);
+    assert!((eval_in_mode("atan2(1, 0)", Radians).unwrap() - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+    assert!((eval_in_mode("atan2(0, -1)", Gradians).unwrap() - 200.0).abs() < 1e-10);
+    assert!(eval_in_mode("atan2(0, 0)", Degrees).is_err());
+    assert_eq!(eval_expr("hypot(3, 4)").unwrap(), 5.0);
+    assert!((eval_expr("hypot(10^200, 10^200) / 10^200").unwrap() - 2f64.sqrt()).abs() < 1e-12);
+    assert_eq!(eval_display("hypot(3 m, 400 cm)").unwrap(), "5 m");
+    assert!(eval_display("hypot(3 m, 4 s)").is_err());
+}
+
+#[test]
+fn test_hyperbolic() {
+    // Hyperbolic functions ignore the angle mode.
+    use matheval::context::AngleMode::*;
+    assert!((eval_in_mode("sinh(1)", Degrees).unwrap() - 1.1752011936438014).abs() < 1e-12);
+    assert!((eval_in_mode("cosh(1)", Radians).unwrap() - 1.5430806348152437).abs() < 1e-12);
+    assert!((eval_expr("tanh(0.5)").unwrap() - 0.46211715726000974).abs() < 1e-12);
+    assert!((eval_expr("asinh(sinh(2))").unwrap() - 2.0).abs() < 1e-12);
+    assert!((eval_expr("acosh(cosh(2))").unwrap() - 2.0).abs() < 1e-12);
+    assert!((eval_expr("atanh(tanh(0.3))").unwrap() - 0.3).abs() < 1e-12);
+    assert!((eval_expr("cosh(3)^2 - sinh(3)^2").unwrap() - 1.0).abs() < 1e-10);
+    assert!(eval_expr("acosh(0.5)").is_err());
+    assert!(eval_expr("atanh(1)").is_err());
+    assert!(eval_expr("atanh(-2)").is_err());
+}
+
+// === TESTS FOR LOGARITHMS AND ROOTS ===
+
+#[test]
+fn test_logarithm_bases() {
+    assert_eq!(eval_expr("log(1000)").unwrap(), 3.0);
+    assert_eq!(eval_expr("log(1000, 10)").unwrap(), 3.0);
+    assert_eq!(eval_expr("log(1024, 2)").unwrap(), 10.0);
+    assert!((eval_expr("log(81, 3)").unwrap() - 4.0).abs() < 1e-12);
+    assert!((eval_expr("log(0.25, 0.5)").unwrap() - 2.0).abs() < 1e-12);
+    assert_eq!(eval_expr("log2(8)").unwrap(), 3.0);
+    assert!(eval_expr("log(8, 1)").is_err());
+    assert!(eval_expr("log(8, -2)").is_err());
+    assert!(eval_expr("log(-8, 2)").is_err());
+    assert!(eval_expr("log2(0)").is_err());
+    assert!(eval_expr("log(1, 2, 3)").is_err());
+}
+
+#[test]
+fn test_roots() {
+    assert_eq!(eval_expr("cbrt(27)").unwrap(), 3.0);
+    assert_eq!(eval_expr("cbrt(-8)").unwrap(), -2.0);
+    assert_eq!(eval_expr("root(-8, 3)").unwrap(), -2.0);
+    assert!((eval_expr("root(-32, 5)").unwrap() + 2.0).abs() < 1e-12);
+    assert!((eval_expr("root(16, 4)").unwrap() - 2.0).abs() < 1e-12);
+    assert!((eval_expr("root(8, -3)").unwrap() - 0.5).abs() < 1e-12);
+    assert!(eval_expr("root(-16, 4)").is_err());
+    assert!(eval_expr("root(-2, 2.5)").is_err());
+    assert!(eval_expr("root(2, 0)").is_err());
+}
+
+#[test]
+fn test_expm1_and_log1p() {
+    // Both stay accurate where exp(x) - 1 and ln(1 + x) lose every digit.
+    let tiny = 2f64.powi(-60);
+    assert_eq!(eval_expr("expm1(2^-60)").unwrap(), tiny);
+    assert_eq!(eval_expr("log1p(2^-60)").unwrap(), tiny);
+    assert_eq!(eval_expr("exp(2^-60) - 1").unwrap(), 0.0);
+    assert!((eval_expr("expm1(1)").unwrap() - (std::f64::consts::E - 1.0)).abs() < 1e-12);
+    assert!((eval_expr("log1p(E - 1)").unwrap() - 1.0).abs() < 1e-12);
+    assert!(eval_expr("log1p(-1)").is_err());
+}
+
+// === TESTS FOR SPECIAL FUNCTIONS ===
+
+fn assert_close(input: &str, expected: f64, tolerance: f64) {
+    let result = eval_in_mode(input, matheval::context::AngleMode::Radians).unwrap();
+    let error = if expected == 0.0 { result.abs() } else { ((result - expected) / expected).abs() };
+    assert!(error <= tolerance, "{} = {}, expected {}", input, result, expected);
+}
+
+#[test]
+fn test_error_function() {
+    assert_close("erf(0.5)", 0.5204998778130465, 1e-14);
+    assert_close("erf(2)", 0.9953222650189527, 1e-14);
+    assert_close("erf(-1)", -0.8427007929497149, 1e-14);
+    assert_close("erfc(3)", 2.209049699858544e-5, 1e-13);
+    assert_close("erfc(5)", 1.537459794428035e-12, 1e-13);
+    assert_close("erfc(10)", 2.088487583762545e-45, 1e-13);
+    assert_close("erfinv(0.5)", 0.4769362762044699, 1e-14);
+    assert_close("erfinv(-0.9)", -1.1630871536766743, 1e-14);
+    assert_close("erfinv(0.999)", 2.3267537655135246, 1e-14);
+    assert_close("erf(erfinv(0.3))", 0.3, 1e-15);
+    assert!(eval_expr("erfinv(1.5)").is_err());
+}
+
+#[test]
+fn test_bessel_functions() {
+    assert_close("besselj(0, 1)", 0.7651976865579666, 1e-14);
+    assert_close("besselj(1, 2.5)", 0.4970941024642741, 1e-14);
+    assert_close("besselj(5, 10)", -0.23406152818679365, 1e-13);
+    assert_close("besselj(0, 50)", 0.05581232766925181, 1e-13);
+    assert_close("besselj(10, 1)", 2.630615123687453e-10, 1e-13);
+    assert_close("besselj(-3, 2)", -0.12894324947440206, 1e-13);
+    assert_close("besselj(1, -2)", -0.5767248077568734, 1e-14);
+    assert_close("bessely(0, 1)", 0.08825696421567696, 1e-13);
+    assert_close("bessely(1, 1)", -0.7812128213002887, 1e-13);
+    assert_close("bessely(10, 2)", -129184.54220803928, 1e-13);
+    assert_close("besseli(0, 1)", 1.2660658777520082, 1e-14);
+    assert_close("besselk(0, 1)", 0.42102443824070834, 1e-13);
+    assert_close("besselk(1, 2)", 0.13986588181652243, 1e-13);
+    assert!(eval_expr("bessely(0, 0)").is_err());
+    assert!(eval_expr("besselk(1, -1)").is_err());
+    assert!(eval_expr("besselj(0.5, -1)").is_err());
+}
+
+#[test]
+fn test_bessel_functions_of_large_order() {
+    // Orders above x, where the answer is far smaller than Schläfli's integrand.
+    assert_close("besselj(50, 15)", 6.106051949533876e-22, 1e-12);
+    assert_close("besselj(60, 18)", 5.640705630119267e-26, 1e-12);
+    assert_close("besselj(100, 60)", 4.78327440787810e-15, 1e-12);
+    assert_close("besselj(45.3, 20.7)", 2.344453640389668e-12, 1e-12);
+    assert_close("besselj(500, 400)", 1.364728110028963e-22, 1e-12);
+    assert_close("besselj(200, 50)", 2.138369004239117e-97, 1e-12);
+    // Just above x, where the recurrence hands over to the integral.
+    assert_close("besselj(100, 99.5)", 0.08694274930456151, 1e-12);
+    assert_close("besselj(1000, 950)", 4.639221366465356e-7, 1e-12);
+    assert_eq!(eval_expr("besselj(10^9, 10^5)").unwrap(), 0.0);
+}
+
+#[test]
+fn test_bessel_functions_of_half_integer_order() {
+    // Half-integer orders have closed forms, covering every method and the reflection formulas.
+    for x in ["0.5", "3", "15", "100"] {
+        assert_close(&format!("besselj(0.5, {x}) / (sqrt(2 / (PI * {x})) * sin({x}))"), 1.0, 1e-12);
+        assert_close(&format!("besselj(-0.5, {x}) / (sqrt(2 / (PI * {x})) * cos({x}))"), 1.0, 1e-12);
+        assert_close(&format!("bessely(0.5, {x}) / (sqrt(2 / (PI * {x})) * cos({x}))"), -1.0, 1e-12);
+        assert_close(&format!("besselk(0.5, {x}) / (sqrt(PI / (2 * {x})) * exp(-{x}))"), 1.0, 1e-12);
+    }
+    assert_close("besseli(0.5, 2) / (sqrt(2 / (PI * 2)) * sinh(2))", 1.0, 1e-13);
+    assert_close("besseli(-0.5, 2) / (sqrt(2 / (PI * 2)) * cosh(2))", 1.0, 1e-13);
+}
+
+#[test]
+fn test_zeta() {
+    assert_close("zeta(2)", std::f64::consts::PI.powi(2) / 6.0, 1e-14);
+    assert_close("zeta(3)", 1.2020569031595942, 1e-14);
+    assert_close("zeta(0.5)", -1.4603545088095868, 1e-14);
+    assert_close("zeta(0)", -0.5, 1e-14);
+    assert_close("zeta(-1)", -1.0 / 12.0, 1e-14);
+    assert_eq!(eval_expr("zeta(-2)").unwrap(), 0.0);
+    // Γ(1 - s) in the functional equation overflows here on its own.
+    assert_close("zeta(-171.5)", 4.739302330550545e172, 1e-12);
+    assert_close("zeta(-200.5)", -2.320000663352899e215, 1e-12);
+    assert!(eval_expr("zeta(1)").is_err());
+}
+
+#[test]
+fn test_lambert_w() {
+    assert_close("lambertw(1)", 0.5671432904097838, 1e-15);
+    assert_close("lambertw(E)", 1.0, 1e-15);
+    assert_close("lambertw(-0.2)", -0.2591711018190737, 1e-14);
+    assert_close("lambertw(-0.1, -1)", -3.577152063957297, 1e-14);
+    assert_eq!(eval_expr("lambertw(-1 / E)").unwrap(), -1.0);
+    assert_close("lambertw(1000) * exp(lambertw(1000))", 1000.0, 1e-14);
+    assert!(eval_expr("lambertw(-1)").is_err());
+    assert!(eval_expr("lambertw(1, -1)").is_err());
+    assert!(eval_expr("lambertw(1, 2)").is_err());
+}
+
+#[test]
+fn test_elliptic_and_exponential_integrals() {
+    assert_close("ellipk(0)", std::f64::consts::FRAC_PI_2, 1e-15);
+    assert_close("ellipk(0.5)", 1.854074677301372, 1e-14);
+    assert_close("ellipe(0.5)", 1.3506438810476755, 1e-14);
+    assert_close("ellipk(-1)", 1.31102877714606, 1e-14);
+    assert_eq!(eval_expr("ellipe(1)").unwrap(), 1.0);
+    assert!(eval_expr("ellipk(1)").is_err());
+    assert!(eval_expr("ellipe(1.5)").is_err());
+    assert_close("ei(1)", 1.8951178163559368, 1e-14);
+    assert_close("ei(10)", 2492.228976241878, 1e-14);
+    assert_close("ei(50)", 1.058563689713169e20, 1e-13);
+    assert_close("ei(-1)", -0.21938393439552029, 1e-14);
+    assert_close("expint(1)", 0.21938393439552029, 1e-14);
+    assert_close("expint(10)", 4.156968929685324e-6, 1e-13);
+    assert!(eval_expr("ei(0)").is_err());
+    assert!(eval_expr("expint(-1)").is_err());
+}
+
+// === TESTS FOR PROBABILITY DISTRIBUTIONS ===
+
+#[test]
+fn test_normal_distribution() {
+    assert_close("normpdf(0)", 0.3989422804014327, 1e-15);
+    assert_close("normcdf(1.96)", 0.9750021048517795, 1e-14);
+    assert_close("normcdf(-10)", 7.619853024160527e-24, 1e-12);
+    assert_close("norminv(0.975)", 1.959963984540054, 1e-14);
+    assert_close("norminv(10^-10)", -6.361340902404056, 1e-13);
+    assert_close("norminv(0.05, 100, 15)", 75.32719559572791, 1e-14);
+    assert_close("normcdf(norminv(0.3, 2, 3), 2, 3)", 0.3, 1e-14);
+    assert!(eval_expr("normpdf(0, 0, 0)").is_err());
+    assert!(eval_expr("normcdf(0, 1)").is_err());
+    assert!(eval_expr("norminv(1.5)").is_err());
+}
+
+#[test]
+fn test_student_chi_squared_and_f_distributions() {
+    assert_close("tpdf(0, 1)", 1.0 / std::f64::consts::PI, 1e-14);
+    assert_close("tcdf(2.228, 10)", 0.9749941140914442, 1e-12);
+    assert_close("tinv(0.975, 10)", 2.228138851986274, 1e-13);
+    assert_close("tinv(0.995, 1)", 63.65674116287399, 1e-12);
+    assert_close("tinv(0.025, 5)", -2.570581835636314, 1e-13);
+    assert_close("chi2inv(0.95, 1)", 3.841458820694124, 1e-13);
+    assert_close("chi2inv(0.95, 10)", 18.307038053275146, 1e-13);
+    assert_close("chi2inv(0.01, 2)", 0.0201006717070029, 1e-13);
+    assert_close("chi2cdf(2, 2)", 1.0 - (-1.0f64).exp(), 1e-14);
+    assert_close("finv(0.95, 5, 10)", 3.325834530413011, 1e-13);
+    assert_close("fcdf(3.325834530413011, 5, 10)", 0.95, 1e-13);
+    assert!(eval_expr("tcdf(1, 0)").is_err());
+    assert!(eval_expr("chi2pdf(1, -2)").is_err());
+}
+
+#[test]
+fn test_discrete_distributions() {
+    assert_close("binopdf(3, 10, 0.5)", 0.1171875, 1e-14);
+    assert_close("binocdf(3, 10, 0.5)", 0.171875, 1e-14);
+    assert_eq!(eval_expr("binopdf(2.5, 10, 0.5)").unwrap(), 0.0);
+    assert_eq!(eval_expr("binoinv(0.5, 10, 0.5)").unwrap(), 5.0);
+    assert_eq!(eval_expr("binoinv(binocdf(3, 10, 0.5), 10, 0.5)").unwrap(), 3.0);
+    assert_close("poisspdf(2, 3)", 0.22404180765538775, 1e-14);
+    assert_close("poisscdf(2, 3)", 0.42319008112684353, 1e-14);
+    assert_eq!(eval_expr("poissinv(0.95, 100)").unwrap(), 117.0);
+    assert!(eval_expr("binopdf(3, 10, 1.5)").is_err());
+    assert!(eval_expr("binopdf(3, 10.5, 0.5)").is_err());
+    assert!(eval_expr("poisspdf(1, 0)").is_err());
+}
+
+#[test]
+fn test_exponential_uniform_gamma_and_beta_distributions() {
+    assert_close("exppdf(1, 2)", 0.5 * (-0.5f64).exp(), 1e-15);
+    assert_close("expinv(0.5, 2)", 2.0 * 2f64.ln(), 1e-15);
+    assert_close("unifcdf(0.3, 0, 2)", 0.15, 1e-15);
+    assert_eq!(eval_expr("unifpdf(3, 0, 2)").unwrap(), 0.0);
+    assert_close("unifinv(0.25, -1, 1)", -0.5, 1e-15);
+    assert_close("gamcdf(2, 2, 1)", 0.5939941502901619, 1e-14);
+    assert_close("gaminv(0.5, 2, 1)", 1.678346990016661, 1e-13);
+    assert_close("gaminv(0.999, 0.5, 1)", 10.827566170662733 / 2.0, 1e-13);
+    assert_close("betapdf(0.5, 2, 2)", 1.5, 1e-14);
+    assert_close("betacdf(0.4, 2, 3)", 0.5248, 1e-13);
+    assert_close("betainv(0.01, 0.5, 0.5)", (0.005 * std::f64::consts::PI).sin().powi(2), 1e-12);
+    assert!(eval_expr("unifpdf(1, 2, 2)").is_err());
+    assert!(eval_expr("gampdf(1, 0, 1)").is_err());
+    assert!(eval_expr("betacdf(0.5, 1)").is_err());
+}
+
+// === TESTS FOR RANDOM NUMBERS ===
+
+fn eval_seeded(lines: &[&str], seed: u64) -> Vec<String> {
+    let mut evaluator = Evaluator::new();
+    evaluator.get_context_mut().seed(seed);
+    lines.iter()
+        .map(|line| {
+            let expr = Parser::new(Lexer::new(line).tokenize().unwrap()).parse().unwrap();
+            evaluator.evaluate(&expr).map_or_else(|e| e.to_string(), |v| v.to_string())
+        })
+        .collect()
+}
+
+#[test]
+fn test_seeded_random_numbers_repeat() {
+    let lines = ["rand()", "randint(1, 6)", "randn()", "choice([10, 20, 30])", "normrnd(5, 2)", "binornd(10, 0.3)"];
+    let first = eval_seeded(&lines, 42);
+    assert_eq!(first, eval_seeded(&lines, 42));
+    assert_ne!(first, eval_seeded(&lines, 43));
+    // Each call draws a new number.
+    let draws = eval_seeded(&["rand()", "rand()"], 1);
+    assert_ne!(draws[0], draws[1]);
+}
+
+#[test]
+fn test_random_ranges() {
+    let rolls = eval_seeded(&["randint(-2, 2)"; 200], 7);
+    assert!(rolls.iter().all(|r| ["-2", "-1", "0", "1", "2"].contains(&r.as_str())));
+    assert!(["-2", "2"].iter().all(|edge| rolls.iter().any(|r| r == edge)));
+    let picks = eval_seeded(&["choice([1, 2 + 3, sqrt(16)])"; 50], 7);
+    assert!(picks.iter().all(|p| ["1", "5", "4"].contains(&p.as_str())));
+    for draw in eval_seeded(&["rand()", "unifrnd(2, 3)", "exprnd(1)", "betarnd(2, 5)", "poissrnd(4)"], 3) {
+        assert!(draw.parse::<f64>().is_ok(), "{}", draw);
+    }
+    let samples: Vec<f64> = eval_seeded(&["randn(100, 15)"; 2000], 11)
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    assert!((mean - 100.0).abs() < 1.5, "mean {}", mean);
+}
+
+#[test]
+fn test_random_errors() {
+    assert!(eval_expr("randint(3, 1)").is_err());
+    assert!(eval_expr("randint(1.5, 3)").is_err());
+    assert!(eval_expr("rand(1)").is_err());
+    assert!(eval_expr("randn(1)").is_err());
+    assert!(eval_expr("choice([])").is_err());
+    assert!(eval_expr("choice(3)").is_err());
+    assert!(eval_expr("normrnd(0, -1)").is_err());
+    assert!(eval_display("[1, 2] + 1").is_err());
+    assert_eq!(eval_display("[0xFF, 16] in hex").unwrap(), "[0xFF, 0x10]");
+}
+
+// === TESTS FOR FUNCTION SAMPLING ===
+
+fn sample(input: &str, from: f64, to: f64, mode: AngleMode) -> Result<Curve, String> {
+    use matheval::{compile::CompiledExpr, plot::{sample_function, SampleOptions}};
+
+    let tokens = Lexer::new(input).tokenize().map_err(|e| e.to_string())?;
+    let ast = Parser::new(tokens).parse().map_err(|e| e.to_string())?;
+    let mut evaluator = Evaluator::new();
+    evaluator.get_context_mut().set_angle_mode(mode);
+    let f = CompiledExpr::new(&ast, &["x"], &mut evaluator).map_err(|e| e.to_string())?;
+    sample_function(&f, &mut evaluator, from, to, &SampleOptions::default()).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_sampling_tan_splits_at_poles() {
+    // In degrees tan has poles at 90 and 270.
+    let curve = sample("tan(x)", 0.0, 360.0, AngleMode::Degrees).unwrap();
+    assert_eq!(curve.segments.len(), 3);
+    assert!(curve.segments[0].iter().all(|p| p.x < 90.0 && p.y >= 0.0));
+    assert!(curve.segments[1].iter().all(|p| p.x > 90.0 && p.x < 270.0));
+    assert!(curve.segments[2].iter().all(|p| p.x > 270.0));
+    // The pieces reach close to the asymptote.
+    assert!(curve.segments[0].last().unwrap().y > 1e6);
+    assert!(curve.segments[1][0].y < -1e6);
+
+    let radians = sample("tan(x)", -std::f64::consts::PI, std::f64::consts::PI, AngleMode::Radians).unwrap();
+    assert_eq!(radians.segments.len(), 3);
+}
+
+#[test]
+fn test_sampling_gaps_and_jumps() {
+    let curve = sample("ln(x)", -5.0, 5.0, AngleMode::Radians).unwrap();
+    assert_eq!(curve.segments.len(), 1);
+    assert!(curve.segments[0][0].x < 1e-2);
+
+    let steps = sample("x // 1", -2.5, 2.5, AngleMode::Radians).unwrap();
+    assert_eq!(steps.segments.len(), 6);
+    assert!(steps.segments.iter().all(|s| s.iter().all(|p| p.y == s[0].y)));
+
+    assert_eq!(sample("abs(x) + x^2", -3.0, 3.0, AngleMode::Radians).unwrap().segments.len(), 1);
+    assert!(sample("sqrt(-1 - x^2)", -3.0, 3.0, AngleMode::Radians).unwrap().segments.is_empty());
+}
+
+#[test]
+fn test_sampling_errors() {
+    assert!(sample("x + y", 0.0, 1.0, AngleMode::Radians).is_err());
+    assert!(sample("x", 1.0, 1.0, AngleMode::Radians).is_err());
+    assert!(sample("x", 0.0, f64::INFINITY, AngleMode::Radians).is_err());
+}
+
+// === TESTS FOR PARAMETRIC AND POLAR CURVES ===
+
+#[test]
+fn test_parametric_and_polar_curves() {
+    use matheval::{compile::CompiledExpr, plot::{sample_parametric, sample_polar, SampleOptions}};
+
+    let mut evaluator = Evaluator::new();
+    let mut compile = |input: &str| {
+        let ast = Parser::new(Lexer::new(input).tokenize().unwrap()).parse().unwrap();
+        CompiledExpr::new(&ast, &["t"], &mut evaluator).unwrap()
+    };
+    // A Lissajous figure, in the default degree mode.
+    let (x, y) = (compile("sin(3*t)"), compile("cos(2*t)"));
+    // A four-petal rose.
+    let rose = compile("cos(2*t)");
+
+    let options = SampleOptions::default();
+    let figure = sample_parametric(&x, &y, &mut evaluator, 0.0, 360.0, &options).unwrap();
+    assert_eq!(figure.segments.len(), 1);
+    assert!(figure.points().all(|p| p.x.abs() <= 1.0 && p.y.abs() <= 1.0));
+    assert!(figure.points().any(|p| p.x > 0.999) && figure.points().any(|p| p.x < -0.999));
+
+    let petals = sample_polar(&rose, &mut evaluator, 0.0, 360.0, &options).unwrap();
+    assert_eq!(petals.segments.len(), 1);
+    assert!(petals.points().all(|p| p.x.hypot(p.y) <= 1.0 + 1e-12));
+    // Petal tips lie on both axes.
+    for (x, y) in [(1.0, 0.0), (0.0, 1.0), (-1.0, 0.0), (0.0, -1.0)] {
+        assert!(petals.points().any(|p| (p.x - x).abs() < 1e-3 && (p.y - y).abs() < 1e-3), "({}, {})", x, y);
+    }
+    assert!(sample_polar(&rose, &mut evaluator, 360.0, 0.0, &options).is_err());
+}
+
+#[test]
+fn test_plot_param_and_plot_polar() {
+    use matheval::{braille::{render, TerminalOptions}, chart::View, value::Value};
+
+    let mut evaluator = Evaluator::new();
+    let mut table = |input: &str| {
+        let ast = Parser::new(Lexer::new(input).tokenize().unwrap()).parse().unwrap();
+        match evaluator.eval_value(&ast) {
+            Ok(Value::Table(table)) => Ok(table),
+            other => Err(format!("{:?}", other)),
+        }
+    };
+
+    // The unit circle, in the default degree mode, as rows of x and y.
+    let circle = table("plot_param(cos(t), sin(t), t, 0, 360)").unwrap();
+    assert_eq!(circle.columns, ["x", "y"]);
+    assert!(circle.rows.len() > 100);
+    assert!(circle.rows.iter().all(|row| (row[0].hypot(row[1]) - 1.0).abs() < 1e-12));
+    assert_eq!(circle.rows[0], [1.0, 0.0]);
+
+    // A cardioid reaches 2 at θ = 0 and passes through the origin at 180.
+    let cardioid = table("plot_polar(1 + cos(θ), θ, 0, 360)").unwrap();
+    assert_eq!(cardioid.rows[0], [2.0, 0.0]);
+    assert!(cardioid.rows.iter().all(|row| row[0].hypot(row[1]) <= 2.0 + 1e-12));
+    assert!(cardioid.rows.iter().any(|row| row[0].hypot(row[1]) < 1e-3));
+
+    // The pole at t = 0 leaves an undefined row, which breaks the plotted curve.
+    let hyperbola = table("plot_param(t, 1/t, t, -1, 1)").unwrap();
+    assert_eq!(hyperbola.rows.iter().filter(|row| row[0].is_nan()).count(), 1);
+    let series = hyperbola.to_series();
+    assert_eq!(series.len(), 1);
+    assert_eq!(series[0].curve.segments.len(), 2);
+
+    let series = circle.to_series();
+    let text = render(&series, View::fit(&series).unwrap(), &TerminalOptions { width: 40, height: 12, color: false });
+    let lines: Vec<&str> = text.lines().collect();
+    // The circle spans the chart from the top row to the bottom one.
+    assert!(lines[0].starts_with(" 1┤") && lines[8].starts_with("-1┤"), "{}", text);
+    assert!(lines[0].contains('⣀') && lines[8].contains('⠉'), "{}", text);
+
+    assert!(table("plot_param(t, t, t, 1, 0)").unwrap_err().contains("from < to"));
+    assert!(table("plot_param(t, t, t, 0)").unwrap_err().contains("expects 5 argument(s)"));
+    assert!(table("plot_polar(1, 2, 0, 1)").unwrap_err().contains("expects a variable name"));
+    assert!(table("plot_polar(r + θ, θ, 0, 1)").is_err());
+}
+
+// === TESTS FOR IMPLICIT CURVES ===
+
+#[test]
+fn test_implicit_curves() {
+    use matheval::{compile::CompiledExpr, implicit::{residual, trace_implicit, ImplicitOptions}};
+
+    let mut evaluator = Evaluator::new();
+    let mut trace = |input: &str, x: (f64, f64), y: (f64, f64)| {
+        let equation = Parser::new(Lexer::new(input).tokenize().unwrap()).parse_equation().unwrap();
+        let f = CompiledExpr::new(&residual(&equation), &["x", "y"], &mut evaluator).unwrap();
+        trace_implicit(&f, &mut evaluator, x, y, &ImplicitOptions::default()).unwrap()
+    };
+
+    // An ellipse, and the same ellipse written with everything on one side.
+    let ellipse = trace("x^2/16 + y^2/4 = 1", (-5.0, 5.0), (-3.0, 3.0));
+    assert_eq!(ellipse.segments.len(), 1);
+    assert!(ellipse.points().all(|p| (p.x * p.x / 16.0 + p.y * p.y / 4.0 - 1.0).abs() < 1e-3));
+    assert_eq!(trace("x^2/16 + y^2/4 - 1", (-5.0, 5.0), (-3.0, 3.0)), ellipse);
+
+    // sin(x*y) = 0.5 in degrees: hyperbola-like branches where x*y = 30 or 150.
+    let curve = trace("sin(x*y) = 0.5", (1.0, 20.0), (1.0, 20.0));
+    assert!(curve.segments.len() >= 2);
+    assert!(curve.points().all(|p| {
+        let xy = p.x * p.y;
+        [30.0, 150.0, 390.0].iter().any(|target| (xy - target).abs() < 0.5)
+    }));
+
+    // Percentages on the right keep their own value.
+    let level = trace("y = 50%", (-1.0, 1.0), (-1.0, 1.0));
+    assert!(level.points().all(|p| (p.y - 0.5).abs() < 1e-9));
+
+    let empty = trace("x^2 + y^2 = -1", (-2.0, 2.0), (-2.0, 2.0));
+    assert!(empty.segments.is_empty());
+}
+
+// === TESTS FOR SVG CHARTS ===
+
+#[test]
+fn test_svg_chart_of_sampled_functions() {
+    use matheval::{chart::Series, svg::{render, SvgOptions}};
+
+    let series: Vec<Series> = ["tan(x)", "x / 45"].iter()
+        .map(|&input| Series { label: input.to_string(), curve: sample(input, 0.0, 360.0, AngleMode::Degrees).unwrap() })
+        .collect();
+    let svg = render(&series, &SvgOptions::default());
+
+    // The poles neither stretch the vertical axis nor get bridged.
+    assert!(svg.matches("<polyline").count() >= 4);
+    let coordinates: Vec<f64> = svg.lines()
+        .filter(|line| line.starts_with("<polyline"))
+        .flat_map(|line| line.split('"').nth(1).unwrap().split([' ', ',']).map(|n| n.parse::<f64>().unwrap()).collect::<Vec<_>>())
+        .collect();
+    assert!(coordinates.iter().all(|&c| (0.0..=800.0).contains(&c)));
+    assert!(svg.contains(">tan(x)</text>") && svg.contains(">x / 45</text>"));
+    // Tick labels for the horizontal axis at round numbers of degrees.
+    assert!(svg.contains(">100</text>") && svg.contains(">300</text>"));
+}
+
+#[test]
+fn test_plot_command_line() {
+    let plot = |args: &[&str]| std::process::Command::new(env!("CARGO_BIN_EXE_matheval-cli"))
+        .arg("plot")
+        .args(args)
+        .current_dir(std::env::temp_dir())
+        .output()
+        .unwrap();
+
+    // Radians by default: a full period of sin over ±2π reaches both 1 and -1.
+    let output = plot(&["sin(x)", "--from", "-6.3", "--to", "6.3"]);
+    assert!(output.status.success());
+    let svg = String::from_utf8(output.stdout).unwrap();
+    assert!(svg.contains(">1.0</text>") && svg.contains(">-1.0</text>"), "{}", svg);
+
+    let output = plot(&["sqrt(-1 - x^2)"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.starts_with("Error: sqrt(-1 - x^2): nothing to draw"), "{}", error);
+}
+
+// === TESTS FOR TERMINAL PLOTS ===
+
+#[test]
+fn test_terminal_plot_of_sampled_functions() {
+    use matheval::{braille::{render, TerminalOptions}, chart::{Series, View}};
+
+    let series = vec![Series { label: "tan(x)".to_string(), curve: sample("tan(x)", 0.0, 360.0, AngleMode::Degrees).unwrap() }];
+    let view = View::fit(&series).unwrap();
+    // The poles don't stretch the vertical axis far beyond the bulk of the graph.
+    assert!(view.y.1 > 2.0 && view.y.1 < 20.0, "{:?}", view);
+
+    let text = render(&series, view, &TerminalOptions { width: 60, height: 20, color: false });
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), 20);
+    assert!(lines.iter().all(|line| line.chars().count() <= 60));
+    assert!(lines[18].contains("0") && lines[18].contains("300"));
+    assert!(lines[19].ends_with("━━ tan(x)"));
+}
+
+// === TESTS FOR TABLES OF VALUES ===
+
+#[test]
+fn test_table_of_values() {
+    let table = eval_session(&["table(x^2 + 1, x, 0, 10, 0.5)"]).unwrap();
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 23);
+    assert_eq!(lines[0], "  x │     y");
+    assert_eq!(lines[2], "  0 │     1");
+    assert_eq!(lines[3], "0.5 │  1.25");
+    assert_eq!(lines[22], " 10 │   101");
+
+    // The step defaults to 1, and the expression can use session functions
+    // and variables but doesn't see or change an `x` of the session.
+    let table = eval_session(&["x = 100", "k = 3", "f(t) = k * t", "table(f(x), x, 1, 3)"]).unwrap();
+    assert_eq!(table, "x │ y\n──┼──\n1 │ 3\n2 │ 6\n3 │ 9");
+    assert_eq!(eval_session(&["x = 100", "table(x, x, 1, 3)", "x"]).unwrap(), "100");
+
+    assert!(eval_session(&["table(x, 2, 0, 1)"]).is_err());
+    assert!(eval_session(&["table(x + y, x, 0, 1)"]).is_err());
+    assert!(eval_session(&["table(x, x, 0, 1, 0)"]).is_err());
+    assert!(eval_session(&["table(x, x, 0)"]).is_err());
+    let error = eval_session(&["table(x, x, 0, 1) + 1"]).unwrap_err();
+    assert!(error.contains("tables cannot be used in arithmetic"), "{}", error);
+    assert!(eval_session(&["2 * table(x, x, 0, 1)"]).unwrap_err().contains("tables cannot be used in arithmetic"));
+}
+
+// === TESTS FOR FUNCTION ANALYSIS ===
+
+#[test]
+fn test_analyze_and_intersect() {
+    // Degrees: sin has its maximum at 90, a root and an inflection at 180.
+    let table = eval_session(&["analyze(sin(x), x, 10, 350)"]).unwrap();
+    assert_eq!(table, [
+        "           │   x │  y",
+        "───────────┼─────┼───",
+        "maximum    │  90 │  1",
+        "root       │ 180 │  0",
+        "inflection │ 180 │  0",
+        "minimum    │ 270 │ -1",
+    ].join("\n"));
+
+    // A function of the session can be passed by name.
+    let table = eval_session(&["f(t) = t^2 - 4", "analyze(f, x, -3, 3)"]).unwrap();
+    let labels: Vec<&str> = table.lines().skip(2).map(|line| line.split(" │").next().unwrap().trim()).collect();
+    assert_eq!(labels, vec!["root", "minimum", "y-intercept", "root"]);
+
+    let table = eval_session(&["intersect(x^2, x + 2, x, -5, 5)"]).unwrap();
+    assert!(table.ends_with("intersection │ -1 │ 1\nintersection │  2 │ 4"), "{}", table);
+
+    assert!(eval_session(&["analyze(x, 1, 0, 1)"]).is_err());
+    assert!(eval_session(&["analyze(x, x, 1, 0)"]).is_err());
+    assert!(eval_session(&["intersect(x, x, 0, 1)"]).is_err());
+}
+
+// === TESTS FOR OPTIMIZATION ===
+
+#[test]
+fn test_minimize_and_maximize() {
+    let table = eval_session(&["minimize((1 - x)^2 + 100*(y - x^2)^2, [x, y], [-1.2, 1])"]).unwrap();
+    assert_eq!(table, "x │ y │ minimum\n──┼───┼────────\n1 │ 1 │       0");
+
+    // One variable, and a function of the session by name.
+    let table = eval_session(&["maximize(x*exp(-x), x, 5)"]).unwrap();
+    assert!(table.ends_with("\n1 │ 0.36787944117144233"), "{}", table);
+    let table = eval_session(&["f(a, b) = (a - 3)^2 + (b + 1)^2 + 2", "minimize(f, [a, b], [0, 0])"]).unwrap();
+    assert!(table.ends_with("\n3 │ -1 │       2"), "{}", table);
+
+    // Bounds keep the minimum of x^2 + y^2 at the corner nearest the origin.
+    let table = eval_session(&["minimize(x^2 + y^2, [x, y], [5, 5], [1, 2], [10, 10])"]).unwrap();
+    assert!(table.ends_with("\n1 │ 2 │       5"), "{}", table);
+
+    let error = eval_session(&["minimize(x^3, x, 0)"]).unwrap_err();
+    assert!(error.contains("decreases without bound"), "{}", error);
+    assert!(eval_session(&["minimize(x^2 + y^2, [x, y], [1])"]).is_err());
+    assert!(eval_session(&["minimize(x^2, x, 1, 2)"]).is_err());
+    assert!(eval_session(&["minimize(x^2, [x, 2], [1, 1])"]).is_err());
+}
+
+// === TESTS FOR DIFFERENTIAL EQUATIONS ===
+
+/// The numbers in the last row of a table.
+fn last_row(table: &str) -> Vec<f64> {
+    table.lines().last().unwrap().split('│').map(|cell| cell.trim().parse().unwrap()).collect()
+}
+
+#[test]
+fn test_odesolve() {
+    // Decay with a rate from the session, sampled every unit of time.
+    let table = eval_session(&["k = 0.5", "odesolve(y' = -k*y, y, t, 0, 4, 2, 1)"]).unwrap();
+    assert!(table.starts_with("t │"), "{}", table);
+    assert_eq!(table.lines().count(), 7);
+    let row = last_row(&table);
+    assert_eq!(row[0], 4.0);
+    assert!((row[1] - 2.0 * (-2.0f64).exp()).abs() < 1e-8, "{}", table);
+
+    // A system, sampled ten times by default: x = cos(t), v = -sin(t).
+    let table = eval_session(&["odesolve([x' = v, v' = -x], [x, v], t, 0, 2*PI, [1, 0])"]).unwrap();
+    assert_eq!(table.lines().count(), 13);
+    let row = last_row(&table);
+    assert!((row[1] - 1.0).abs() < 1e-7 && row[2].abs() < 1e-7, "{}", table);
+
+    // The last row is at the end of the interval even between steps.
+    let table = eval_session(&["odesolve(y' = t, y, t, 0, 1, 0, 0.3)"]).unwrap();
+    assert_eq!(table.lines().count(), 7);
+    assert_eq!(last_row(&table), vec![1.0, 0.5]);
+
+    // Stiff: the solver switches methods rather than crawling.
+    let table = eval_session(&["odesolve(y' = -100000*(y - t), y, t, 0, 1, 0, 0.25)"]).unwrap();
+    assert!((last_row(&table)[1] - 0.99999).abs() < 1e-6, "{}", table);
+
+    let error = eval_session(&["odesolve(y' = y^2, y, t, 0, 2, 1)"]).unwrap_err();
+    assert!(error.contains("blow up"), "{}", error);
+    assert!(eval_session(&["odesolve(z' = 1, y, t, 0, 1, 0)"]).is_err());
+    assert!(eval_session(&["odesolve([x' = 1, y' = 1], [x, y], t, 0, 1, 0)"]).is_err());
+    assert!(eval_session(&["odesolve(y' = 1, y, 2, 0, 1, 0)"]).is_err());
+    let error = eval_session(&["odesolve(y' = -y, y, t, 0, 0, 1)"]).unwrap_err();
+    assert!(error.contains("cannot solve from 0 to 0: the end must differ from the start"), "{}", error);
+    assert!(eval_session(&["y' = 3"]).unwrap_err().contains("stands for a derivative"));
+}
+
+// === TESTS FOR TAYLOR SERIES ===
+
+#[test]
+fn test_taylor() {
+    assert_eq!(eval_session(&["taylor(exp(x), x, 0, 3)"]).unwrap(), "1 + x + x ^ 2 / 2 + x ^ 3 / 6");
+    assert_eq!(eval_session(&["taylor(sin(x), x, 0, 1)"]).unwrap(), "0.0174532925199433 * x");
+    assert_eq!(eval_session(&["f(t) = t^3", "a = 2", "taylor(f(x), x, a, 1)"]).unwrap(), "8 + 12 * (x - 2)");
+
+    // A polynomial defined from one can be evaluated like any other function.
+    let value: f64 = eval_session(&["p(x) = taylor(exp(x), x, 0, 10)", "p(0.5)"]).unwrap().parse().unwrap();
+    assert!((value - 0.5f64.exp()).abs() < 1e-9, "{}", value);
+    let table = eval_session(&["p(x) = taylor(1/(1 - x), x, 0, 4)", "table(p(x), x, 0, 1, 0.5)"]).unwrap();
+    assert!(table.contains("1.9375"), "{}", table);
+
+    let error = eval_session(&["taylor(ln(x), x, 0, 2)"]).unwrap_err();
+    assert!(error.contains("no Taylor expansion"), "{}", error);
+    let error = eval_session(&["taylor(floor(x), x, 0, 2)"]).unwrap_err();
+    assert!(error.contains("cannot differentiate floor"), "{}", error);
+    assert!(eval_session(&["taylor(x^2, x, 0, 1.5)"]).is_err());
+    let error = eval_session(&["p = taylor(exp(x), x, 0, 2)", "p + 1"]).unwrap_err();
+    assert!(error.contains("cannot be used in arithmetic; define a function"), "{}", error);
+    let error = eval_session(&["taylor(2*x, x, 0, 2^70)"]).unwrap_err();
+    assert!(error.contains("from 0 to 20"), "{}", error);
+    // Deep derivatives evaluate like any other expression.
+    assert!(eval_session(&["taylor(atan(x)/(1 + x^2)^3*exp(x), x, 0, 19)"]).is_ok());
+    assert!(eval_session(&["taylor(x^2, 2, 0, 1)"]).is_err());
+    assert!(eval_session(&["taylor(x^2, x, 0)"]).is_err());
+}